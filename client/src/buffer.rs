@@ -1,43 +1,297 @@
+//! Durable queue of readings waiting to be uploaded.
+//!
+//! Readings are appended as JSON lines to numbered segment files in the buffer directory and
+//! fsynced before the write returns. An `ack` file records the first reading the server has
+//! not yet confirmed; it is replaced atomically after each successful upload, so a crash
+//! at any point at worst re-sends readings, which the server ignores by id. Segments that
+//! have been fully acknowledged are deleted.
+
 use std::collections::VecDeque;
-use std::time::Duration;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use shared::TiltReading;
 
+/// Readings per segment file before a new one is started. Size limits drop whole segments.
+const SEGMENT_READINGS: usize = 1000;
+const SEGMENT_EXTENSION: &str = "jsonl";
+const ACK_FILE: &str = "ack";
+
+#[derive(Debug, Clone, Copy)]
+pub struct BufferLimits {
+    /// Readings kept before the oldest segments are dropped.
+    pub max_readings: usize,
+    /// Readings older than this are dropped instead of uploaded.
+    pub max_age: Duration,
+}
+
+/// A reading's place in the queue: segment sequence number and line within it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Position {
+    segment: u64,
+    line: usize,
+}
+
+/// Readings read from the queue but not yet acknowledged.
+pub struct Chunk {
+    pub readings: Vec<TiltReading>,
+    /// Where the queue resumes once this chunk is acknowledged.
+    end: Position,
+    /// Lines consumed, including unreadable or expired ones that were skipped.
+    consumed: usize,
+    /// The chunk reaches the end of the queue.
+    last: bool,
+}
+
+struct Writer {
+    segment: u64,
+    file: File,
+    lines: usize,
+}
+
 pub struct ReadingBuffer {
-    buffer: VecDeque<TiltReading>,
-    capacity: usize,
+    dir: PathBuf,
+    limits: BufferLimits,
+    /// Segment sequence numbers on disk, oldest first.
+    segments: VecDeque<u64>,
+    ack: Position,
+    /// Segment being appended to. Each run starts a fresh one, so a line torn by a crash
+    /// is never followed by new data in the same file.
+    writer: Option<Writer>,
+    len: usize,
 }
 
 impl ReadingBuffer {
-    pub fn new(capacity: usize) -> Self {
-        Self {
-            buffer: VecDeque::with_capacity(capacity),
-            capacity,
+    pub fn open(dir: impl Into<PathBuf>, limits: BufferLimits) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut segments: Vec<u64> = fs::read_dir(&dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != SEGMENT_EXTENSION {
+                    return None;
+                }
+                path.file_stem()?.to_str()?.parse().ok()
+            })
+            .collect();
+        segments.sort_unstable();
+
+        let ack = read_ack(&dir.join(ACK_FILE))?.unwrap_or(Position {
+            segment: segments.first().copied().unwrap_or_default(),
+            line: 0,
+        });
+        let mut buffer = Self {
+            dir,
+            limits,
+            segments: segments.into(),
+            ack,
+            writer: None,
+            len: 0,
+        };
+        buffer.remove_acknowledged_segments()?;
+        for &segment in &buffer.segments {
+            let lines = count_lines(&buffer.segment_path(segment))?;
+            buffer.len += if segment == ack.segment {
+                lines.saturating_sub(ack.line)
+            } else {
+                lines
+            };
         }
+        Ok(buffer)
     }
 
-    pub fn push_batch(&mut self, readings: &[TiltReading]) {
+    /// Appends readings and syncs them to disk, then enforces the size and age limits.
+    pub fn push_batch(&mut self, readings: &[TiltReading]) -> io::Result<()> {
         for reading in readings {
-            if self.buffer.len() >= self.capacity {
-                self.buffer.pop_front();
+            let line = serde_json::to_string(reading)?;
+            let writer = self.writer()?;
+            writeln!(writer.file, "{line}")?;
+            writer.lines += 1;
+            self.len += 1;
+        }
+        if let Some(writer) = &mut self.writer {
+            writer.file.sync_data()?;
+        }
+        self.enforce_limits()
+    }
+
+    /// Up to `max` of the oldest unacknowledged readings. Reading again without an
+    /// [`ReadingBuffer::ack`] returns the same chunk.
+    pub fn next_chunk(&self, max: usize) -> io::Result<Chunk> {
+        let cutoff = SystemTime::now() - self.limits.max_age;
+        let mut chunk = Chunk {
+            readings: Vec::new(),
+            end: self.ack,
+            consumed: 0,
+            last: false,
+        };
+
+        for &segment in self.segments.iter().filter(|s| **s >= self.ack.segment) {
+            let skip = if segment == self.ack.segment {
+                self.ack.line
+            } else {
+                0
+            };
+            chunk.end = Position {
+                segment,
+                line: skip,
+            };
+            let file = BufReader::new(File::open(self.segment_path(segment))?);
+            for line in file.lines().skip(skip) {
+                if chunk.readings.len() >= max {
+                    return Ok(chunk);
+                }
+                let line = line?;
+                chunk.end.line += 1;
+                chunk.consumed += 1;
+                match serde_json::from_str::<TiltReading>(&line) {
+                    Ok(reading) if SystemTime::from(reading.recorded_at) >= cutoff => {
+                        chunk.readings.push(reading)
+                    }
+                    Ok(_) => tracing::debug!("Dropping buffered reading older than the age limit"),
+                    Err(e) => tracing::warn!(segment, "Skipping unreadable buffered reading: {e}"),
+                }
             }
-            self.buffer.push_back(reading.clone());
         }
+        chunk.last = true;
+        Ok(chunk)
+    }
+
+    /// Records that the server has `chunk`, so it is never replayed.
+    pub fn ack(&mut self, chunk: &Chunk) -> io::Result<()> {
+        self.ack = chunk.end;
+        self.len = if chunk.last {
+            0
+        } else {
+            self.len.saturating_sub(chunk.consumed)
+        };
+        write_ack(&self.dir, self.ack)?;
+        self.remove_acknowledged_segments()
     }
 
-    pub fn drain_all(&mut self) -> Vec<TiltReading> {
-        self.buffer.drain(..).collect()
+    /// Applies new limits; they take effect on the next push.
+    pub fn set_limits(&mut self, limits: BufferLimits) {
+        self.limits = limits;
     }
 
     pub fn len(&self) -> usize {
-        self.buffer.len()
+        self.len
     }
 
     pub fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.len == 0
+    }
+
+    fn segment_path(&self, segment: u64) -> PathBuf {
+        self.dir.join(format!("{segment:020}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn writer(&mut self) -> io::Result<&mut Writer> {
+        if self
+            .writer
+            .as_ref()
+            .is_none_or(|w| w.lines >= SEGMENT_READINGS)
+        {
+            let segment = self.segments.back().map_or(self.ack.segment, |s| s + 1);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.segment_path(segment))?;
+            self.segments.push_back(segment);
+            self.writer = Some(Writer {
+                segment,
+                file,
+                lines: 0,
+            });
+        }
+        Ok(self.writer.as_mut().expect("writer was just opened"))
+    }
+
+    fn remove_acknowledged_segments(&mut self) -> io::Result<()> {
+        while let Some(&oldest) = self.segments.front()
+            && oldest < self.ack.segment
+        {
+            fs::remove_file(self.segment_path(oldest))?;
+            self.segments.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Drops the oldest segments while over `max_readings` or while they were last written
+    /// before `max_age`. The segment being written is never dropped.
+    fn enforce_limits(&mut self) -> io::Result<()> {
+        let active = self.writer.as_ref().map(|w| w.segment);
+        while let Some(&oldest) = self.segments.front()
+            && Some(oldest) != active
+        {
+            let path = self.segment_path(oldest);
+            let expired = fs::metadata(&path)?
+                .modified()?
+                .elapsed()
+                .is_ok_and(|age| age > self.limits.max_age);
+            if self.len <= self.limits.max_readings && !expired {
+                break;
+            }
+            let lines = count_lines(&path)?;
+            let unacked = if oldest == self.ack.segment {
+                lines.saturating_sub(self.ack.line)
+            } else {
+                lines
+            };
+            tracing::warn!(
+                dropped = unacked,
+                expired,
+                "Buffer limit reached, dropping oldest readings"
+            );
+            self.len = self.len.saturating_sub(unacked);
+            self.ack = Position {
+                segment: oldest + 1,
+                line: 0,
+            };
+            write_ack(&self.dir, self.ack)?;
+            self.remove_acknowledged_segments()?;
+        }
+        Ok(())
+    }
+}
+
+fn count_lines(path: &Path) -> io::Result<usize> {
+    Ok(BufReader::new(File::open(path)?).lines().count())
+}
+
+fn read_ack(path: &Path) -> io::Result<Option<Position>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut parts = text.split_whitespace().map(str::parse::<u64>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(segment)), Some(Ok(line))) => Ok(Some(Position {
+            segment,
+            line: line as usize,
+        })),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("corrupt ack file {}", path.display()),
+        )),
     }
 }
 
+/// Writes to a temporary file and renames it over `ack`, so a crash leaves either the old
+/// or the new position, never a partial one.
+fn write_ack(dir: &Path, position: Position) -> io::Result<()> {
+    let tmp = dir.join(format!("{ACK_FILE}.tmp"));
+    let mut file = File::create(&tmp)?;
+    writeln!(file, "{} {}", position.segment, position.line)?;
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(ACK_FILE))?;
+    File::open(dir)?.sync_all()
+}
+
 pub struct Backoff {
     current: Duration,
     initial: Duration,
@@ -82,37 +336,125 @@ mod tests {
         TiltReading::new(TiltColor::Red, temp, 1.050, None, Utc::now())
     }
 
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("tilt-buffer-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn limits() -> BufferLimits {
+        BufferLimits {
+            max_readings: 100_000,
+            max_age: Duration::from_secs(86_400),
+        }
+    }
+
+    fn temps(chunk: &Chunk) -> Vec<f64> {
+        chunk.readings.iter().map(|r| r.temperature_f).collect()
+    }
+
     #[test]
     fn buffer_new_is_empty() {
-        let buf = ReadingBuffer::new(10);
+        let dir = temp_dir();
+        let buf = ReadingBuffer::open(&dir, limits()).unwrap();
         assert!(buf.is_empty());
         assert_eq!(buf.len(), 0);
+        assert!(buf.next_chunk(10).unwrap().readings.is_empty());
+        fs::remove_dir_all(dir).unwrap();
     }
 
     #[test]
-    fn buffer_push_and_drain() {
-        let mut buf = ReadingBuffer::new(10);
-        buf.push_batch(&[make_reading(68.0), make_reading(69.0)]);
-        assert_eq!(buf.len(), 2);
+    fn buffer_chunks_in_order_and_acks() {
+        let dir = temp_dir();
+        let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+        buf.push_batch(&[make_reading(1.0), make_reading(2.0), make_reading(3.0)])
+            .unwrap();
+        assert_eq!(buf.len(), 3);
 
-        let drained = buf.drain_all();
-        assert_eq!(drained.len(), 2);
+        let chunk = buf.next_chunk(2).unwrap();
+        assert_eq!(temps(&chunk), vec![1.0, 2.0]);
+        // Unacknowledged chunks are handed out again.
+        assert_eq!(temps(&buf.next_chunk(2).unwrap()), vec![1.0, 2.0]);
+
+        buf.ack(&chunk).unwrap();
+        assert_eq!(buf.len(), 1);
+        let chunk = buf.next_chunk(2).unwrap();
+        assert_eq!(temps(&chunk), vec![3.0]);
+        buf.ack(&chunk).unwrap();
         assert!(buf.is_empty());
+        fs::remove_dir_all(dir).unwrap();
     }
 
     #[test]
-    fn buffer_drops_oldest_when_full() {
-        let mut buf = ReadingBuffer::new(3);
-        buf.push_batch(&[make_reading(1.0), make_reading(2.0), make_reading(3.0)]);
-        assert_eq!(buf.len(), 3);
+    fn buffer_survives_reopen_and_replays_unacked() {
+        let dir = temp_dir();
+        {
+            let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+            buf.push_batch(&[make_reading(1.0), make_reading(2.0)])
+                .unwrap();
+            let chunk = buf.next_chunk(1).unwrap();
+            buf.ack(&chunk).unwrap();
+            // Read but never acknowledged, as if the upload timed out before a crash.
+            buf.next_chunk(1).unwrap();
+        }
+        let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+        assert_eq!(buf.len(), 1);
+        buf.push_batch(&[make_reading(3.0)]).unwrap();
+        assert_eq!(temps(&buf.next_chunk(10).unwrap()), vec![2.0, 3.0]);
+        fs::remove_dir_all(dir).unwrap();
+    }
 
-        buf.push_batch(&[make_reading(4.0), make_reading(5.0)]);
-        assert_eq!(buf.len(), 3);
+    #[test]
+    fn buffer_skips_torn_lines() {
+        let dir = temp_dir();
+        {
+            let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+            buf.push_batch(&[make_reading(1.0)]).unwrap();
+            let segment = buf.segment_path(buf.segments[0]);
+            let mut file = OpenOptions::new().append(true).open(segment).unwrap();
+            write!(file, "{{\"color\":\"Re").unwrap();
+        }
+        let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+        buf.push_batch(&[make_reading(2.0)]).unwrap();
+        let chunk = buf.next_chunk(10).unwrap();
+        assert_eq!(temps(&chunk), vec![1.0, 2.0]);
+        buf.ack(&chunk).unwrap();
+        assert!(buf.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn buffer_drops_oldest_segments_when_full() {
+        let dir = temp_dir();
+        let limits = BufferLimits {
+            max_readings: SEGMENT_READINGS,
+            ..limits()
+        };
+        let mut buf = ReadingBuffer::open(&dir, limits).unwrap();
+        let batch: Vec<TiltReading> = (0..SEGMENT_READINGS)
+            .map(|i| make_reading(i as f64))
+            .collect();
+        buf.push_batch(&batch).unwrap();
+        buf.push_batch(&[make_reading(-1.0)]).unwrap();
 
-        let drained = buf.drain_all();
-        assert!((drained[0].temperature_f - 3.0).abs() < f64::EPSILON);
-        assert!((drained[1].temperature_f - 4.0).abs() < f64::EPSILON);
-        assert!((drained[2].temperature_f - 5.0).abs() < f64::EPSILON);
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf.segments.len(), 1);
+        assert_eq!(temps(&buf.next_chunk(10).unwrap()), vec![-1.0]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn buffer_skips_readings_past_max_age() {
+        let dir = temp_dir();
+        let mut buf = ReadingBuffer::open(&dir, limits()).unwrap();
+        let old = TiltReading {
+            recorded_at: Utc::now() - chrono::Duration::days(2),
+            ..make_reading(1.0)
+        };
+        buf.push_batch(&[old, make_reading(2.0)]).unwrap();
+        let chunk = buf.next_chunk(10).unwrap();
+        assert_eq!(temps(&chunk), vec![2.0]);
+        buf.ack(&chunk).unwrap();
+        assert!(buf.is_empty());
+        fs::remove_dir_all(dir).unwrap();
     }
 
     #[test]
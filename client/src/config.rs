@@ -0,0 +1,508 @@
+//! Client settings merged from command-line flags, environment variables and an optional
+//! TOML file, in that order of precedence. The file is re-read on SIGHUP; flags and
+//! environment variables keep overriding it across reloads.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use serde::Deserialize;
+use shared::TiltColor;
+
+use crate::buffer::BufferLimits;
+
+const DEFAULT_SCAN_INTERVAL: u64 = 5;
+const DEFAULT_UPLOAD_INTERVAL: u64 = 60;
+const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_BUFFER_DIR: &str = "tilt-buffer";
+const DEFAULT_BUFFER_SIZE: usize = 100_000;
+const DEFAULT_BUFFER_MAX_AGE_HOURS: u64 = 720;
+const DEFAULT_UPLOAD_CHUNK_SIZE: usize = 500;
+const DEFAULT_HEARTBEAT_INTERVAL: u64 = 60;
+
+/// Name of the target built from `server_url` and `api_key`. Its buffer lives directly in
+/// `buffer_dir`; other targets get a subdirectory of `buffer_dir/targets`.
+pub const PRIMARY_TARGET: &str = "server";
+
+/// Settings that can come from a flag, an environment variable or the config file.
+#[derive(clap::Args, Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Options {
+    #[arg(
+        long,
+        env = "TILT_SERVER_URL",
+        help = "Server URL to upload readings to"
+    )]
+    pub server_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "TILT_API_KEY",
+        hide_env_values = true,
+        help = "API key for authenticating with the server (X-API-Key header)"
+    )]
+    pub api_key: Option<String>,
+
+    #[arg(
+        long,
+        env = "TILT_SCAN_INTERVAL",
+        help = "BLE scan window in seconds (how long to listen per cycle) [default: 5]"
+    )]
+    pub scan_interval: Option<u64>,
+
+    #[arg(
+        long,
+        env = "TILT_UPLOAD_INTERVAL",
+        help = "Minimum seconds between uploads per hydrometer color [default: 60]"
+    )]
+    pub upload_interval: Option<u64>,
+
+    #[arg(
+        long,
+        env = "TILT_LOG_LEVEL",
+        help = "Log level (trace, debug, info, warn, error) [default: info]"
+    )]
+    pub log_level: Option<String>,
+
+    #[arg(
+        long,
+        env = "TILT_BUFFER_DIR",
+        help = "Directory for the on-disk queue of readings awaiting upload [default: tilt-buffer]"
+    )]
+    pub buffer_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "TILT_BUFFER_SIZE",
+        help = "Maximum number of readings to buffer locally; the oldest are dropped first \
+                [default: 100000]"
+    )]
+    pub buffer_size: Option<usize>,
+
+    #[arg(
+        long,
+        env = "TILT_BUFFER_MAX_AGE_HOURS",
+        help = "Drop buffered readings older than this many hours instead of uploading them \
+                [default: 720]"
+    )]
+    pub buffer_max_age_hours: Option<u64>,
+
+    #[arg(
+        long,
+        env = "TILT_UPLOAD_CHUNK_SIZE",
+        help = "Maximum readings per upload request when replaying the buffer [default: 500]"
+    )]
+    pub upload_chunk_size: Option<usize>,
+
+    #[arg(
+        long,
+        env = "TILT_HEARTBEAT_INTERVAL",
+        help = "Seconds between status heartbeats to the server; 0 disables them [default: 60]"
+    )]
+    pub heartbeat_interval: Option<u64>,
+
+    #[arg(
+        long,
+        env = "TILT_STATUS_LISTEN",
+        help = "Address for the local /healthz, /status and /metrics HTTP server, e.g. \
+                127.0.0.1:9101; disabled when unset"
+    )]
+    pub status_listen: Option<SocketAddr>,
+}
+
+impl Options {
+    /// Fields set in `self`, falling back to `other`.
+    fn or(self, other: Options) -> Options {
+        Options {
+            server_url: self.server_url.or(other.server_url),
+            api_key: self.api_key.or(other.api_key),
+            scan_interval: self.scan_interval.or(other.scan_interval),
+            upload_interval: self.upload_interval.or(other.upload_interval),
+            log_level: self.log_level.or(other.log_level),
+            buffer_dir: self.buffer_dir.or(other.buffer_dir),
+            buffer_size: self.buffer_size.or(other.buffer_size),
+            buffer_max_age_hours: self.buffer_max_age_hours.or(other.buffer_max_age_hours),
+            upload_chunk_size: self.upload_chunk_size.or(other.upload_chunk_size),
+            heartbeat_interval: self.heartbeat_interval.or(other.heartbeat_interval),
+            status_listen: self.status_listen.or(other.status_listen),
+        }
+    }
+}
+
+/// A `[colors.<Color>]` table in the config file.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ColorSettings {
+    /// Readings from a disabled color are ignored.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Overrides the global upload interval, in seconds.
+    pub upload_interval: Option<u64>,
+    /// Name shown for this color in the logs, e.g. the fermenter it floats in.
+    pub alias: Option<String>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Where a target sends readings.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Another instance of this server, via `POST /api/v1/readings`.
+    Server {
+        url: String,
+        api_key: Option<String>,
+    },
+    /// A Brewfather or Brewer's Friend style custom stream URL, one JSON post per reading.
+    CustomStream { url: String },
+    /// Any HTTP endpoint. `body` is a JSON template rendered and posted per reading.
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        body: serde_json::Value,
+    },
+}
+
+/// A `[[targets]]` entry: an extra destination with its own buffer, interval and backoff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetConfig {
+    pub name: String,
+    /// Overrides the per-color and global upload intervals for this target, in seconds.
+    pub upload_interval: Option<u64>,
+    pub sink: SinkConfig,
+}
+
+impl TargetConfig {
+    fn from_table(mut table: toml::Table) -> anyhow::Result<Self> {
+        let Some(toml::Value::String(name)) = table.remove("name") else {
+            bail!("every [[targets]] entry needs a name");
+        };
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            bail!("target name {name:?} may only contain letters, digits, '-' and '_'");
+        }
+        let upload_interval = table
+            .remove("upload_interval")
+            .map(|v| v.try_into::<u64>())
+            .transpose()
+            .with_context(|| format!("invalid upload_interval for target {name}"))?;
+        let sink = toml::Value::Table(table)
+            .try_into()
+            .with_context(|| format!("invalid target {name}"))?;
+        Ok(Self {
+            name,
+            upload_interval,
+            sink,
+        })
+    }
+}
+
+/// Contents of the TOML config file.
+#[derive(Debug, Default)]
+pub struct FileConfig {
+    pub options: Options,
+    pub colors: HashMap<TiltColor, ColorSettings>,
+    pub targets: Vec<TargetConfig>,
+}
+
+impl FileConfig {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        let colors = match table.remove("colors") {
+            Some(colors) => colors.try_into().context("invalid [colors] table")?,
+            None => HashMap::new(),
+        };
+        let targets = match table.remove("targets") {
+            Some(targets) => targets
+                .try_into::<Vec<toml::Table>>()
+                .context("targets must be an array of tables ([[targets]])")?
+                .into_iter()
+                .map(TargetConfig::from_table)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        let mut names = HashSet::from([PRIMARY_TARGET]);
+        for target in &targets {
+            if !names.insert(&target.name) {
+                bail!("duplicate or reserved target name {:?}", target.name);
+            }
+        }
+        let options = toml::Value::Table(table).try_into()?;
+        Ok(Self {
+            options,
+            colors,
+            targets,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("invalid config file {}", path.display()))
+    }
+}
+
+/// Effective settings after merging flags, environment and the config file.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub server_url: String,
+    pub api_key: Option<String>,
+    pub scan_interval: Duration,
+    pub upload_interval: Duration,
+    pub log_level: String,
+    pub buffer_dir: PathBuf,
+    pub buffer_limits: BufferLimits,
+    pub upload_chunk_size: usize,
+    /// `None` when heartbeats are disabled.
+    pub heartbeat_interval: Option<Duration>,
+    /// Where the status server listens; `None` when it is disabled.
+    pub status_listen: Option<SocketAddr>,
+    pub colors: HashMap<TiltColor, ColorSettings>,
+    /// Extra targets from the config file; see [`Settings::targets`].
+    pub extra_targets: Vec<TargetConfig>,
+}
+
+impl Settings {
+    pub fn resolve(overrides: &Options, file: FileConfig) -> anyhow::Result<Self> {
+        let options = overrides.clone().or(file.options);
+        let server_url = options.server_url.context(
+            "no server URL configured (--server-url, TILT_SERVER_URL or server_url in the \
+             config file)",
+        )?;
+        let max_age_hours = options
+            .buffer_max_age_hours
+            .unwrap_or(DEFAULT_BUFFER_MAX_AGE_HOURS);
+        Ok(Self {
+            server_url,
+            api_key: options.api_key,
+            scan_interval: Duration::from_secs(
+                options.scan_interval.unwrap_or(DEFAULT_SCAN_INTERVAL),
+            ),
+            upload_interval: Duration::from_secs(
+                options.upload_interval.unwrap_or(DEFAULT_UPLOAD_INTERVAL),
+            ),
+            log_level: options
+                .log_level
+                .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()),
+            buffer_dir: options
+                .buffer_dir
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_BUFFER_DIR)),
+            buffer_limits: BufferLimits {
+                max_readings: options.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE),
+                max_age: Duration::from_secs(max_age_hours * 3600),
+            },
+            upload_chunk_size: options
+                .upload_chunk_size
+                .unwrap_or(DEFAULT_UPLOAD_CHUNK_SIZE)
+                .max(1),
+            heartbeat_interval: Some(
+                options
+                    .heartbeat_interval
+                    .unwrap_or(DEFAULT_HEARTBEAT_INTERVAL),
+            )
+            .filter(|s| *s > 0)
+            .map(Duration::from_secs),
+            status_listen: options.status_listen,
+            colors: file.colors,
+            extra_targets: file.targets,
+        })
+    }
+
+    /// Settings from `overrides` and the config file at `path`, if any.
+    pub fn load(overrides: &Options, path: Option<&Path>) -> anyhow::Result<Self> {
+        let file = match path {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        Self::resolve(overrides, file)
+    }
+
+    /// Every upload target, starting with the primary server.
+    pub fn targets(&self) -> Vec<TargetConfig> {
+        let primary = TargetConfig {
+            name: PRIMARY_TARGET.to_string(),
+            upload_interval: None,
+            sink: SinkConfig::Server {
+                url: self.server_url.clone(),
+                api_key: self.api_key.clone(),
+            },
+        };
+        std::iter::once(primary)
+            .chain(self.extra_targets.iter().cloned())
+            .collect()
+    }
+
+    pub fn target_buffer_dir(&self, name: &str) -> PathBuf {
+        if name == PRIMARY_TARGET {
+            self.buffer_dir.clone()
+        } else {
+            self.buffer_dir.join("targets").join(name)
+        }
+    }
+
+    pub fn is_enabled(&self, color: TiltColor) -> bool {
+        self.colors.get(&color).is_none_or(|c| c.enabled)
+    }
+
+    /// Minimum time between uploads of `color` to `target`.
+    pub fn upload_interval_for(&self, target: &TargetConfig, color: TiltColor) -> Duration {
+        target
+            .upload_interval
+            .or_else(|| self.colors.get(&color).and_then(|c| c.upload_interval))
+            .map_or(self.upload_interval, Duration::from_secs)
+    }
+
+    /// The color's alias, or its name when it has none.
+    pub fn label(&self, color: TiltColor) -> String {
+        self.colors
+            .get(&color)
+            .and_then(|c| c.alias.clone())
+            .unwrap_or_else(|| format!("{color:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        server_url = "http://brewery:8000"
+        upload_interval = 300
+        buffer_dir = "/var/lib/tilt-client"
+
+        [colors.Red]
+        alias = "Fermenter 1"
+        upload_interval = 60
+
+        [colors.Pink]
+        enabled = false
+
+        [[targets]]
+        name = "brewfather"
+        kind = "custom-stream"
+        url = "http://log.brewfather.net/stream?id=abc"
+        upload_interval = 900
+
+        [[targets]]
+        name = "sheet"
+        kind = "http"
+        url = "https://example.com/hook"
+        headers = { Authorization = "Bearer token" }
+        body = { sg = "{{gravity}}", beer = "{{name}}" }
+    "#;
+
+    #[test]
+    fn parses_options_and_colors() {
+        let file = FileConfig::parse(EXAMPLE).unwrap();
+        assert_eq!(
+            file.options.server_url.as_deref(),
+            Some("http://brewery:8000")
+        );
+        assert_eq!(file.options.upload_interval, Some(300));
+        assert_eq!(
+            file.colors[&TiltColor::Red],
+            ColorSettings {
+                enabled: true,
+                upload_interval: Some(60),
+                alias: Some("Fermenter 1".to_string()),
+            }
+        );
+        assert!(!file.colors[&TiltColor::Pink].enabled);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_colors() {
+        assert!(FileConfig::parse("server_ulr = \"http://x\"").is_err());
+        assert!(FileConfig::parse("[colors.Teal]\nenabled = false").is_err());
+        assert!(FileConfig::parse("[colors.Red]\naliass = \"x\"").is_err());
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_file() {
+        let overrides = Options {
+            upload_interval: Some(30),
+            ..Options::default()
+        };
+        let settings = Settings::resolve(&overrides, FileConfig::parse(EXAMPLE).unwrap()).unwrap();
+        assert_eq!(settings.server_url, "http://brewery:8000");
+        assert_eq!(settings.upload_interval, Duration::from_secs(30));
+        assert_eq!(settings.buffer_dir, PathBuf::from("/var/lib/tilt-client"));
+        assert_eq!(
+            settings.scan_interval,
+            Duration::from_secs(DEFAULT_SCAN_INTERVAL)
+        );
+    }
+
+    #[test]
+    fn server_url_is_required() {
+        assert!(Settings::resolve(&Options::default(), FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn per_color_settings_fall_back_to_globals() {
+        let settings =
+            Settings::resolve(&Options::default(), FileConfig::parse(EXAMPLE).unwrap()).unwrap();
+        let [primary, brewfather, _] = settings.targets().try_into().unwrap();
+        assert_eq!(
+            settings.upload_interval_for(&primary, TiltColor::Red),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            settings.upload_interval_for(&primary, TiltColor::Blue),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            settings.upload_interval_for(&brewfather, TiltColor::Red),
+            Duration::from_secs(900)
+        );
+        assert!(!settings.is_enabled(TiltColor::Pink));
+        assert!(settings.is_enabled(TiltColor::Blue));
+        assert_eq!(settings.label(TiltColor::Red), "Fermenter 1");
+        assert_eq!(settings.label(TiltColor::Blue), "Blue");
+    }
+
+    #[test]
+    fn parses_targets_after_the_primary_server() {
+        let settings =
+            Settings::resolve(&Options::default(), FileConfig::parse(EXAMPLE).unwrap()).unwrap();
+        let targets = settings.targets();
+        assert_eq!(
+            targets.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            vec![PRIMARY_TARGET, "brewfather", "sheet"]
+        );
+        assert_eq!(
+            targets[0].sink,
+            SinkConfig::Server {
+                url: "http://brewery:8000".to_string(),
+                api_key: None,
+            }
+        );
+        let SinkConfig::Http { headers, body, .. } = &targets[2].sink else {
+            panic!("expected an http target");
+        };
+        assert_eq!(headers["Authorization"], "Bearer token");
+        assert_eq!(body["sg"], "{{gravity}}");
+        assert_eq!(
+            settings.target_buffer_dir("sheet"),
+            PathBuf::from("/var/lib/tilt-client/targets/sheet")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        let target = |body: &str| FileConfig::parse(&format!("[[targets]]\n{body}"));
+        assert!(target("kind = \"custom-stream\"\nurl = \"http://x\"").is_err());
+        assert!(target("name = \"a b\"\nkind = \"custom-stream\"\nurl = \"http://x\"").is_err());
+        assert!(target("name = \"server\"\nkind = \"custom-stream\"\nurl = \"http://x\"").is_err());
+        assert!(target("name = \"x\"\nkind = \"ftp\"\nurl = \"http://x\"").is_err());
+        assert!(target("name = \"x\"\nkind = \"custom-stream\"\nurll = \"http://x\"").is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Periodic status reports to the primary server, which uses them to list this client as a
+//! gateway and to notice when it goes offline.
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use shared::{GatewayHeartbeat, ScanStats, TiltReading};
+
+use crate::config::Settings;
+use crate::uploader::Uploader;
+
+pub struct Heartbeat {
+    uploader: Option<Uploader>,
+    interval: Option<Duration>,
+    hostname: Option<String>,
+    adapter: String,
+    last_scan: Option<ScanStats>,
+    last_sent: Option<Instant>,
+}
+
+impl Heartbeat {
+    pub fn new(settings: &Settings, adapter: impl Into<String>) -> Self {
+        let mut heartbeat = Self {
+            uploader: None,
+            interval: None,
+            hostname: hostname(),
+            adapter: adapter.into(),
+            last_scan: None,
+            last_sent: None,
+        };
+        heartbeat.configure(settings);
+        heartbeat
+    }
+
+    /// Heartbeats are authenticated with the API key, so they are off without one.
+    pub fn configure(&mut self, settings: &Settings) {
+        self.interval = settings.heartbeat_interval;
+        self.uploader = settings
+            .api_key
+            .as_ref()
+            .map(|key| Uploader::new(&settings.server_url, Some(key.clone())));
+        if self.uploader.is_none() && self.interval.is_some() {
+            tracing::info!("No API key configured, gateway heartbeats are disabled");
+        }
+    }
+
+    /// Sends a heartbeat in the background if one is due. A failed scan passes `None`, which
+    /// keeps reporting the last successful one.
+    pub fn tick(&mut self, buffered_readings: usize, scan: Option<ScanStats>) {
+        if scan.is_some() {
+            self.last_scan = scan;
+        }
+        let (Some(uploader), Some(interval)) = (&self.uploader, self.interval) else {
+            return;
+        };
+        if self.last_sent.is_some_and(|t| t.elapsed() < interval) {
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+
+        let heartbeat = GatewayHeartbeat {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: self.hostname.clone(),
+            adapter: Some(self.adapter.clone()),
+            buffered_readings: buffered_readings as u64,
+            last_scan: self.last_scan.clone(),
+        };
+        let uploader = uploader.clone();
+        tokio::spawn(async move {
+            match uploader.heartbeat(&heartbeat).await {
+                Ok(()) => tracing::debug!("Heartbeat sent"),
+                Err(e) => tracing::debug!("Heartbeat failed: {e}"),
+            }
+        });
+    }
+}
+
+/// Stats for a scan window that took `duration` and produced `readings`. Colors are listed
+/// once each, in the order they were first heard.
+pub fn scan_stats(readings: &[TiltReading], duration: Duration) -> ScanStats {
+    let mut colors = Vec::new();
+    for reading in readings {
+        if !colors.contains(&reading.color) {
+            colors.push(reading.color);
+        }
+    }
+    ScanStats {
+        finished_at: Utc::now(),
+        duration_ms: duration.as_millis() as u64,
+        readings: readings.len() as u32,
+        colors,
+    }
+}
+
+fn hostname() -> Option<String> {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|name| name.trim().to_string())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::TiltColor;
+
+    #[test]
+    fn scan_stats_lists_each_color_once() {
+        let reading = |color| TiltReading::new(color, 68.0, 1.050, None, Utc::now());
+        let readings = [
+            reading(TiltColor::Red),
+            reading(TiltColor::Blue),
+            reading(TiltColor::Red),
+        ];
+        let stats = scan_stats(&readings, Duration::from_millis(5_000));
+        assert_eq!(stats.readings, 3);
+        assert_eq!(stats.duration_ms, 5_000);
+        assert_eq!(stats.colors, vec![TiltColor::Red, TiltColor::Blue]);
+    }
+}
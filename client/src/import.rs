@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use chrono::FixedOffset;
+use shared::TiltColor;
+use shared::tilt_csv::{self, LogOptions};
+
+use crate::uploader::Uploader;
+
+/// Parses a Tilt CSV log locally, reporting bad rows, then (unless `dry_run`) sends it to
+/// the server, which maps it onto hydrometers and brews and skips readings it already has.
+pub async fn run(
+    uploader: &Uploader,
+    file: &Path,
+    color: Option<TiltColor>,
+    utc_offset_minutes: i32,
+    create_brews: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let csv = std::fs::read_to_string(file)?;
+    let options = LogOptions {
+        color,
+        utc_offset: FixedOffset::east_opt(utc_offset_minutes * 60)
+            .ok_or_else(|| anyhow::anyhow!("UTC offset out of range"))?,
+    };
+    let log = tilt_csv::parse(&csv, &options).map_err(|e| anyhow::anyhow!(e))?;
+
+    for error in &log.errors {
+        tracing::warn!(line = error.line, "{}", error.message);
+    }
+    tracing::info!(
+        file = %file.display(),
+        valid = log.rows.len(),
+        invalid = log.errors.len(),
+        "Parsed log"
+    );
+    if dry_run {
+        return Ok(());
+    }
+
+    let report = uploader
+        .import_log(csv, color, utc_offset_minutes, create_brews)
+        .await?;
+    for name in &report.brews_created {
+        tracing::info!(brew = %name, "Created brew");
+    }
+    tracing::info!(
+        rows = report.rows,
+        imported = report.imported,
+        duplicates = report.duplicates,
+        errors = report.errors.len(),
+        "Import complete"
+    );
+    Ok(())
+}
@@ -1,49 +1,50 @@
 mod buffer;
+mod config;
+mod heartbeat;
+mod import;
 mod scanner;
 mod simulator;
+mod status;
+mod targets;
 mod uploader;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use buffer::{Backoff, ReadingBuffer};
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use config::{Options, PRIMARY_TARGET, Settings};
+use heartbeat::Heartbeat;
 use scanner::TiltScanner;
 use shared::TiltColor;
 use simulator::TiltSimulator;
+use status::Monitor;
+use targets::TargetHandle;
+use tokio::signal::unix::{SignalKind, signal};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Registry, reload};
 use uploader::Uploader;
 
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "tilt-client",
     about = "Tilt Hydrometer BLE scanner and uploader"
 )]
 struct Args {
-    #[arg(long, help = "Server URL to upload readings to")]
-    server_url: String,
-
-    #[arg(long, default_value_t = 5, help = "BLE scan window in seconds (how long to listen per cycle)")]
-    scan_interval: u64,
-
-    #[arg(long, default_value_t = 60, help = "Minimum seconds between uploads per hydrometer color")]
-    upload_interval: u64,
-
     #[arg(
         long,
-        default_value = "info",
-        help = "Log level (trace, debug, info, warn, error)"
+        env = "TILT_CONFIG",
+        help = "TOML config file; flags and TILT_* environment variables override it, and it is \
+                re-read on SIGHUP"
     )]
-    log_level: String,
+    config: Option<PathBuf>,
 
-    #[arg(
-        long,
-        default_value_t = 100,
-        help = "Maximum number of readings to buffer locally"
-    )]
-    buffer_size: usize,
-
-    #[arg(long, help = "API key for authenticating with the server (X-API-Key header)")]
-    api_key: Option<String>,
+    #[command(flatten)]
+    options: Options,
 
     #[arg(
         long,
@@ -75,25 +76,92 @@ struct Args {
         help = "Simulated base temperature in °F"
     )]
     sim_temp: f64,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Import historical readings from a Tilt app or TiltPi CSV log
+    Import {
+        #[arg(help = "CSV log file")]
+        file: PathBuf,
+
+        #[arg(long, help = "Tilt color for logs without a color column")]
+        color: Option<String>,
+
+        #[arg(
+            long,
+            default_value_t = 0,
+            allow_hyphen_values = true,
+            help = "UTC offset in minutes of timestamps without a time zone (e.g. -300)"
+        )]
+        utc_offset_minutes: i32,
+
+        #[arg(long, help = "Don't create brews for beer names that match no existing brew")]
+        no_create_brews: bool,
+
+        #[arg(long, help = "Only parse the log and report bad rows; upload nothing")]
+        dry_run: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let settings = match Settings::load(&args.options, args.config.as_deref()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(2);
+        }
+    };
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_new(&args.log_level)
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
+    let (log_filter, log_handle) = reload::Layer::new(log_filter(&settings.log_level));
+    tracing_subscriber::registry()
+        .with(log_filter)
+        .with(tracing_subscriber::fmt::layer())
         .init();
 
+    if let Some(Command::Import {
+        file,
+        color,
+        utc_offset_minutes,
+        no_create_brews,
+        dry_run,
+    }) = &args.command
+    {
+        let color = color.as_deref().map(|s| {
+            TiltColor::parse(s.trim()).unwrap_or_else(|| {
+                tracing::error!(color = s, "Invalid Tilt color name. Valid: Red, Green, Black, Purple, Orange, Blue, Yellow, Pink");
+                std::process::exit(1);
+            })
+        });
+        let uploader = Uploader::new(&settings.server_url, settings.api_key.clone());
+        let result = import::run(
+            &uploader,
+            file,
+            color,
+            *utc_offset_minutes,
+            !no_create_brews,
+            *dry_run,
+        )
+        .await;
+        if let Err(e) = result {
+            tracing::error!("Import failed: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     if args.simulate {
         tracing::info!(
-            server_url = %args.server_url,
-            scan_interval = args.scan_interval,
-            upload_interval = args.upload_interval,
-            buffer_size = args.buffer_size,
+            server_url = %settings.server_url,
+            scan_interval = ?settings.scan_interval,
+            upload_interval = ?settings.upload_interval,
+            buffer_dir = %settings.buffer_dir.display(),
+            buffer_size = settings.buffer_limits.max_readings,
             sim_colors = %args.sim_colors,
             sim_og = args.sim_og,
             sim_target_fg = args.sim_target_fg,
@@ -102,21 +170,56 @@ async fn main() {
         );
     } else {
         tracing::info!(
-            server_url = %args.server_url,
-            scan_interval = args.scan_interval,
-            upload_interval = args.upload_interval,
-            buffer_size = args.buffer_size,
-            log_level = %args.log_level,
+            server_url = %settings.server_url,
+            scan_interval = ?settings.scan_interval,
+            upload_interval = ?settings.upload_interval,
+            buffer_dir = %settings.buffer_dir.display(),
+            buffer_size = settings.buffer_limits.max_readings,
+            log_level = %settings.log_level,
             "Starting Tilt Hydrometer BLE client"
         );
     }
 
-    let uploader = Uploader::new(&args.server_url, args.api_key.clone());
-    let mut reading_buffer = ReadingBuffer::new(args.buffer_size);
-    let mut backoff = Backoff::default();
-    let scan_duration = Duration::from_secs(args.scan_interval);
-    let upload_interval = Duration::from_secs(args.upload_interval);
-    let mut last_uploaded: HashMap<TiltColor, Instant> = HashMap::new();
+    // The simulator uploads every reading it generates; real scans honour the intervals.
+    let throttle = !args.simulate;
+    let mut settings = Arc::new(settings);
+    let monitor = Monitor::new(settings.scan_interval);
+    if let Some(addr) = settings.status_listen {
+        let listener = match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(%addr, "Failed to start status server: {e}");
+                std::process::exit(1);
+            }
+        };
+        let monitor = monitor.clone();
+        tokio::spawn(async move {
+            if let Err(e) = status::serve(listener, monitor).await {
+                tracing::error!("Status server stopped: {e}");
+            }
+        });
+    }
+    let mut targets = HashMap::new();
+    for target in settings.targets() {
+        let name = target.name.clone();
+        match targets::spawn(target, settings.clone(), throttle, monitor.clone()) {
+            Ok(handle) => {
+                targets.insert(name, handle);
+            }
+            Err(e) => {
+                let dir = settings.target_buffer_dir(&name);
+                tracing::error!(target_name = %name, dir = %dir.display(), "Failed to open reading buffer: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::error!("Failed to install SIGHUP handler: {e}");
+            std::process::exit(1);
+        }
+    };
 
     if args.simulate {
         let colors: Vec<TiltColor> = args
@@ -132,116 +235,187 @@ async fn main() {
             .collect();
 
         let simulator = TiltSimulator::new(colors, args.sim_og, args.sim_target_fg, args.sim_temp);
+        let mut heartbeat = Heartbeat::new(&settings, "simulator");
+        // Register before the first upload so its readings are attributed to this gateway.
+        heartbeat.tick(buffered(&targets), None);
 
         loop {
+            let scan_started = Instant::now();
             tokio::select! {
                 _ = tokio::signal::ctrl_c() => {
                     tracing::info!("Received Ctrl+C, shutting down gracefully");
                     break;
                 }
-                _ = tokio::time::sleep(scan_duration) => {
+                _ = hangup.recv() => {
+                    reload(&args, &mut settings, &mut targets, throttle, &monitor, &log_handle);
+                    heartbeat.configure(&settings);
+                }
+                _ = tokio::time::sleep(settings.scan_interval) => {
                     let mut readings = simulator.generate_readings();
                     for r in &readings {
-                        tracing::info!(color = ?r.color, temp = format!("{:.1}", r.temperature_f), gravity = format!("{:.4}", r.gravity), rssi = ?r.rssi, "Simulated reading");
+                        monitor.advertisement(r.color);
                     }
-                    tracing::info!(count = readings.len(), "Simulated scan complete");
-
-                    if !reading_buffer.is_empty() {
-                        let buffered = reading_buffer.drain_all();
-                        tracing::info!(buffered = buffered.len(), "Prepending buffered readings");
-                        let mut all = buffered;
-                        all.append(&mut readings);
-                        readings = all;
+                    readings.retain(|r| settings.is_enabled(r.color));
+                    monitor.scan_complete(&readings, settings.scan_interval);
+                    for r in &readings {
+                        tracing::info!(tilt = %settings.label(r.color), temp = format!("{:.1}", r.temperature_f), gravity = format!("{:.4}", r.gravity), rssi = ?r.rssi, "Simulated reading");
                     }
+                    tracing::info!(count = readings.len(), "Simulated scan complete");
 
-                    match uploader.upload_batch(&readings).await {
-                        Ok(response) => {
-                            tracing::info!(?response, "Upload successful");
-                            backoff.reset();
-                        }
-                        Err(e) => {
-                            tracing::warn!("Upload failed: {e}");
-                            reading_buffer.push_batch(&readings);
-                            let delay = backoff.next_delay();
-                            tracing::info!(?delay, buffered = reading_buffer.len(), "Backing off");
-                            tokio::time::sleep(delay).await;
-                        }
+                    let stats = heartbeat::scan_stats(&readings, scan_started.elapsed());
+                    for target in targets.values() {
+                        target.send(readings.clone());
                     }
+                    heartbeat.tick(buffered(&targets), Some(stats));
                 }
             }
         }
     } else {
-        let mut scanner = match TiltScanner::new().await {
+        let mut scanner = match TiltScanner::new(monitor.clone()).await {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!("Failed to initialize BLE scanner: {e}");
                 return;
             }
         };
+        let mut heartbeat = Heartbeat::new(&settings, scanner.adapter_info());
+        // Register before the first upload so its readings are attributed to this gateway.
+        heartbeat.tick(buffered(&targets), None);
 
         loop {
-            tokio::select! {
-                _ = tokio::signal::ctrl_c() => {
-                    tracing::info!("Received Ctrl+C, shutting down gracefully");
-                    break;
+            let scan_started = Instant::now();
+            let mut reload_requested = false;
+            let result = {
+                let scan = scanner.next_batch(settings.scan_interval);
+                tokio::pin!(scan);
+                loop {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => break None,
+                        // Applied once the scan window closes, so the readings collected in
+                        // it are still uploaded.
+                        _ = hangup.recv() => reload_requested = true,
+                        result = &mut scan => break Some(result),
+                    }
                 }
-                result = scanner.next_batch(scan_duration) => {
-                    match result {
-                        Ok(all_readings) => {
-                            tracing::debug!(count = all_readings.len(), "Scan complete");
-
-                            let now = Instant::now();
-                            let mut readings: Vec<_> = all_readings
-                                .into_iter()
-                                .filter(|r| {
-                                    last_uploaded
-                                        .get(&r.color)
-                                        .map_or(true, |t| now.duration_since(*t) >= upload_interval)
-                                })
-                                .collect();
-
-                            for r in &readings {
-                                last_uploaded.insert(r.color, now);
-                            }
-
-                            if !reading_buffer.is_empty() {
-                                let buffered = reading_buffer.drain_all();
-                                tracing::info!(buffered = buffered.len(), "Prepending buffered readings");
-                                let mut all = buffered;
-                                all.append(&mut readings);
-                                readings = all;
-                            }
-
-                            if readings.is_empty() {
-                                tracing::debug!("No readings due (throttled)");
-                                continue;
-                            }
-
-                            tracing::info!(count = readings.len(), "Uploading readings");
-
-                            match uploader.upload_batch(&readings).await {
-                                Ok(response) => {
-                                    tracing::info!(?response, "Upload successful");
-                                    backoff.reset();
-                                }
-                                Err(e) => {
-                                    tracing::warn!("Upload failed: {e}");
-                                    reading_buffer.push_batch(&readings);
-                                    let delay = backoff.next_delay();
-                                    tracing::info!(?delay, buffered = reading_buffer.len(), "Backing off");
-                                    tokio::time::sleep(delay).await;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!("Scan failed: {e:#}");
-                            tokio::time::sleep(Duration::from_secs(5)).await;
-                        }
+            };
+            let Some(result) = result else {
+                tracing::info!("Received Ctrl+C, shutting down gracefully");
+                break;
+            };
+
+            match result {
+                Ok(all_readings) => {
+                    tracing::debug!(count = all_readings.len(), "Scan complete");
+
+                    let readings: Vec<_> = all_readings
+                        .into_iter()
+                        .filter(|r| settings.is_enabled(r.color))
+                        .collect();
+                    monitor.scan_complete(&readings, settings.scan_interval);
+
+                    for r in &readings {
+                        tracing::debug!(tilt = %settings.label(r.color), gravity = r.gravity, "Tilt reading");
+                    }
+
+                    // Each target throttles to its own interval and uploads in its own task.
+                    let stats = heartbeat::scan_stats(&readings, scan_started.elapsed());
+                    for target in targets.values() {
+                        target.send(readings.clone());
                     }
+                    heartbeat.tick(buffered(&targets), Some(stats));
                 }
+                Err(e) => {
+                    tracing::warn!("Scan failed: {e:#}");
+                    heartbeat.tick(buffered(&targets), None);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+
+            if reload_requested {
+                reload(&args, &mut settings, &mut targets, throttle, &monitor, &log_handle);
+                heartbeat.configure(&settings);
             }
         }
     }
 
     tracing::info!("Tilt client shut down");
 }
+
+/// Readings waiting to reach the primary server, as reported in heartbeats.
+fn buffered(targets: &HashMap<String, TargetHandle>) -> usize {
+    targets
+        .get(PRIMARY_TARGET)
+        .map_or(0, TargetHandle::buffered)
+}
+
+fn log_filter(level: &str) -> EnvFilter {
+    EnvFilter::try_new(level).unwrap_or_else(|_| EnvFilter::new("info"))
+}
+
+/// Re-reads the config file on SIGHUP and applies it. Flags and environment variables still
+/// take precedence. The buffer directory and status server address can't change while
+/// running; everything else, including adding and removing targets, takes effect
+/// immediately. An invalid file is reported and the current settings are kept.
+fn reload(
+    args: &Args,
+    settings: &mut Arc<Settings>,
+    targets: &mut HashMap<String, TargetHandle>,
+    throttle: bool,
+    monitor: &Arc<Monitor>,
+    log_handle: &LogFilterHandle,
+) {
+    let Some(path) = args.config.as_deref() else {
+        tracing::info!("Received SIGHUP but no config file is in use, nothing to reload");
+        return;
+    };
+    let mut new = match Settings::load(&args.options, Some(path)) {
+        Ok(new) => new,
+        Err(e) => {
+            tracing::error!("Config reload failed, keeping current settings: {e:#}");
+            return;
+        }
+    };
+
+    if new.status_listen != settings.status_listen {
+        tracing::warn!("Changing status_listen requires a restart");
+        new.status_listen = settings.status_listen;
+    }
+    if new.buffer_dir != settings.buffer_dir {
+        tracing::warn!(
+            buffer_dir = %settings.buffer_dir.display(),
+            "Changing buffer_dir requires a restart; still using the current directory"
+        );
+        new.buffer_dir = settings.buffer_dir.clone();
+    }
+    if new.log_level != settings.log_level
+        && let Err(e) = log_handle.reload(log_filter(&new.log_level))
+    {
+        tracing::warn!("Failed to change log level: {e}");
+    }
+    *settings = Arc::new(new);
+
+    let configured = settings.targets();
+    targets.retain(|name, _| {
+        let keep = configured.iter().any(|t| &t.name == name);
+        if !keep {
+            tracing::info!(target_name = %name, "Removing upload target");
+            monitor.remove_target(name);
+        }
+        keep
+    });
+    for target in configured {
+        if let Some(handle) = targets.get(&target.name) {
+            handle.configure(settings.clone());
+            continue;
+        }
+        let name = target.name.clone();
+        match targets::spawn(target, settings.clone(), throttle, monitor.clone()) {
+            Ok(handle) => {
+                tracing::info!(target_name = %name, "Added upload target");
+                targets.insert(name, handle);
+            }
+            Err(e) => tracing::error!(target_name = %name, "Failed to open reading buffer: {e}"),
+        }
+    }
+    tracing::info!(config = %path.display(), "Reloaded configuration");
+}
@@ -1,35 +1,43 @@
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
-use btleplug::api::{Central, CentralEvent, Manager as _, ScanFilter};
-use btleplug::platform::{Adapter, Manager};
+use btleplug::api::{Central, CentralEvent, Manager as _, Peripheral as _, ScanFilter};
+use btleplug::platform::{Adapter, Manager, PeripheralId};
 use futures::{Stream, StreamExt};
-use shared::{TiltColor, TiltReading};
+use shared::{TiltColor, TiltDeviceKind, TiltReading};
 use uuid::Uuid;
 
+use crate::status::Monitor;
+
 const APPLE_COMPANY_ID: u16 = 0x004C;
 const IBEACON_TYPE: u8 = 0x02;
 const IBEACON_LENGTH: u8 = 0x15;
+/// Tilt Pro broadcasts gravity ×10000, so its minor is always above this value,
+/// while a standard Tilt's gravity ×1000 never comes close to it.
+const HIGH_RES_MIN_MINOR: u16 = 5000;
 
 pub struct TiltScanner {
-    #[allow(dead_code)]
     adapter: Adapter,
     /// Event stream created once and kept alive for the lifetime of the scanner.
     /// Dropping it triggers a bluez-async panic (D-Bus match cleanup race),
     /// so we hold it forever and reuse it across scan cycles.
     events: Pin<Box<dyn Stream<Item = CentralEvent> + Send>>,
+    adapter_info: String,
+    monitor: Arc<Monitor>,
 }
 
 impl TiltScanner {
-    pub async fn new() -> anyhow::Result<Self> {
+    pub async fn new(monitor: Arc<Monitor>) -> anyhow::Result<Self> {
         let manager = Manager::new().await?;
         let adapters = manager.adapters().await?;
         let adapter = adapters
             .into_iter()
             .next()
             .ok_or_else(|| anyhow::anyhow!("No Bluetooth adapter found"))?;
-        tracing::info!("Using BLE adapter: {:?}", adapter.adapter_info().await?);
+        let adapter_info = adapter.adapter_info().await?;
+        tracing::info!("Using BLE adapter: {:?}", adapter_info);
 
         let events = adapter.events().await
             .map_err(|e| anyhow::anyhow!("failed to get event stream: {e:#}"))?;
@@ -37,7 +45,17 @@ impl TiltScanner {
         adapter.start_scan(ScanFilter::default()).await
             .map_err(|e| anyhow::anyhow!("start_scan failed: {e:#}"))?;
 
-        Ok(Self { adapter, events })
+        Ok(Self {
+            adapter,
+            events,
+            adapter_info,
+            monitor,
+        })
+    }
+
+    /// Description of the Bluetooth adapter in use, e.g. `hci0 (usb:v1D6Bp0246d0537)`.
+    pub fn adapter_info(&self) -> &str {
+        &self.adapter_info
     }
 
     /// Scan continuously and collect all readings seen within each `interval` window.
@@ -52,17 +70,26 @@ impl TiltScanner {
                 _ = tokio::time::sleep_until(deadline) => break,
                 event = self.events.next() => {
                     match event {
-                        Some(CentralEvent::ManufacturerDataAdvertisement { manufacturer_data, .. }) => {
-                            if let Some(data) = manufacturer_data.get(&APPLE_COMPANY_ID) {
-                                if let Some(reading) = parse_ibeacon_tilt(data) {
-                                    tracing::debug!(
-                                        color = ?reading.color,
-                                        temp = reading.temperature_f,
-                                        gravity = reading.gravity,
-                                        "Tilt advertisement"
-                                    );
-                                    latest.insert(reading.color, reading);
-                                }
+                        Some(CentralEvent::ManufacturerDataAdvertisement { id, manufacturer_data }) => {
+                            let Some(data) = manufacturer_data.get(&APPLE_COMPANY_ID) else {
+                                continue;
+                            };
+                            if let Some(mut reading) = parse_ibeacon_tilt(data) {
+                                self.monitor.advertisement(reading.color);
+                                reading.rssi = self.rssi_for(&id).await;
+                                tracing::debug!(
+                                    color = ?reading.color,
+                                    kind = ?reading.device_kind,
+                                    temp = reading.temperature_f,
+                                    gravity = reading.gravity,
+                                    rssi = ?reading.rssi,
+                                    tx_power = ?reading.tx_power,
+                                    "Tilt advertisement"
+                                );
+                                latest.insert(reading.color, reading);
+                            } else if is_tilt_beacon(data) {
+                                self.monitor.parse_failure();
+                                tracing::debug!(len = data.len(), "Malformed Tilt advertisement");
                             }
                         }
                         None => {
@@ -77,6 +104,26 @@ impl TiltScanner {
 
         Ok(latest.into_values().collect())
     }
+
+    /// The advertisement event carries no RSSI, so read the most recent value bluez
+    /// recorded for the peripheral that sent it.
+    async fn rssi_for(&self, id: &PeripheralId) -> Option<i16> {
+        let peripheral = self.adapter.peripheral(id).await.ok()?;
+        match peripheral.properties().await {
+            Ok(properties) => properties?.rssi,
+            Err(e) => {
+                tracing::debug!("Failed to read peripheral properties: {e:#}");
+                None
+            }
+        }
+    }
+}
+
+/// Whether `data` starts like a Tilt's iBeacon payload, whatever follows the UUID.
+pub fn is_tilt_beacon(data: &[u8]) -> bool {
+    data.len() >= 18
+        && data[0] == IBEACON_TYPE
+        && Uuid::from_slice(&data[2..18]).is_ok_and(|uuid| TiltColor::from_uuid(&uuid).is_some())
 }
 
 pub fn parse_ibeacon_tilt(data: &[u8]) -> Option<TiltReading> {
@@ -84,8 +131,8 @@ pub fn parse_ibeacon_tilt(data: &[u8]) -> Option<TiltReading> {
     // [0] = 0x02 (iBeacon type)
     // [1] = 0x15 (length = 21 bytes)
     // [2..18] = UUID (16 bytes)
-    // [18..20] = Major (u16 big-endian) = temperature °F
-    // [20..22] = Minor (u16 big-endian) = gravity * 1000
+    // [18..20] = Major (u16 big-endian) = temperature °F (×10 on Tilt Pro)
+    // [20..22] = Minor (u16 big-endian) = gravity * 1000 (×10000 on Tilt Pro)
     // [22] = TX Power (i8)
     if data.len() < 23 {
         return None;
@@ -101,17 +148,21 @@ pub fn parse_ibeacon_tilt(data: &[u8]) -> Option<TiltReading> {
 
     let color = TiltColor::from_uuid(&uuid)?;
 
-    let temperature_f = u16::from_be_bytes([data[18], data[19]]) as f64;
-    let gravity = u16::from_be_bytes([data[20], data[21]]) as f64 / 1000.0;
-    let _tx_power = data[22] as i8;
-
-    Some(TiltReading::new(
-        color,
-        temperature_f,
-        gravity,
-        None,
-        chrono::Utc::now(),
-    ))
+    let major = u16::from_be_bytes([data[18], data[19]]);
+    let minor = u16::from_be_bytes([data[20], data[21]]);
+    let tx_power = data[22] as i8;
+
+    let (device_kind, temperature_f, gravity) = if minor >= HIGH_RES_MIN_MINOR {
+        (TiltDeviceKind::Pro, major as f64 / 10.0, minor as f64 / 10000.0)
+    } else {
+        (TiltDeviceKind::Standard, major as f64, minor as f64 / 1000.0)
+    };
+
+    Some(TiltReading {
+        device_kind,
+        tx_power: Some(tx_power as i16),
+        ..TiltReading::new(color, temperature_f, gravity, None, chrono::Utc::now())
+    })
 }
 
 #[cfg(test)]
@@ -138,6 +189,34 @@ mod tests {
         assert_eq!(reading.color, TiltColor::Red);
         assert!((reading.temperature_f - 68.0).abs() < f64::EPSILON);
         assert!((reading.gravity - 1.016).abs() < 0.0001);
+        assert_eq!(reading.device_kind, TiltDeviceKind::Standard);
+    }
+
+    #[test]
+    fn parse_tx_power_as_signed() {
+        let data = make_ibeacon_data(red_uuid_bytes(), 68, 1016, -59);
+        let reading = parse_ibeacon_tilt(&data).unwrap();
+        assert_eq!(reading.tx_power, Some(-59));
+        assert!(reading.rssi.is_none());
+    }
+
+    #[test]
+    fn parse_tilt_pro_high_resolution() {
+        let data = make_ibeacon_data(red_uuid_bytes(), 681, 10163, -59);
+        let reading = parse_ibeacon_tilt(&data).unwrap();
+        assert_eq!(reading.device_kind, TiltDeviceKind::Pro);
+        assert!((reading.temperature_f - 68.1).abs() < 0.0001);
+        assert!((reading.gravity - 1.0163).abs() < 0.00001);
+    }
+
+    #[test]
+    fn parse_tilt_pro_low_gravity_still_high_resolution() {
+        // 0.9950 in ×10000 encoding stays well above the standard range.
+        let data = make_ibeacon_data(red_uuid_bytes(), 335, 9950, -59);
+        let reading = parse_ibeacon_tilt(&data).unwrap();
+        assert_eq!(reading.device_kind, TiltDeviceKind::Pro);
+        assert!((reading.temperature_f - 33.5).abs() < 0.0001);
+        assert!((reading.gravity - 0.995).abs() < 0.00001);
     }
 
     #[test]
@@ -173,6 +252,15 @@ mod tests {
         assert!(parse_ibeacon_tilt(&data).is_none());
     }
 
+    #[test]
+    fn truncated_tilt_beacon_is_recognised() {
+        let data = make_ibeacon_data(red_uuid_bytes(), 68, 1016, -59);
+        assert!(is_tilt_beacon(&data[..20]));
+        assert!(parse_ibeacon_tilt(&data[..20]).is_none());
+        assert!(!is_tilt_beacon(&make_ibeacon_data([0; 16], 68, 1016, -59)));
+        assert!(!is_tilt_beacon(&data[..10]));
+    }
+
     #[test]
     fn parse_too_short_data_returns_none() {
         let data = vec![0x02, 0x15, 0x00];
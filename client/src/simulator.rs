@@ -138,7 +138,7 @@ mod tests {
             let readings = sim.generate_readings();
             for r in &readings {
                 let rssi = r.rssi.expect("RSSI should be Some");
-                assert!(rssi >= -80 && rssi <= -60, "RSSI {} out of range", rssi,);
+                assert!((-80..=-60).contains(&rssi), "RSSI {} out of range", rssi,);
             }
         }
     }
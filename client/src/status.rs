@@ -0,0 +1,371 @@
+//! Local monitoring for the client: counters and state fed by the scanner and upload targets,
+//! served by an optional embedded HTTP server as `/healthz`, `/status` (JSON) and `/metrics`
+//! (Prometheus text format).
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State as AxumState;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use serde::Serialize;
+use shared::{TiltColor, TiltReading};
+use tokio::net::TcpListener;
+use tokio::time::Instant;
+
+/// Latency buckets for uploads, in seconds.
+const UPLOAD_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Extra time allowed on top of three scan windows before the scanner counts as stuck.
+const HEALTH_GRACE: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadResult {
+    pub at: DateTime<Utc>,
+    pub ok: bool,
+    pub readings: usize,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetStatus {
+    pub buffered: usize,
+    /// Failed uploads since the last successful one.
+    pub consecutive_failures: u32,
+    /// When the target will retry after a failure; `None` while it isn't backing off.
+    pub retry_at: Option<DateTime<Utc>>,
+    pub last_upload: Option<UploadResult>,
+}
+
+/// Body of `GET /status`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusReport {
+    pub version: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub last_scan_at: Option<DateTime<Utc>>,
+    pub healthy: bool,
+    /// Most recent reading of each color, in color order.
+    pub readings: Vec<TiltReading>,
+    pub targets: BTreeMap<String, TargetStatus>,
+}
+
+#[derive(Default)]
+struct State {
+    readings: HashMap<TiltColor, TiltReading>,
+    last_scan_at: Option<DateTime<Utc>>,
+    scan_interval: Duration,
+    targets: BTreeMap<String, TargetStatus>,
+}
+
+/// Shared by the scanner loop, every upload target and the HTTP server.
+pub struct Monitor {
+    started_at: DateTime<Utc>,
+    state: Mutex<State>,
+    registry: Registry,
+    advertisements: IntCounterVec,
+    parse_failures: IntCounter,
+    uploads: IntCounterVec,
+    upload_duration: HistogramVec,
+    buffered: IntGaugeVec,
+}
+
+impl Monitor {
+    /// `scan_interval` is how often scan windows are expected to complete; later scans update it.
+    pub fn new(scan_interval: Duration) -> Arc<Self> {
+        let advertisements = IntCounterVec::new(
+            Opts::new(
+                "tilt_client_advertisements_total",
+                "Tilt advertisements received, by color",
+            ),
+            &["color"],
+        )
+        .expect("valid metric");
+        let parse_failures = IntCounter::new(
+            "tilt_client_parse_failures_total",
+            "Advertisements with a Tilt UUID that could not be parsed",
+        )
+        .expect("valid metric");
+        let uploads = IntCounterVec::new(
+            Opts::new(
+                "tilt_client_uploads_total",
+                "Upload requests, by target and result (ok or error)",
+            ),
+            &["target", "result"],
+        )
+        .expect("valid metric");
+        let upload_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "tilt_client_upload_duration_seconds",
+                "Upload request latency, by target",
+            )
+            .buckets(UPLOAD_BUCKETS.to_vec()),
+            &["target"],
+        )
+        .expect("valid metric");
+        let buffered = IntGaugeVec::new(
+            Opts::new(
+                "tilt_client_buffered_readings",
+                "Readings waiting in each target's buffer",
+            ),
+            &["target"],
+        )
+        .expect("valid metric");
+
+        let registry = Registry::new();
+        for collector in [
+            Box::new(advertisements.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(parse_failures.clone()),
+            Box::new(uploads.clone()),
+            Box::new(upload_duration.clone()),
+            Box::new(buffered.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        Arc::new(Self {
+            started_at: Utc::now(),
+            state: Mutex::new(State {
+                scan_interval,
+                ..State::default()
+            }),
+            registry,
+            advertisements,
+            parse_failures,
+            uploads,
+            upload_duration,
+            buffered,
+        })
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn advertisement(&self, color: TiltColor) {
+        self.advertisements
+            .with_label_values(&[&format!("{color:?}")])
+            .inc();
+    }
+
+    pub fn parse_failure(&self) {
+        self.parse_failures.inc();
+    }
+
+    /// Records the end of a scan window and the readings it produced.
+    pub fn scan_complete(&self, readings: &[TiltReading], scan_interval: Duration) {
+        let mut state = self.state();
+        state.last_scan_at = Some(Utc::now());
+        state.scan_interval = scan_interval;
+        for reading in readings {
+            state.readings.insert(reading.color, reading.clone());
+        }
+    }
+
+    /// Records one upload attempt by `target`.
+    pub fn upload(&self, target: &str, readings: usize, elapsed: Duration, error: Option<String>) {
+        let result = if error.is_none() { "ok" } else { "error" };
+        self.uploads.with_label_values(&[target, result]).inc();
+        self.upload_duration
+            .with_label_values(&[target])
+            .observe(elapsed.as_secs_f64());
+
+        let mut state = self.state();
+        let status = state.targets.entry(target.to_string()).or_default();
+        if error.is_none() {
+            status.consecutive_failures = 0;
+        } else {
+            status.consecutive_failures += 1;
+        }
+        status.last_upload = Some(UploadResult {
+            at: Utc::now(),
+            ok: error.is_none(),
+            readings,
+            latency_ms: elapsed.as_millis() as u64,
+            error,
+        });
+    }
+
+    /// Updates a target's buffer depth and backoff state.
+    pub fn target_state(&self, target: &str, buffered: usize, retry_at: Option<Instant>) {
+        self.buffered
+            .with_label_values(&[target])
+            .set(buffered as i64);
+        let retry_at = retry_at.map(|at| {
+            let remaining = at.saturating_duration_since(Instant::now());
+            Utc::now() + chrono::Duration::from_std(remaining).unwrap_or_default()
+        });
+        let mut state = self.state();
+        let status = state.targets.entry(target.to_string()).or_default();
+        status.buffered = buffered;
+        status.retry_at = retry_at;
+    }
+
+    /// Forgets a target removed by a reload.
+    pub fn remove_target(&self, target: &str) {
+        let _ = self.buffered.remove_label_values(&[target]);
+        self.state().targets.remove(target);
+    }
+
+    pub fn report(&self) -> StatusReport {
+        let state = self.state();
+        let readings = TiltColor::all()
+            .iter()
+            .filter_map(|color| state.readings.get(color).cloned())
+            .collect();
+        StatusReport {
+            version: env!("CARGO_PKG_VERSION"),
+            started_at: self.started_at,
+            last_scan_at: state.last_scan_at,
+            healthy: is_healthy(
+                self.started_at,
+                state.last_scan_at,
+                state.scan_interval,
+                Utc::now(),
+            ),
+            readings,
+            targets: state.targets.clone(),
+        }
+    }
+
+    pub fn metrics(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to encode metrics: {e}");
+                String::new()
+            })
+    }
+}
+
+/// The scanner is healthy while scan windows keep completing. Until the first one does, the
+/// startup time stands in for the last scan.
+pub fn is_healthy(
+    started_at: DateTime<Utc>,
+    last_scan_at: Option<DateTime<Utc>>,
+    scan_interval: Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    let allowed = chrono::Duration::from_std(scan_interval * 3 + HEALTH_GRACE)
+        .unwrap_or(chrono::Duration::MAX);
+    now - last_scan_at.unwrap_or(started_at) <= allowed
+}
+
+/// Serves the monitoring endpoints on `listener` until the process exits.
+pub async fn serve(listener: TcpListener, monitor: Arc<Monitor>) -> std::io::Result<()> {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, "Status server listening");
+    }
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/status", get(status))
+        .route("/metrics", get(metrics))
+        .with_state(monitor);
+    axum::serve(listener, app).await
+}
+
+async fn healthz(AxumState(monitor): AxumState<Arc<Monitor>>) -> impl IntoResponse {
+    if monitor.report().healthy {
+        (StatusCode::OK, "ok\n")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "scanner stalled\n")
+    }
+}
+
+async fn status(AxumState(monitor): AxumState<Arc<Monitor>>) -> Json<StatusReport> {
+    Json(monitor.report())
+}
+
+async fn metrics(AxumState(monitor): AxumState<Arc<Monitor>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        monitor.metrics(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn healthy_until_scans_stop() {
+        let start = Utc::now();
+        let interval = Duration::from_secs(10);
+        let at = |s| start + chrono::Duration::seconds(s);
+        assert!(is_healthy(start, None, interval, at(60)));
+        assert!(!is_healthy(start, None, interval, at(61)));
+        assert!(is_healthy(start, Some(at(100)), interval, at(160)));
+        assert!(!is_healthy(start, Some(at(100)), interval, at(161)));
+    }
+
+    #[test]
+    fn upload_results_update_counters_and_status() {
+        let monitor = Monitor::new(Duration::from_secs(5));
+        monitor.advertisement(TiltColor::Red);
+        monitor.upload(
+            "server",
+            3,
+            Duration::from_millis(20),
+            Some("timeout".into()),
+        );
+        monitor.upload(
+            "server",
+            3,
+            Duration::from_millis(20),
+            Some("timeout".into()),
+        );
+        monitor.target_state("server", 3, Some(Instant::now() + Duration::from_secs(4)));
+
+        let report = monitor.report();
+        let status = &report.targets["server"];
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(status.buffered, 3);
+        assert!(status.retry_at.is_some());
+        assert_eq!(
+            status.last_upload.as_ref().unwrap().error.as_deref(),
+            Some("timeout")
+        );
+
+        monitor.upload("server", 3, Duration::from_millis(20), None);
+        assert_eq!(monitor.report().targets["server"].consecutive_failures, 0);
+
+        let text = monitor.metrics();
+        assert!(text.contains("tilt_client_advertisements_total{color=\"Red\"} 1"));
+        assert!(text.contains("tilt_client_uploads_total{result=\"error\",target=\"server\"} 2"));
+        assert!(text.contains("tilt_client_uploads_total{result=\"ok\",target=\"server\"} 1"));
+        assert!(text.contains("tilt_client_buffered_readings{target=\"server\"} 3"));
+    }
+
+    #[test]
+    fn report_lists_latest_reading_per_color_in_color_order() {
+        let monitor = Monitor::new(Duration::from_secs(5));
+        let reading = |color, gravity| TiltReading::new(color, 68.0, gravity, None, Utc::now());
+        monitor.scan_complete(
+            &[
+                reading(TiltColor::Blue, 1.050),
+                reading(TiltColor::Red, 1.040),
+            ],
+            Duration::from_secs(5),
+        );
+        monitor.scan_complete(&[reading(TiltColor::Blue, 1.045)], Duration::from_secs(5));
+
+        let report = monitor.report();
+        let colors: Vec<_> = report.readings.iter().map(|r| r.color).collect();
+        assert_eq!(colors, vec![TiltColor::Red, TiltColor::Blue]);
+        assert!((report.readings[1].gravity - 1.045).abs() < f64::EPSILON);
+        assert!(report.last_scan_at.is_some());
+    }
+}
@@ -0,0 +1,289 @@
+//! Fan-out of readings to upload targets. Each target runs in its own task with its own
+//! on-disk buffer, upload interval and backoff, so a slow or unreachable target never holds
+//! up the scanner or the other targets.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use shared::{TiltColor, TiltReading};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::buffer::{Backoff, ReadingBuffer};
+use crate::config::{Settings, SinkConfig, TargetConfig};
+use crate::status::Monitor;
+use crate::uploader::{StreamUploader, TemplateUploader, UploadError, Uploader};
+
+enum Command {
+    Readings(Vec<TiltReading>),
+    Configure(Arc<Settings>),
+}
+
+/// Sends work to a target's task. Dropping the handle stops the task; readings it has not
+/// uploaded stay in its buffer for the next run.
+pub struct TargetHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    buffered: Arc<AtomicUsize>,
+}
+
+impl TargetHandle {
+    pub fn send(&self, readings: Vec<TiltReading>) {
+        let _ = self.commands.send(Command::Readings(readings));
+    }
+
+    /// Applies reloaded settings, including this target's entry in them.
+    pub fn configure(&self, settings: Arc<Settings>) {
+        let _ = self.commands.send(Command::Configure(settings));
+    }
+
+    /// Readings waiting in the target's buffer.
+    pub fn buffered(&self) -> usize {
+        self.buffered.load(Ordering::Relaxed)
+    }
+}
+
+/// Opens the target's buffer and starts its task. With `throttle` unset every reading sent
+/// is uploaded, ignoring the upload intervals.
+pub fn spawn(
+    config: TargetConfig,
+    settings: Arc<Settings>,
+    throttle: bool,
+    monitor: Arc<Monitor>,
+) -> io::Result<TargetHandle> {
+    let buffer = ReadingBuffer::open(
+        settings.target_buffer_dir(&config.name),
+        settings.buffer_limits,
+    )?;
+    if !buffer.is_empty() {
+        tracing::info!(
+            target_name = %config.name,
+            buffered = buffer.len(),
+            "Replaying readings buffered by a previous run"
+        );
+    }
+    let (commands, receiver) = mpsc::unbounded_channel();
+    let buffered = Arc::new(AtomicUsize::new(buffer.len()));
+    monitor.target_state(&config.name, buffer.len(), None);
+    let target = Target {
+        buffered: buffered.clone(),
+        sink: Sink::new(&config.sink),
+        config,
+        settings,
+        buffer,
+        backoff: Backoff::default(),
+        retry_at: None,
+        last_queued: HashMap::new(),
+        throttle,
+        monitor,
+    };
+    tokio::spawn(target.run(receiver));
+    Ok(TargetHandle { commands, buffered })
+}
+
+enum Sink {
+    Server(Uploader),
+    Stream(StreamUploader),
+    Http(TemplateUploader),
+}
+
+impl Sink {
+    fn new(config: &SinkConfig) -> Self {
+        match config {
+            SinkConfig::Server { url, api_key } => {
+                Sink::Server(Uploader::new(url, api_key.clone()))
+            }
+            SinkConfig::CustomStream { url } => Sink::Stream(StreamUploader::new(url)),
+            SinkConfig::Http { url, headers, body } => {
+                Sink::Http(TemplateUploader::new(url, headers.clone(), body.clone()))
+            }
+        }
+    }
+}
+
+struct Target {
+    config: TargetConfig,
+    settings: Arc<Settings>,
+    sink: Sink,
+    buffer: ReadingBuffer,
+    backoff: Backoff,
+    /// Set after a failed upload; nothing is sent before then.
+    retry_at: Option<Instant>,
+    last_queued: HashMap<TiltColor, Instant>,
+    throttle: bool,
+    /// Shared with the handle so the buffer depth can be reported in heartbeats.
+    buffered: Arc<AtomicUsize>,
+    monitor: Arc<Monitor>,
+}
+
+impl Target {
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            let retry_at = self.retry_at;
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(Command::Readings(readings)) => self.enqueue(readings).await,
+                    Some(Command::Configure(settings)) => self.configure(settings),
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(retry_at.unwrap_or_else(Instant::now)),
+                    if retry_at.is_some() =>
+                {
+                    self.retry_at = None;
+                }
+            }
+            if self.retry_at.is_none() {
+                self.upload_buffered().await;
+            }
+            self.buffered.store(self.buffer.len(), Ordering::Relaxed);
+            self.monitor
+                .target_state(&self.config.name, self.buffer.len(), self.retry_at);
+        }
+        tracing::info!(target_name = %self.config.name, "Upload target stopped");
+    }
+
+    fn configure(&mut self, settings: Arc<Settings>) {
+        let Some(config) = settings
+            .targets()
+            .into_iter()
+            .find(|t| t.name == self.config.name)
+        else {
+            return;
+        };
+        if config.sink != self.config.sink {
+            self.sink = Sink::new(&config.sink);
+            self.retry_at = None;
+            self.backoff.reset();
+        }
+        self.buffer.set_limits(settings.buffer_limits);
+        self.config = config;
+        self.settings = settings;
+    }
+
+    /// Writes readings that are due for this target to its buffer. If the disk write fails
+    /// they are uploaded directly instead, so a full or read-only disk doesn't stop readings
+    /// reaching the target while it is up.
+    async fn enqueue(&mut self, mut readings: Vec<TiltReading>) {
+        if self.throttle {
+            let now = Instant::now();
+            readings.retain(|r| {
+                let interval = self.settings.upload_interval_for(&self.config, r.color);
+                let due = self
+                    .last_queued
+                    .get(&r.color)
+                    .is_none_or(|t| now.duration_since(*t) >= interval);
+                if due {
+                    self.last_queued.insert(r.color, now);
+                }
+                due
+            });
+        }
+        if readings.is_empty() {
+            tracing::debug!(target_name = %self.config.name, "No readings due (throttled)");
+            return;
+        }
+
+        let Err(e) = self.buffer.push_batch(&readings) else {
+            return;
+        };
+        tracing::warn!(
+            target_name = %self.config.name,
+            "Failed to buffer readings, uploading them unbuffered: {e}"
+        );
+        if let Err(e) = self.upload(&readings).await {
+            tracing::warn!(
+                target_name = %self.config.name,
+                count = readings.len(),
+                "Upload failed, readings lost: {e}"
+            );
+        }
+    }
+
+    /// Uploads the buffer oldest first in chunks of at most `upload_chunk_size`,
+    /// acknowledging each chunk once the target accepts it. Stops at the first failure and
+    /// backs off; the failed chunk stays buffered for the retry.
+    async fn upload_buffered(&mut self) {
+        while !self.buffer.is_empty() {
+            let chunk = match self.buffer.next_chunk(self.settings.upload_chunk_size) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    tracing::error!(
+                        target_name = %self.config.name,
+                        "Failed to read buffered readings: {e}"
+                    );
+                    return;
+                }
+            };
+            if !chunk.readings.is_empty() {
+                tracing::info!(
+                    target_name = %self.config.name,
+                    count = chunk.readings.len(),
+                    buffered = self.buffer.len(),
+                    "Uploading readings"
+                );
+                match self.upload(&chunk.readings).await {
+                    Ok(()) => self.backoff.reset(),
+                    Err(e) => {
+                        let delay = self.backoff.next_delay();
+                        tracing::warn!(target_name = %self.config.name, "Upload failed: {e}");
+                        tracing::info!(
+                            target_name = %self.config.name,
+                            ?delay,
+                            buffered = self.buffer.len(),
+                            "Backing off"
+                        );
+                        self.retry_at = Some(Instant::now() + delay);
+                        return;
+                    }
+                }
+            }
+            if let Err(e) = self.buffer.ack(&chunk) {
+                tracing::error!(
+                    target_name = %self.config.name,
+                    "Failed to acknowledge uploaded readings: {e}"
+                );
+                return;
+            }
+        }
+    }
+
+    /// Uploads `readings` to the sink, recording the attempt with the monitor.
+    async fn upload(&self, readings: &[TiltReading]) -> Result<(), UploadError> {
+        let started = Instant::now();
+        let result = self.send(readings).await;
+        self.monitor.upload(
+            &self.config.name,
+            readings.len(),
+            started.elapsed(),
+            result.as_ref().err().map(ToString::to_string),
+        );
+        result
+    }
+
+    async fn send(&self, readings: &[TiltReading]) -> Result<(), UploadError> {
+        let settings = self.settings.clone();
+        let name = move |color| settings.label(color);
+        match &self.sink {
+            Sink::Server(uploader) => {
+                let response = uploader.upload_batch(readings).await?;
+                tracing::info!(
+                    target_name = %self.config.name,
+                    inserted = response.inserted,
+                    duplicates = response.duplicates,
+                    replaced = response.replaced,
+                    "Upload successful"
+                );
+            }
+            Sink::Stream(uploader) => {
+                uploader.upload(readings, &name).await?;
+                tracing::info!(target_name = %self.config.name, count = readings.len(), "Upload successful");
+            }
+            Sink::Http(uploader) => {
+                uploader.upload(readings, &name).await?;
+                tracing::info!(target_name = %self.config.name, count = readings.len(), "Upload successful");
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use reqwest::StatusCode;
-use shared::{CreateReadingsBatch, TiltReading};
+use serde_json::json;
+use shared::{
+    CreateReadingsBatch, GatewayHeartbeat, ImportReport, ReadingsBatchResult, TiltColor,
+    TiltReading,
+};
 
 #[derive(Debug)]
 pub enum UploadError {
@@ -28,9 +33,11 @@ impl From<reqwest::Error> for UploadError {
     }
 }
 
+#[derive(Clone)]
 pub struct Uploader {
     client: reqwest::Client,
     readings_url: String,
+    heartbeat_url: String,
     api_key: Option<String>,
 }
 
@@ -40,6 +47,7 @@ impl Uploader {
         Self {
             client: reqwest::Client::new(),
             readings_url: format!("{base}/api/v1/readings"),
+            heartbeat_url: format!("{base}/api/v1/gateways/heartbeat"),
             api_key,
         }
     }
@@ -47,7 +55,7 @@ impl Uploader {
     pub async fn upload_batch(
         &self,
         readings: &[TiltReading],
-    ) -> Result<serde_json::Value, UploadError> {
+    ) -> Result<ReadingsBatchResult, UploadError> {
         let batch = CreateReadingsBatch(readings.to_vec());
 
         tracing::debug!(
@@ -70,11 +78,249 @@ impl Uploader {
         tracing::debug!(%status, %body, "Upload response");
 
         if status.is_success() {
-            let value: serde_json::Value =
-                serde_json::from_str(&body).unwrap_or(serde_json::json!({ "raw": body }));
-            Ok(value)
+            // Older servers reply with only `{"count": n}`; the readings are stored either way.
+            Ok(serde_json::from_str(&body).unwrap_or_default())
         } else {
             Err(UploadError::ServerError(status, body))
         }
     }
+
+    /// Reports this client's status to `POST /gateways/heartbeat`.
+    pub async fn heartbeat(&self, heartbeat: &GatewayHeartbeat) -> Result<(), UploadError> {
+        let mut request = self.client.post(&self.heartbeat_url).json(heartbeat);
+        if let Some(ref key) = self.api_key {
+            request = request.header("X-API-Key", key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(UploadError::ServerError(status, response.text().await?));
+        }
+        Ok(())
+    }
+
+    /// Sends a Tilt CSV log to `POST /readings/import` and returns the server's report.
+    pub async fn import_log(
+        &self,
+        csv: String,
+        color: Option<TiltColor>,
+        utc_offset_minutes: i32,
+        create_brews: bool,
+    ) -> Result<ImportReport, UploadError> {
+        let mut url = format!(
+            "{}/import?utc_offset_minutes={utc_offset_minutes}&create_brews={create_brews}",
+            self.readings_url
+        );
+        if let Some(color) = color {
+            url.push_str(&format!("&color={color:?}"));
+        }
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "text/csv")
+            .body(csv);
+        if let Some(ref key) = self.api_key {
+            request = request.header("X-API-Key", key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(UploadError::ServerError(status, body));
+        }
+        serde_json::from_str(&body).map_err(|_| UploadError::ServerError(status, body))
+    }
+}
+
+/// Posts one JSON document per reading to `url` and fails on the first error response.
+async fn post_each(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &BTreeMap<String, String>,
+    bodies: Vec<serde_json::Value>,
+) -> Result<(), UploadError> {
+    for body in bodies {
+        let mut request = client.post(url).json(&body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(UploadError::ServerError(status, response.text().await?));
+        }
+    }
+    Ok(())
+}
+
+/// Brewfather and Brewer's Friend "custom stream" endpoints, which accept one device reading
+/// per request and identify the device by `name`.
+pub struct StreamUploader {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl StreamUploader {
+    pub fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+        }
+    }
+
+    pub async fn upload(
+        &self,
+        readings: &[TiltReading],
+        name: &(dyn Fn(TiltColor) -> String + Sync),
+    ) -> Result<(), UploadError> {
+        let bodies = readings
+            .iter()
+            .map(|r| {
+                json!({
+                "name": name(r.color),
+                "temp": r.temperature_f,
+                "temp_unit": "F",
+                "gravity": r.gravity,
+                "gravity_unit": "G",
+                "device_source": "Tilt",
+                })
+            })
+            .collect();
+        post_each(&self.client, &self.url, &BTreeMap::new(), bodies).await
+    }
+}
+
+/// Arbitrary HTTP endpoints, posting a body rendered from a JSON template per reading.
+pub struct TemplateUploader {
+    client: reqwest::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+    template: serde_json::Value,
+}
+
+impl TemplateUploader {
+    pub fn new(url: &str, headers: BTreeMap<String, String>, template: serde_json::Value) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            headers,
+            template,
+        }
+    }
+
+    pub async fn upload(
+        &self,
+        readings: &[TiltReading],
+        name: &(dyn Fn(TiltColor) -> String + Sync),
+    ) -> Result<(), UploadError> {
+        let bodies = readings
+            .iter()
+            .map(|r| render_template(&self.template, r, &name(r.color)))
+            .collect();
+        post_each(&self.client, &self.url, &self.headers, bodies).await
+    }
+}
+
+/// Fills `{{placeholder}}`s in every string of `template`. A string that is exactly one
+/// placeholder becomes the typed value (a number, or `null` for a missing RSSI); otherwise
+/// the value is interpolated as text. Placeholders: `color`, `name` (the color's alias),
+/// `gravity`, `temperature_f`, `temperature_c`, `rssi`, `recorded_at` and `id`.
+pub fn render_template(
+    template: &serde_json::Value,
+    reading: &TiltReading,
+    name: &str,
+) -> serde_json::Value {
+    use serde_json::Value;
+
+    let value = |key: &str| -> Option<Value> {
+        Some(match key {
+            "color" => json!(format!("{:?}", reading.color)),
+            "name" => json!(name),
+            "gravity" => json!(reading.gravity),
+            "temperature_f" => json!(reading.temperature_f),
+            "temperature_c" => json!((reading.temperature_f - 32.0) * 5.0 / 9.0),
+            "rssi" => json!(reading.rssi),
+            "recorded_at" => json!(reading.recorded_at.to_rfc3339()),
+            "id" => json!(reading.id),
+            _ => return None,
+        })
+    };
+
+    match template {
+        Value::String(text) => {
+            if let Some(key) = text.strip_prefix("{{").and_then(|t| t.strip_suffix("}}"))
+                && let Some(value) = value(key.trim())
+            {
+                return value;
+            }
+            let mut out = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let Some(len) = rest[start..].find("}}") else {
+                    break;
+                };
+                let key = rest[start + 2..start + len].trim();
+                out.push_str(&rest[..start]);
+                match value(key) {
+                    Some(Value::String(s)) => out.push_str(&s),
+                    Some(other) => out.push_str(&other.to_string()),
+                    None => out.push_str(&rest[start..start + len + 2]),
+                }
+                rest = &rest[start + len + 2..];
+            }
+            out.push_str(rest);
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_template(item, reading, name))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), render_template(v, reading, name)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use serde_json::Value;
+
+    fn reading() -> TiltReading {
+        let at: DateTime<Utc> = "2026-10-17T12:00:00Z".parse().unwrap();
+        TiltReading::new(TiltColor::Red, 68.0, 1.050, None, at)
+    }
+
+    #[test]
+    fn template_placeholders_keep_their_type() {
+        let template = json!({
+            "sg": "{{gravity}}",
+            "temp": "{{ temperature_c }}",
+            "rssi": "{{rssi}}",
+            "tags": ["{{color}}", 1],
+        });
+        let body = render_template(&template, &reading(), "FV1");
+        assert_eq!(body["sg"], json!(1.050));
+        assert_eq!(body["temp"], json!(20.0));
+        assert_eq!(body["rssi"], Value::Null);
+        assert_eq!(body["tags"], json!(["Red", 1]));
+    }
+
+    #[test]
+    fn template_interpolates_inside_text() {
+        let template = json!("{{name}} at {{gravity}} on {{recorded_at}} {{unknown}}");
+        assert_eq!(
+            render_template(&template, &reading(), "FV1"),
+            json!("FV1 at 1.05 on 2026-10-17T12:00:00+00:00 {{unknown}}")
+        );
+    }
 }
@@ -5,6 +5,20 @@ mod m20260215_000002_create_brews;
 mod m20260215_000003_create_readings;
 mod m20260219_012142_create_user_sessions;
 mod m20260219_012410_create_api_keys;
+mod m20261017_000001_add_reading_calibration;
+mod m20261017_000002_add_reading_device_kind;
+mod m20261017_000003_add_reading_tx_power;
+mod m20261017_000004_add_brew_stable_since;
+mod m20261017_000005_create_alerts;
+mod m20261017_000006_create_notifications;
+mod m20261017_000007_unique_reading_time;
+mod m20261017_000008_create_gateways;
+mod m20261017_000009_add_reading_gateway;
+mod m20261017_000010_add_ownership;
+mod m20261017_000011_create_teams;
+mod m20261017_000012_add_api_key_restrictions;
+mod m20261017_000013_add_api_key_rotation;
+mod m20261017_000014_create_audit_events;
 
 pub struct Migrator;
 
@@ -17,6 +31,20 @@ impl MigratorTrait for Migrator {
             Box::new(m20260215_000003_create_readings::Migration),
             Box::new(m20260219_012142_create_user_sessions::Migration),
             Box::new(m20260219_012410_create_api_keys::Migration),
+            Box::new(m20261017_000001_add_reading_calibration::Migration),
+            Box::new(m20261017_000002_add_reading_device_kind::Migration),
+            Box::new(m20261017_000003_add_reading_tx_power::Migration),
+            Box::new(m20261017_000004_add_brew_stable_since::Migration),
+            Box::new(m20261017_000005_create_alerts::Migration),
+            Box::new(m20261017_000006_create_notifications::Migration),
+            Box::new(m20261017_000007_unique_reading_time::Migration),
+            Box::new(m20261017_000008_create_gateways::Migration),
+            Box::new(m20261017_000009_add_reading_gateway::Migration),
+            Box::new(m20261017_000010_add_ownership::Migration),
+            Box::new(m20261017_000011_create_teams::Migration),
+            Box::new(m20261017_000012_add_api_key_restrictions::Migration),
+            Box::new(m20261017_000013_add_api_key_rotation::Migration),
+            Box::new(m20261017_000014_create_audit_events::Migration),
         ]
     }
 }
@@ -74,7 +74,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
-enum ApiKeys {
+pub(crate) enum ApiKeys {
     Table,
     Id,
     Name,
@@ -0,0 +1,130 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260215_000001_create_hydrometers::Hydrometers;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Readings::Table)
+                    .add_column(ColumnDef::new(Readings::RawTemperatureF).double().null())
+                    .add_column(ColumnDef::new(Readings::RawGravity).double().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing readings were stored uncorrected, so their raw values are the stored values.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE readings SET raw_temperature_f = temperature_f, raw_gravity = gravity",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Readings::Table)
+                    .modify_column(
+                        ColumnDef::new(Readings::RawTemperatureF)
+                            .double()
+                            .not_null(),
+                    )
+                    .modify_column(ColumnDef::new(Readings::RawGravity).double().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CalibrationPoints::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CalibrationPoints::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(CalibrationPoints::HydrometerId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CalibrationPoints::RawGravity)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CalibrationPoints::ActualGravity)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CalibrationPoints::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_calibration_points_hydrometer_id")
+                            .from(CalibrationPoints::Table, CalibrationPoints::HydrometerId)
+                            .to(Hydrometers::Table, Hydrometers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_calibration_points_hydrometer_id")
+                    .table(CalibrationPoints::Table)
+                    .col(CalibrationPoints::HydrometerId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CalibrationPoints::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Readings::Table)
+                    .drop_column(Readings::RawTemperatureF)
+                    .drop_column(Readings::RawGravity)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Readings {
+    Table,
+    RawTemperatureF,
+    RawGravity,
+}
+
+#[derive(DeriveIden)]
+enum CalibrationPoints {
+    Table,
+    Id,
+    HydrometerId,
+    RawGravity,
+    ActualGravity,
+    CreatedAt,
+}
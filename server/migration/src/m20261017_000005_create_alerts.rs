@@ -0,0 +1,182 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260215_000001_create_hydrometers::Hydrometers;
+use super::m20260215_000002_create_brews::Brews;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlertRules::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AlertRules::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(ColumnDef::new(AlertRules::Name).string().not_null())
+                    .col(ColumnDef::new(AlertRules::BrewId).uuid().null())
+                    .col(ColumnDef::new(AlertRules::HydrometerId).uuid().null())
+                    .col(ColumnDef::new(AlertRules::Kind).string().not_null())
+                    .col(ColumnDef::new(AlertRules::Threshold).double().null())
+                    .col(ColumnDef::new(AlertRules::DurationMinutes).integer().null())
+                    .col(
+                        ColumnDef::new(AlertRules::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(ColumnDef::new(AlertRules::CreatedBy).string().not_null())
+                    .col(
+                        ColumnDef::new(AlertRules::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .col(
+                        ColumnDef::new(AlertRules::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_alert_rules_brew_id")
+                            .from(AlertRules::Table, AlertRules::BrewId)
+                            .to(Brews::Table, Brews::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_alert_rules_hydrometer_id")
+                            .from(AlertRules::Table, AlertRules::HydrometerId)
+                            .to(Hydrometers::Table, Hydrometers::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alert_rules_brew_id")
+                    .table(AlertRules::Table)
+                    .col(AlertRules::BrewId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alert_rules_hydrometer_id")
+                    .table(AlertRules::Table)
+                    .col(AlertRules::HydrometerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Alerts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Alerts::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(ColumnDef::new(Alerts::RuleId).uuid().not_null())
+                    .col(ColumnDef::new(Alerts::State).string().not_null())
+                    .col(ColumnDef::new(Alerts::Message).text().not_null())
+                    .col(ColumnDef::new(Alerts::Value).double().null())
+                    .col(
+                        ColumnDef::new(Alerts::FiredAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Alerts::ResolvedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_alerts_rule_id")
+                            .from(Alerts::Table, Alerts::RuleId)
+                            .to(AlertRules::Table, AlertRules::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alerts_rule_id")
+                    .table(Alerts::Table)
+                    .col(Alerts::RuleId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // At most one open alert per rule, so concurrent evaluations can't double-fire.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX idx_alerts_rule_id_firing ON alerts (rule_id) WHERE state = 'Firing'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Alerts::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(AlertRules::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AlertRules {
+    Table,
+    Id,
+    Name,
+    BrewId,
+    HydrometerId,
+    Kind,
+    Threshold,
+    DurationMinutes,
+    Enabled,
+    CreatedBy,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Alerts {
+    Table,
+    Id,
+    RuleId,
+    State,
+    Message,
+    Value,
+    FiredAt,
+    ResolvedAt,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const INDEX: &str = "idx_readings_hydrometer_id_recorded_at";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Retried uploads may already have stored the same reading twice; keep the first copy.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM readings a USING readings b \
+                 WHERE a.hydrometer_id = b.hydrometer_id \
+                 AND a.recorded_at = b.recorded_at \
+                 AND (a.created_at, a.id) > (b.created_at, b.id)",
+            )
+            .await?;
+
+        manager
+            .drop_index(Index::drop().name(INDEX).table(Readings::Table).to_owned())
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name(INDEX)
+                    .table(Readings::Table)
+                    .col(Readings::HydrometerId)
+                    .col(Readings::RecordedAt)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name(INDEX).table(Readings::Table).to_owned())
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name(INDEX)
+                    .table(Readings::Table)
+                    .col(Readings::HydrometerId)
+                    .col(Readings::RecordedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Readings {
+    Table,
+    HydrometerId,
+    RecordedAt,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20260219_012410_create_api_keys::ApiKeys;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Gateways::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Gateways::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(Gateways::ApiKeyId)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Gateways::Version).string().not_null())
+                    .col(ColumnDef::new(Gateways::Hostname).string().null())
+                    .col(ColumnDef::new(Gateways::Adapter).string().null())
+                    .col(
+                        ColumnDef::new(Gateways::BufferedReadings)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Gateways::LastScan).json_binary().null())
+                    .col(ColumnDef::new(Gateways::LastIp).string().null())
+                    .col(
+                        ColumnDef::new(Gateways::FirstSeenAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .col(
+                        ColumnDef::new(Gateways::LastSeenAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .col(
+                        ColumnDef::new(Gateways::OfflineSince)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_gateways_api_key_id")
+                            .from(Gateways::Table, Gateways::ApiKeyId)
+                            .to(ApiKeys::Table, ApiKeys::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Gateways::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Gateways {
+    Table,
+    Id,
+    ApiKeyId,
+    Version,
+    Hostname,
+    Adapter,
+    BufferedReadings,
+    LastScan,
+    LastIp,
+    FirstSeenAt,
+    LastSeenAt,
+    OfflineSince,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20261017_000008_create_gateways::Gateways;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const FOREIGN_KEY: &str = "fk_readings_gateway_id";
+const INDEX: &str = "idx_readings_gateway_id";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Readings::Table)
+                    .add_column(ColumnDef::new(Readings::GatewayId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name(FOREIGN_KEY)
+                    .from(Readings::Table, Readings::GatewayId)
+                    .to(Gateways::Table, Gateways::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name(INDEX)
+                    .table(Readings::Table)
+                    .col(Readings::GatewayId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name(INDEX).table(Readings::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(FOREIGN_KEY)
+                    .table(Readings::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Readings::Table)
+                    .drop_column(Readings::GatewayId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Readings {
+    Table,
+    GatewayId,
+}
@@ -0,0 +1,145 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const PERSONAL_COLOR_INDEX: &str = "idx_hydrometers_owner_color";
+const TEAM_COLOR_INDEX: &str = "idx_hydrometers_team_id_color";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [Tables::Hydrometers, Tables::Brews, Tables::Readings] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            ColumnDef::new(Ownership::Owner)
+                                .string()
+                                .not_null()
+                                .default(""),
+                        )
+                        .add_column(ColumnDef::new(Ownership::TeamId).uuid().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tables::ApiKeys)
+                    .add_column(ColumnDef::new(Ownership::TeamId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing data predates ownership: give it to whoever set the instance up, i.e. the
+        // creator of the oldest API key or, failing that, the first user to sign in.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE hydrometers SET owner = COALESCE( \
+             (SELECT created_by FROM api_keys ORDER BY created_at LIMIT 1), \
+             (SELECT user_sub FROM user_sessions ORDER BY created_at LIMIT 1), '')",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE brews b SET owner = h.owner FROM hydrometers h WHERE h.id = b.hydrometer_id",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE readings r SET owner = h.owner FROM hydrometers h WHERE h.id = r.hydrometer_id",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "ALTER TABLE hydrometers DROP CONSTRAINT IF EXISTS hydrometers_color_key",
+        )
+        .await?;
+        // A color is unique among a user's personal hydrometers and among a team's, so every
+        // member's keys upload into the same team hydrometer.
+        db.execute_unprepared(&format!(
+            "CREATE UNIQUE INDEX {PERSONAL_COLOR_INDEX} ON hydrometers (owner, color) \
+             WHERE team_id IS NULL"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "CREATE UNIQUE INDEX {TEAM_COLOR_INDEX} ON hydrometers (team_id, color) \
+             WHERE team_id IS NOT NULL"
+        ))
+        .await?;
+
+        for (table, index) in [
+            (Tables::Brews, "idx_brews_owner"),
+            (Tables::Readings, "idx_readings_owner"),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(index)
+                        .table(table)
+                        .col(Ownership::Owner)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for index in [PERSONAL_COLOR_INDEX, TEAM_COLOR_INDEX] {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(index)
+                        .table(Tables::Hydrometers)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        // Fails if two owners have registered the same color, as it should.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE hydrometers ADD CONSTRAINT hydrometers_color_key UNIQUE (color)",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tables::ApiKeys)
+                    .drop_column(Ownership::TeamId)
+                    .to_owned(),
+            )
+            .await?;
+        // Dropping the columns drops their indexes too.
+        for table in [Tables::Readings, Tables::Brews, Tables::Hydrometers] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(Ownership::Owner)
+                        .drop_column(Ownership::TeamId)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The tables gaining ownership columns.
+#[derive(DeriveIden, Clone, Copy)]
+pub(crate) enum Tables {
+    Hydrometers,
+    Brews,
+    Readings,
+    ApiKeys,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Ownership {
+    Owner,
+    TeamId,
+}
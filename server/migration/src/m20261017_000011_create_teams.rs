@@ -0,0 +1,219 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20261017_000010_add_ownership::{Ownership, Tables};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tables whose `team_id` now references `teams`, with their foreign key names.
+const TEAM_FOREIGN_KEYS: [(Tables, &str); 4] = [
+    (Tables::Hydrometers, "fk_hydrometers_team_id"),
+    (Tables::Brews, "fk_brews_team_id"),
+    (Tables::Readings, "fk_readings_team_id"),
+    (Tables::ApiKeys, "fk_api_keys_team_id"),
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Teams::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Teams::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(ColumnDef::new(Teams::Name).string().not_null())
+                    .col(ColumnDef::new(Teams::CreatedBy).string().not_null())
+                    .col(
+                        ColumnDef::new(Teams::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TeamMembers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TeamMembers::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(ColumnDef::new(TeamMembers::TeamId).uuid().not_null())
+                    .col(ColumnDef::new(TeamMembers::UserSub).string().not_null())
+                    .col(ColumnDef::new(TeamMembers::Email).string().not_null())
+                    .col(ColumnDef::new(TeamMembers::Name).string().not_null())
+                    .col(ColumnDef::new(TeamMembers::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(TeamMembers::JoinedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_team_members_team_id")
+                            .from(TeamMembers::Table, TeamMembers::TeamId)
+                            .to(Teams::Table, Teams::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_team_members_team_id_user_sub")
+                    .table(TeamMembers::Table)
+                    .col(TeamMembers::TeamId)
+                    .col(TeamMembers::UserSub)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_team_members_user_sub")
+                    .table(TeamMembers::Table)
+                    .col(TeamMembers::UserSub)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TeamInvitations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TeamInvitations::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(ColumnDef::new(TeamInvitations::TeamId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(TeamInvitations::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(TeamInvitations::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(TeamInvitations::CreatedBy)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TeamInvitations::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .col(
+                        ColumnDef::new(TeamInvitations::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TeamInvitations::AcceptedBy).string().null())
+                    .col(
+                        ColumnDef::new(TeamInvitations::AcceptedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_team_invitations_team_id")
+                            .from(TeamInvitations::Table, TeamInvitations::TeamId)
+                            .to(Teams::Table, Teams::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Deleting a team hands its data back to the members who created it.
+        for (table, name) in TEAM_FOREIGN_KEYS {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name(name)
+                        .from(table, Ownership::TeamId)
+                        .to(Teams::Table, Teams::Id)
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, name) in TEAM_FOREIGN_KEYS {
+            manager
+                .drop_foreign_key(ForeignKey::drop().name(name).table(table).to_owned())
+                .await?;
+        }
+        manager
+            .drop_table(Table::drop().table(TeamInvitations::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TeamMembers::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Teams::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Teams {
+    Table,
+    Id,
+    Name,
+    CreatedBy,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TeamMembers {
+    Table,
+    Id,
+    TeamId,
+    UserSub,
+    Email,
+    Name,
+    Role,
+    JoinedAt,
+}
+
+#[derive(DeriveIden)]
+enum TeamInvitations {
+    Table,
+    Id,
+    TeamId,
+    TokenHash,
+    Role,
+    CreatedBy,
+    CreatedAt,
+    ExpiresAt,
+    AcceptedBy,
+    AcceptedAt,
+}
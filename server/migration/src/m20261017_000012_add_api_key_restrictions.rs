@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing keys could only upload readings, so that is the scope they keep.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiKeys::Table)
+                    .add_column(
+                        ColumnDef::new(ApiKeys::Scopes)
+                            .json_binary()
+                            .not_null()
+                            .extra("DEFAULT '[\"readings:write\"]'::jsonb"),
+                    )
+                    .add_column(ColumnDef::new(ApiKeys::AllowedColors).json_binary().null())
+                    .add_column(ColumnDef::new(ApiKeys::AllowedIps).json_binary().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiKeys::Table)
+                    .drop_column(ApiKeys::Scopes)
+                    .drop_column(ApiKeys::AllowedColors)
+                    .drop_column(ApiKeys::AllowedIps)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiKeys {
+    Table,
+    Scopes,
+    AllowedColors,
+    AllowedIps,
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiKeys::Table)
+                    .add_column(ColumnDef::new(ApiKeys::PreviousKeyHash).string().null())
+                    .add_column(
+                        ColumnDef::new(ApiKeys::PreviousExpiresAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(ApiKeys::RotatedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(ApiKeys::RequestCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(ApiKeys::LastIp).string().null())
+                    .add_column(ColumnDef::new(ApiKeys::LastUserAgent).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_api_keys_previous_key_hash")
+                    .table(ApiKeys::Table)
+                    .col(ApiKeys::PreviousKeyHash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiKeys::Table)
+                    .drop_column(ApiKeys::PreviousKeyHash)
+                    .drop_column(ApiKeys::PreviousExpiresAt)
+                    .drop_column(ApiKeys::RotatedAt)
+                    .drop_column(ApiKeys::RequestCount)
+                    .drop_column(ApiKeys::LastIp)
+                    .drop_column(ApiKeys::LastUserAgent)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiKeys {
+    Table,
+    PreviousKeyHash,
+    PreviousExpiresAt,
+    RotatedAt,
+    RequestCount,
+    LastIp,
+    LastUserAgent,
+}
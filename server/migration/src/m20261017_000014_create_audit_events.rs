@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvents::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(AuditEvents::OccurredAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .extra("DEFAULT now()"),
+                    )
+                    .col(ColumnDef::new(AuditEvents::Actor).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::ApiKeyId).uuid().null())
+                    .col(ColumnDef::new(AuditEvents::Action).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::TargetType).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::TargetId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(AuditEvents::Changes)
+                            .json_binary()
+                            .not_null()
+                            .extra("DEFAULT '{}'::jsonb"),
+                    )
+                    .col(ColumnDef::new(AuditEvents::Ip).string().null())
+                    .col(ColumnDef::new(AuditEvents::Owner).string().not_null())
+                    .col(ColumnDef::new(AuditEvents::TeamId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_owner_occurred_at")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::Owner)
+                    .col(AuditEvents::OccurredAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_team_id")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::TeamId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_target")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::TargetType)
+                    .col(AuditEvents::TargetId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Retention deletes by age across all owners.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_occurred_at")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::OccurredAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditEvents {
+    Table,
+    Id,
+    OccurredAt,
+    Actor,
+    ApiKeyId,
+    Action,
+    TargetType,
+    TargetId,
+    Changes,
+    Ip,
+    Owner,
+    TeamId,
+}
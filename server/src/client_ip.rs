@@ -0,0 +1,103 @@
+//! The address a request came from. This is the connecting peer unless the peer is one of
+//! the reverse proxies listed in `TRUSTED_PROXIES`, in which case the proxy's `X-Real-IP`
+//! header is believed instead. Anyone can send that header, so it is ignored otherwise.
+
+use std::net::IpAddr;
+
+use rocket::Request;
+
+use crate::services::api_keys::IpRule;
+
+/// Header the reverse proxy puts the client address in.
+const REAL_IP_HEADER: &str = "X-Real-IP";
+
+#[derive(Debug, Default)]
+pub struct TrustedProxies {
+    rules: Vec<IpRule>,
+}
+
+impl TrustedProxies {
+    /// Comma-separated addresses or CIDR blocks. Unparseable entries are skipped with a
+    /// warning.
+    pub fn from_env() -> Self {
+        let raw = std::env::var("TRUSTED_PROXIES").unwrap_or_default();
+        let rules = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| match entry.parse::<IpRule>() {
+                Ok(rule) => Some(rule),
+                Err(()) => {
+                    tracing::warn!(entry, "ignoring invalid TRUSTED_PROXIES entry");
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The client address for a connection from `peer` carrying `real_ip` in its header.
+    pub fn resolve(&self, peer: Option<IpAddr>, real_ip: Option<&str>) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.rules.iter().any(|rule| rule.contains(peer)) {
+            return Some(peer);
+        }
+        real_ip
+            .and_then(|ip| ip.trim().parse().ok())
+            .or(Some(peer))
+    }
+}
+
+/// The client address of `req`; see the module docs.
+pub fn client_ip(req: &Request<'_>) -> Option<IpAddr> {
+    let peer = req.remote().map(|addr| addr.ip());
+    match req.rocket().state::<TrustedProxies>() {
+        Some(proxies) => proxies.resolve(peer, req.headers().get_one(REAL_IP_HEADER)),
+        None => peer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::api_keys::{ApiKeyRestrictions, ApiKeyScope};
+
+    fn proxies(rules: &[&str]) -> TrustedProxies {
+        TrustedProxies {
+            rules: rules.iter().map(|r| r.parse().unwrap()).collect(),
+        }
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn forged_real_ip_does_not_pass_an_allow_list() {
+        let restrictions = ApiKeyRestrictions {
+            allowed_ips: Some(vec!["192.168.1.0/24".to_string()]),
+            ..ApiKeyRestrictions::default()
+        };
+        let client = TrustedProxies::default().resolve(ip("203.0.113.7"), Some("192.168.1.20"));
+        assert_eq!(client, ip("203.0.113.7"));
+        assert!(!restrictions.permits(ApiKeyScope::ReadingsWrite, client));
+
+        // Nor does it from a peer that isn't one of the trusted proxies.
+        let client = proxies(&["10.0.0.1"]).resolve(ip("203.0.113.7"), Some("192.168.1.20"));
+        assert!(!restrictions.permits(ApiKeyScope::ReadingsWrite, client));
+    }
+
+    #[test]
+    fn trusted_proxies_forward_the_client_address() {
+        let proxies = proxies(&["10.0.0.0/8", "::1"]);
+        assert_eq!(
+            proxies.resolve(ip("10.1.2.3"), Some("192.168.1.20")),
+            ip("192.168.1.20")
+        );
+        assert_eq!(proxies.resolve(ip("::1"), Some(" 2001:db8::1 ")), ip("2001:db8::1"));
+        // A proxy that sends no usable address is the client itself.
+        assert_eq!(proxies.resolve(ip("10.1.2.3"), None), ip("10.1.2.3"));
+        assert_eq!(proxies.resolve(ip("10.1.2.3"), Some("bogus")), ip("10.1.2.3"));
+        assert_eq!(proxies.resolve(None, Some("192.168.1.20")), None);
+    }
+}
@@ -0,0 +1,278 @@
+//! In-process fan-out of live events to `GET /readings/stream` subscribers. Recent events
+//! are kept so a reconnecting client can resume from its `Last-Event-ID`.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rocket::response::stream::Event;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use shared::{AlertResponse, BrewResponse, ReadingsEvent};
+
+/// Events kept for `Last-Event-ID` resume.
+const HISTORY_CAPACITY: usize = 1000;
+/// Events a slow subscriber may fall behind by before it is disconnected to resume.
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    Readings(ReadingsEvent),
+    Alert(AlertResponse),
+    BrewStatus(BrewResponse),
+}
+
+impl StreamEvent {
+    /// SSE `event:` name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Readings(_) => "readings",
+            StreamEvent::Alert(_) => "alert",
+            StreamEvent::BrewStatus(_) => "brew",
+        }
+    }
+
+    /// The brew and hydrometer the event is about.
+    fn subjects(&self) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            StreamEvent::Readings(e) => (e.brew_id, Some(e.hydrometer_id)),
+            StreamEvent::Alert(a) => (a.brew_id, a.hydrometer_id),
+            StreamEvent::BrewStatus(b) => (Some(b.id), Some(b.hydrometer_id)),
+        }
+    }
+
+    /// Whether the event concerns the given brew and/or hydrometer. `None` filters match all.
+    pub fn matches(&self, brew_id: Option<Uuid>, hydrometer_id: Option<Uuid>) -> bool {
+        let (event_brew, event_hydrometer) = self.subjects();
+        brew_id.is_none_or(|id| event_brew == Some(id))
+            && hydrometer_id.is_none_or(|id| event_hydrometer == Some(id))
+    }
+}
+
+/// The brews and hydrometers a subscriber can see. Events about anything else are not sent.
+/// Resolved when the subscriber connects and again whenever [`EventBus::visibility_version`]
+/// moves on.
+#[derive(Debug, Default)]
+pub struct Audience {
+    pub brews: HashSet<Uuid>,
+    pub hydrometers: HashSet<Uuid>,
+}
+
+impl Audience {
+    pub fn admits(&self, event: &StreamEvent) -> bool {
+        let (brew_id, hydrometer_id) = event.subjects();
+        brew_id.is_some_and(|id| self.brews.contains(&id))
+            || hydrometer_id.is_some_and(|id| self.hydrometers.contains(&id))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    pub id: u64,
+    pub event: StreamEvent,
+}
+
+impl Envelope {
+    pub fn to_sse(&self) -> Event {
+        let event = match &self.event {
+            StreamEvent::Readings(e) => Event::json(e),
+            StreamEvent::Alert(a) => Event::json(a),
+            StreamEvent::BrewStatus(b) => Event::json(b),
+        };
+        event.event(self.event.name()).id(self.id.to_string())
+    }
+}
+
+struct History {
+    next_id: u64,
+    recent: VecDeque<Envelope>,
+}
+
+/// Cheap to clone; all clones publish to the same subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Envelope>,
+    history: Arc<Mutex<History>>,
+    visibility: Arc<AtomicU64>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        // Seed ids from the clock so they keep increasing across restarts and a stale
+        // Last-Event-ID from before a restart replays the whole history.
+        let next_id = chrono::Utc::now().timestamp_millis().max(1) as u64;
+        Self {
+            sender,
+            history: Arc::new(Mutex::new(History {
+                next_id,
+                recent: VecDeque::with_capacity(HISTORY_CAPACITY),
+            })),
+            visibility: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Tells subscribers that who can see which brews and hydrometers may have changed: one
+    /// was created, or a team membership was added or removed.
+    pub fn visibility_changed(&self) {
+        self.visibility.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Bumped by [`Self::visibility_changed`]; subscribers re-resolve their [`Audience`] when
+    /// it differs from the value they resolved it at.
+    pub fn visibility_version(&self) -> u64 {
+        self.visibility.load(Ordering::Relaxed)
+    }
+
+    pub fn publish(&self, event: StreamEvent) {
+        let mut history = self.history.lock().expect("event history poisoned");
+        let envelope = Envelope {
+            id: history.next_id,
+            event,
+        };
+        history.next_id += 1;
+        if history.recent.len() == HISTORY_CAPACITY {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(envelope.clone());
+        // No receivers just means nobody is listening.
+        let _ = self.sender.send(envelope);
+    }
+
+    /// Returns the events after `last_event_id` still in history, plus a receiver for
+    /// everything published afterwards. Both are taken under the same lock so nothing is
+    /// missed or repeated between them.
+    pub fn subscribe(
+        &self,
+        last_event_id: Option<u64>,
+    ) -> (Vec<Envelope>, broadcast::Receiver<Envelope>) {
+        let history = self.history.lock().expect("event history poisoned");
+        let receiver = self.sender.subscribe();
+        let replay = match last_event_id {
+            Some(last) => history
+                .recent
+                .iter()
+                .filter(|e| e.id > last)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        (replay, receiver)
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn readings_event(hydrometer_id: Uuid, brew_id: Option<Uuid>) -> StreamEvent {
+        StreamEvent::Readings(ReadingsEvent {
+            hydrometer_id,
+            brew_id,
+            readings: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn filters_match_brew_and_hydrometer() {
+        let (hydrometer, brew) = (Uuid::new_v4(), Uuid::new_v4());
+        let event = readings_event(hydrometer, Some(brew));
+        assert!(event.matches(None, None));
+        assert!(event.matches(Some(brew), None));
+        assert!(event.matches(None, Some(hydrometer)));
+        assert!(event.matches(Some(brew), Some(hydrometer)));
+        assert!(!event.matches(Some(Uuid::new_v4()), None));
+        assert!(!event.matches(None, Some(Uuid::new_v4())));
+        assert!(!readings_event(hydrometer, None).matches(Some(brew), None));
+    }
+
+    #[test]
+    fn alert_scoped_to_hydrometer_matches_hydrometer_filter() {
+        let hydrometer = Uuid::new_v4();
+        let alert = StreamEvent::Alert(AlertResponse {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            rule_name: "Too warm".to_string(),
+            kind: shared::AlertRuleKind::TemperatureAbove,
+            brew_id: None,
+            hydrometer_id: Some(hydrometer),
+            state: shared::AlertState::Firing,
+            message: String::new(),
+            value: None,
+            fired_at: Utc::now(),
+            resolved_at: None,
+        });
+        assert_eq!(alert.name(), "alert");
+        assert!(alert.matches(None, Some(hydrometer)));
+        assert!(!alert.matches(Some(Uuid::new_v4()), None));
+    }
+
+    #[test]
+    fn audience_admits_events_about_visible_brews_or_hydrometers() {
+        let (hydrometer, brew) = (Uuid::new_v4(), Uuid::new_v4());
+        let audience = Audience {
+            brews: HashSet::from([brew]),
+            hydrometers: HashSet::from([hydrometer]),
+        };
+        assert!(audience.admits(&readings_event(hydrometer, None)));
+        assert!(audience.admits(&readings_event(Uuid::new_v4(), Some(brew))));
+        assert!(!audience.admits(&readings_event(Uuid::new_v4(), None)));
+        assert!(!Audience::default().admits(&readings_event(hydrometer, Some(brew))));
+    }
+
+    #[test]
+    fn subscribe_replays_after_last_event_id() {
+        let bus = EventBus::new();
+        let hydrometer = Uuid::new_v4();
+        for _ in 0..3 {
+            bus.publish(readings_event(hydrometer, None));
+        }
+        let (all, _) = bus.subscribe(Some(0));
+        assert_eq!(all.len(), 3);
+        assert!(all.windows(2).all(|w| w[1].id == w[0].id + 1));
+
+        let (rest, _) = bus.subscribe(Some(all[0].id));
+        assert_eq!(
+            rest.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![all[1].id, all[2].id]
+        );
+
+        let (none, _) = bus.subscribe(None);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn subscriber_receives_later_events() {
+        let bus = EventBus::new();
+        let (_, mut receiver) = bus.subscribe(None);
+        bus.publish(readings_event(Uuid::new_v4(), None));
+        let envelope = receiver.try_recv().unwrap();
+        assert_eq!(envelope.event.name(), "readings");
+    }
+
+    #[test]
+    fn visibility_changes_are_shared_between_clones() {
+        let bus = EventBus::new();
+        let before = bus.visibility_version();
+        bus.clone().visibility_changed();
+        assert_ne!(bus.visibility_version(), before);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let bus = EventBus::new();
+        for _ in 0..HISTORY_CAPACITY + 5 {
+            bus.publish(readings_event(Uuid::new_v4(), None));
+        }
+        let (replay, _) = bus.subscribe(Some(0));
+        assert_eq!(replay.len(), HISTORY_CAPACITY);
+    }
+}
@@ -0,0 +1,49 @@
+use rocket::{
+    Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use crate::events::{EventBus, StreamEvent};
+use crate::services::alert_service;
+
+/// Re-evaluates every alert rule once a minute so time-based rules (no reading, stalled
+/// gravity) fire and resolve even when no new readings arrive.
+pub struct AlertSweeper;
+
+#[rocket::async_trait]
+impl Fairing for AlertSweeper {
+    fn info(&self) -> Info {
+        Info {
+            name: "Alert Sweeper",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<rocket::Orbit>) {
+        let db = rocket
+            .state::<DatabaseConnection>()
+            .expect("DatabaseConnection not managed")
+            .clone();
+        let bus = rocket
+            .state::<EventBus>()
+            .expect("EventBus not managed")
+            .clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                match alert_service::evaluate_all(&db).await {
+                    Ok(transitions) => {
+                        for alert in transitions {
+                            bus.publish(StreamEvent::Alert(alert));
+                        }
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Alert sweep failed"),
+                }
+            }
+        });
+    }
+}
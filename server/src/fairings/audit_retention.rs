@@ -0,0 +1,52 @@
+use rocket::{
+    Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use crate::services::audit_service::{self, AuditConfig};
+
+/// Deletes audit events older than `AUDIT_RETENTION_DAYS` once a day.
+pub struct AuditRetention;
+
+#[rocket::async_trait]
+impl Fairing for AuditRetention {
+    fn info(&self) -> Info {
+        Info {
+            name: "Audit Retention",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<rocket::Orbit>) {
+        let db = rocket
+            .state::<DatabaseConnection>()
+            .expect("DatabaseConnection not managed")
+            .clone();
+        let config = rocket
+            .state::<AuditConfig>()
+            .expect("AuditConfig not managed")
+            .clone();
+        if config.retention.is_none() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(24 * 3600));
+            loop {
+                interval.tick().await;
+                match audit_service::delete_expired(&db, &config).await {
+                    Ok(n) => {
+                        if n > 0 {
+                            tracing::info!(deleted = n, "Deleted expired audit events");
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Audit retention cleanup failed");
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,60 @@
+use rocket::{
+    Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use crate::events::{EventBus, StreamEvent};
+use crate::services::brew_service;
+use crate::services::fermentation_service::{self, CompletionConfig};
+use crate::tenant::Tenant;
+
+pub struct FermentationMonitor;
+
+#[rocket::async_trait]
+impl Fairing for FermentationMonitor {
+    fn info(&self) -> Info {
+        Info {
+            name: "Fermentation Monitor",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<rocket::Orbit>) {
+        let db = rocket
+            .state::<DatabaseConnection>()
+            .expect("DatabaseConnection not managed")
+            .clone();
+        let bus = rocket
+            .state::<EventBus>()
+            .expect("EventBus not managed")
+            .clone();
+        let config = CompletionConfig::from_env();
+        tracing::info!(
+            stable_for_hours = config.stable_for.num_hours(),
+            tolerance = config.tolerance,
+            auto_complete = config.auto_complete,
+            "Fermentation monitor configured"
+        );
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(900));
+            loop {
+                interval.tick().await;
+                let changed = match fermentation_service::check_active_brews(&db, &config).await {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Fermentation check failed");
+                        continue;
+                    }
+                };
+                for brew in changed {
+                    if let Ok(Some(response)) = brew_service::find_by_id(&db, &Tenant::user(&brew.owner), brew.id).await {
+                        bus.publish(StreamEvent::BrewStatus(response));
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,47 @@
+use rocket::{
+    Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use crate::services::gateway_service::{self, GatewayConfig};
+
+/// Checks once a minute for gateways whose heartbeats have stopped, so their owners hear
+/// about a dead Pi instead of just seeing readings stop.
+pub struct GatewayMonitor;
+
+#[rocket::async_trait]
+impl Fairing for GatewayMonitor {
+    fn info(&self) -> Info {
+        Info {
+            name: "Gateway Monitor",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<rocket::Orbit>) {
+        let db = rocket
+            .state::<DatabaseConnection>()
+            .expect("DatabaseConnection not managed")
+            .clone();
+        let config = rocket
+            .state::<GatewayConfig>()
+            .expect("GatewayConfig not managed")
+            .clone();
+        tracing::info!(
+            offline_after_seconds = config.offline_after.num_seconds(),
+            "Gateway monitor configured"
+        );
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = gateway_service::mark_offline(&db, &config).await {
+                    tracing::warn!(error = %e, "Gateway liveness check failed");
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,45 @@
+use rocket::{
+    Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+};
+use std::time::Instant;
+
+use crate::metrics::Metrics;
+
+/// Records every response in the managed [`Metrics`], labelled with the matched route's
+/// URI template rather than the request path.
+pub struct HttpMetrics;
+
+#[rocket::async_trait]
+impl Fairing for HttpMetrics {
+    fn info(&self) -> Info {
+        Info {
+            name: "HTTP Metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(metrics) = req.rocket().state::<Metrics>() else {
+            return;
+        };
+        let start = req.local_cache(|| RequestStart(Instant::now()));
+        let route = req
+            .route()
+            .map(|r| r.uri.to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        metrics.observe_request(
+            req.method().as_str(),
+            &route,
+            res.status().code,
+            start.0.elapsed(),
+        );
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RequestStart(Instant);
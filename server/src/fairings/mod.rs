@@ -1,3 +1,9 @@
+pub mod alert_sweeper;
+pub mod audit_retention;
+pub mod fermentation_monitor;
+pub mod gateway_monitor;
+pub mod http_metrics;
+pub mod notification_dispatcher;
 pub mod rate_limit;
 pub mod request_logger;
 pub mod security_headers;
@@ -0,0 +1,45 @@
+use rocket::{
+    Rocket,
+    fairing::{Fairing, Info, Kind},
+};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+use crate::notifications::Notifier;
+use crate::services::notification_service;
+
+/// Sends queued notification deliveries and retries failed ones with backoff.
+pub struct NotificationDispatcher;
+
+#[rocket::async_trait]
+impl Fairing for NotificationDispatcher {
+    fn info(&self) -> Info {
+        Info {
+            name: "Notification Dispatcher",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<rocket::Orbit>) {
+        let db = rocket
+            .state::<DatabaseConnection>()
+            .expect("DatabaseConnection not managed")
+            .clone();
+        let notifier = rocket
+            .state::<Notifier>()
+            .expect("Notifier not managed")
+            .clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            loop {
+                interval.tick().await;
+                match notification_service::deliver_due(&db, &notifier).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::debug!(count, "Dispatched notifications"),
+                    Err(e) => tracing::warn!(error = %e, "Notification dispatch failed"),
+                }
+            }
+        });
+    }
+}
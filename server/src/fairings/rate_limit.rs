@@ -16,6 +16,8 @@ use rocket::{
     outcome::Outcome,
 };
 
+use crate::metrics::Metrics;
+
 type KeyedLimiter = RateLimiter<IpAddr, DefaultKeyedStateStore<IpAddr>, DefaultClock>;
 
 pub struct RateLimit {
@@ -38,7 +40,7 @@ impl RateLimit {
 }
 
 fn client_ip(req: &Request<'_>) -> IpAddr {
-    req.client_ip()
+    crate::client_ip::client_ip(req)
         .unwrap_or(IpAddr::from([127, 0, 0, 1]))
 }
 
@@ -63,14 +65,19 @@ impl Fairing for RateLimit {
         let path = req.uri().path().to_string();
         let ip = client_ip(req);
 
-        if is_auth_path(&path) {
-            if self.auth_limiter.check_key(&ip).is_err() {
-                req.local_cache(|| RateLimitExceeded(true));
-            }
+        let limited = if is_auth_path(&path) {
+            self.auth_limiter.check_key(&ip).is_err().then_some("auth")
         } else if is_api_path(&path) {
-            if self.api_limiter.check_key(&ip).is_err() {
-                req.local_cache(|| RateLimitExceeded(true));
+            self.api_limiter.check_key(&ip).is_err().then_some("api")
+        } else {
+            None
+        };
+
+        if let Some(limiter) = limited {
+            if let Some(metrics) = req.rocket().state::<Metrics>() {
+                metrics.rate_limited(limiter);
             }
+            req.local_cache(|| RateLimitExceeded(true));
         }
     }
 }
@@ -4,6 +4,8 @@ use rocket::{
 };
 use std::time::Instant;
 
+use crate::client_ip::client_ip;
+
 pub struct RequestLogger;
 
 #[rocket::async_trait]
@@ -25,8 +27,7 @@ impl Fairing for RequestLogger {
         let method = req.method();
         let uri = req.uri();
         let status = res.status().code;
-        let ip = req
-            .client_ip()
+        let ip = client_ip(req)
             .map(|ip| ip.to_string())
             .unwrap_or_else(|| "-".to_string());
 
@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use rocket::{
     Request,
     http::Status,
@@ -5,30 +7,79 @@ use rocket::{
 };
 use sea_orm::DatabaseConnection;
 
+use shared::TeamRole;
+
+use crate::client_ip::client_ip;
 use crate::models::entities::api_keys;
-use crate::services::api_keys::{validate_api_key, ApiKeyError};
+use crate::services::api_keys::{ApiKeyError, ApiKeyScope, validate_api_key};
+
+/// What a route needs: the scope an API key must grant, or the role a signed-in user must
+/// have in the team they are acting in.
+pub trait RequiredScope: Send + Sync + 'static {
+    const SCOPE: ApiKeyScope;
+    const ROLE: TeamRole;
+}
+
+pub struct WriteReadings;
+
+impl RequiredScope for WriteReadings {
+    const SCOPE: ApiKeyScope = ApiKeyScope::ReadingsWrite;
+    const ROLE: TeamRole = TeamRole::Brewer;
+}
+
+pub struct ReadReadings;
+
+impl RequiredScope for ReadReadings {
+    const SCOPE: ApiKeyScope = ApiKeyScope::ReadingsRead;
+    const ROLE: TeamRole = TeamRole::Viewer;
+}
+
+pub struct ReadBrews;
+
+impl RequiredScope for ReadBrews {
+    const SCOPE: ApiKeyScope = ApiKeyScope::BrewsRead;
+    const ROLE: TeamRole = TeamRole::Viewer;
+}
+
+/// Managing brews, calibrations and API keys: `admin` for a key, brewer for a user.
+pub struct Admin;
+
+impl RequiredScope for Admin {
+    const SCOPE: ApiKeyScope = ApiKeyScope::Admin;
+    const ROLE: TeamRole = TeamRole::Brewer;
+}
+
+/// Reading calibrations and listing or revoking one's own API keys, which viewers may also do
+/// in a session. A key still needs `admin`.
+pub struct AdminRead;
+
+impl RequiredScope for AdminRead {
+    const SCOPE: ApiKeyScope = ApiKeyScope::Admin;
+    const ROLE: TeamRole = TeamRole::Viewer;
+}
+
+/// The raw key from `X-API-Key` or `Authorization: Bearer`.
+pub fn raw_key<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+    req.headers().get_one("X-API-Key").or_else(|| {
+        req.headers()
+            .get_one("Authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+    })
+}
 
-#[allow(dead_code)]
-pub struct ApiKeyGuard {
+/// A valid API key granting `S`, used from an address it is allowed from. A team key's
+/// creator must still have `S::ROLE` in the team.
+pub struct ApiKeyGuard<S: RequiredScope = WriteReadings> {
     pub key: api_keys::Model,
+    scope: PhantomData<S>,
 }
 
 #[rocket::async_trait]
-impl<'r> FromRequest<'r> for ApiKeyGuard {
+impl<'r, S: RequiredScope> FromRequest<'r> for ApiKeyGuard<S> {
     type Error = ();
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let raw_key = req
-            .headers()
-            .get_one("X-API-Key")
-            .or_else(|| {
-                req.headers()
-                    .get_one("Authorization")
-                    .and_then(|v| v.strip_prefix("Bearer "))
-            })
-            .map(|s| s.to_string());
-
-        let raw_key = match raw_key {
+        let raw_key = match raw_key(req) {
             Some(k) => k,
             None => return Outcome::Error((Status::Unauthorized, ())),
         };
@@ -38,10 +89,15 @@ impl<'r> FromRequest<'r> for ApiKeyGuard {
             _ => return Outcome::Error((Status::InternalServerError, ())),
         };
 
-        match validate_api_key(db, &raw_key).await {
-            Ok(key) => Outcome::Success(ApiKeyGuard { key }),
+        let user_agent = req.headers().get_one("User-Agent");
+        match validate_api_key(db, raw_key, S::SCOPE, S::ROLE, client_ip(req), user_agent).await {
+            Ok(valid) => Outcome::Success(ApiKeyGuard {
+                key: valid.key,
+                scope: PhantomData,
+            }),
             Err(ApiKeyError::Expired) => Outcome::Error((Status::Unauthorized, ())),
             Err(ApiKeyError::Invalid) => Outcome::Error((Status::Unauthorized, ())),
+            Err(ApiKeyError::Forbidden) => Outcome::Error((Status::Forbidden, ())),
             Err(ApiKeyError::Db(_)) => Outcome::Error((Status::InternalServerError, ())),
         }
     }
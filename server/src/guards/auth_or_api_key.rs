@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use rocket::{
     Request,
     http::Status,
@@ -5,19 +7,34 @@ use rocket::{
 };
 use sea_orm::DatabaseConnection;
 
-use crate::services::{
-    api_keys::{validate_api_key, ApiKeyError},
-    sessions,
+use crate::client_ip::client_ip;
+use crate::guards::api_key::{
+    raw_key, Admin, AdminRead, ReadBrews, ReadReadings, RequiredScope, WriteReadings,
 };
-use crate::guards::current_user::SESSION_COOKIE;
-use uuid::Uuid;
+use crate::guards::current_user::CurrentUser;
+use crate::models::entities::api_keys;
+use crate::services::api_keys::{validate_api_key, ApiKeyError};
+use crate::tenant::Tenant;
 
 /// A guard that accepts either a valid session cookie (CurrentUser) OR a valid API key.
-/// Used on the POST /readings endpoint so both the web UI and the Pi client can submit readings.
-pub struct AuthOrApiKey;
+/// Used on the readings endpoints so both the web UI and the Pi client can submit readings,
+/// and on read-only endpoints that dashboards poll with a key. The key must grant `S`; a
+/// signed-in user needs the matching role instead.
+pub struct AuthOrApiKey<S: RequiredScope = WriteReadings> {
+    /// The key the request was made with; `None` for a session.
+    pub api_key: Option<api_keys::Model>,
+    /// Who submitted readings are attributed to: the key's creator, or the signed-in user.
+    pub tenant: Tenant,
+    scope: PhantomData<S>,
+}
+
+pub type ReadingsReader = AuthOrApiKey<ReadReadings>;
+pub type BrewsReader = AuthOrApiKey<ReadBrews>;
+pub type Administrator = AuthOrApiKey<Admin>;
+pub type AdminReader = AuthOrApiKey<AdminRead>;
 
 #[rocket::async_trait]
-impl<'r> FromRequest<'r> for AuthOrApiKey {
+impl<'r, S: RequiredScope> FromRequest<'r> for AuthOrApiKey<S> {
     type Error = ();
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
@@ -27,47 +44,35 @@ impl<'r> FromRequest<'r> for AuthOrApiKey {
         };
 
         // Try API key first (X-API-Key or Authorization: Bearer)
-        let raw_key = req
-            .headers()
-            .get_one("X-API-Key")
-            .or_else(|| {
-                req.headers()
-                    .get_one("Authorization")
-                    .and_then(|v| v.strip_prefix("Bearer "))
-            })
-            .map(|s| s.to_string());
-
-        if let Some(raw_key) = raw_key {
-            match validate_api_key(db, &raw_key).await {
-                Ok(_) => return Outcome::Success(AuthOrApiKey),
+        if let Some(raw_key) = raw_key(req) {
+            let user_agent = req.headers().get_one("User-Agent");
+            match validate_api_key(db, raw_key, S::SCOPE, S::ROLE, client_ip(req), user_agent).await {
+                Ok(valid) => {
+                    return Outcome::Success(AuthOrApiKey {
+                        tenant: Tenant::api_key(&valid.key, valid.team_role).with_ip(client_ip(req)),
+                        api_key: Some(valid.key),
+                        scope: PhantomData,
+                    });
+                }
                 Err(ApiKeyError::Expired) => return Outcome::Error((Status::Unauthorized, ())),
+                Err(ApiKeyError::Forbidden) => return Outcome::Error((Status::Forbidden, ())),
                 Err(ApiKeyError::Invalid) => {} // fall through to session check
                 Err(ApiKeyError::Db(_)) => return Outcome::Error((Status::InternalServerError, ())),
             }
         }
 
-        // Try session cookie
-        let session_id_str = match req.cookies().get_private(SESSION_COOKIE) {
-            Some(c) => c.value().to_string(),
-            None => return Outcome::Error((Status::Unauthorized, ())),
-        };
-
-        let session_id = match Uuid::parse_str(&session_id_str) {
-            Ok(id) => id,
-            Err(_) => return Outcome::Error((Status::Unauthorized, ())),
-        };
-
-        match sessions::get_session_by_id(db, session_id).await {
-            Ok(Some(session)) => {
-                let now = chrono::Utc::now().fixed_offset();
-                if session.expires_at < now {
-                    return Outcome::Error((Status::Unauthorized, ()));
-                }
-                let _ = sessions::touch_session(db, session_id).await;
-                Outcome::Success(AuthOrApiKey)
+        // Try session cookie. Viewers may read but not submit readings.
+        match req.guard::<CurrentUser>().await {
+            Outcome::Success(user) if user.role() < S::ROLE => {
+                Outcome::Error((Status::Forbidden, ()))
             }
-            Ok(None) => Outcome::Error((Status::Unauthorized, ())),
-            Err(_) => Outcome::Error((Status::InternalServerError, ())),
+            Outcome::Success(user) => Outcome::Success(AuthOrApiKey {
+                api_key: None,
+                tenant: user.tenant(),
+                scope: PhantomData,
+            }),
+            Outcome::Error(e) => Outcome::Error(e),
+            Outcome::Forward(s) => Outcome::Forward(s),
         }
     }
 }
@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use rocket::{
     Request,
     http::Status,
@@ -6,7 +8,11 @@ use rocket::{
 use sea_orm::DatabaseConnection;
 use uuid::Uuid;
 
-use crate::services::sessions;
+use shared::TeamRole;
+
+use crate::client_ip::client_ip;
+use crate::services::{sessions, team_service};
+use crate::tenant::{Membership, Tenant};
 
 #[derive(Debug, Clone)]
 pub struct CurrentUser {
@@ -14,9 +20,34 @@ pub struct CurrentUser {
     pub user_sub: String,
     pub email: String,
     pub name: String,
+    pub memberships: Vec<Membership>,
+    /// Team the request acts in, from the `X-Team-Id` header; `None` acts for the user alone.
+    pub team_id: Option<Uuid>,
+    pub ip: Option<IpAddr>,
+}
+
+impl CurrentUser {
+    pub fn tenant(&self) -> Tenant {
+        Tenant::member(&self.user_sub, &self.memberships, self.team_id).with_ip(self.ip)
+    }
+
+    pub fn role_in(&self, team_id: Uuid) -> Option<TeamRole> {
+        self.memberships
+            .iter()
+            .find(|m| m.team_id == team_id)
+            .map(|m| m.role)
+    }
+
+    /// The user's role in the team they are acting in. Everyone owns their own data.
+    pub fn role(&self) -> TeamRole {
+        self.team_id
+            .and_then(|id| self.role_in(id))
+            .unwrap_or(TeamRole::Owner)
+    }
 }
 
 pub const SESSION_COOKIE: &str = "session_id";
+pub const TEAM_HEADER: &str = "X-Team-Id";
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for CurrentUser {
@@ -46,11 +77,26 @@ impl<'r> FromRequest<'r> for CurrentUser {
                     return Outcome::Error((Status::Unauthorized, ()));
                 }
                 let _ = sessions::touch_session(db, session_id).await;
+                let memberships = match team_service::memberships_for(db, &session.user_sub).await {
+                    Ok(memberships) => memberships,
+                    Err(_) => return Outcome::Error((Status::InternalServerError, ())),
+                };
+                // Acting in a team the user doesn't belong to is refused rather than ignored.
+                let team_id = match req.headers().get_one(TEAM_HEADER) {
+                    Some(raw) => match Uuid::parse_str(raw.trim()) {
+                        Ok(id) if memberships.iter().any(|m| m.team_id == id) => Some(id),
+                        _ => return Outcome::Error((Status::Forbidden, ())),
+                    },
+                    None => None,
+                };
                 Outcome::Success(CurrentUser {
                     session_id,
                     user_sub: session.user_sub,
                     email: session.email,
                     name: session.name,
+                    memberships,
+                    team_id,
+                    ip: client_ip(req),
                 })
             }
             Ok(None) => Outcome::Error((Status::Unauthorized, ())),
@@ -0,0 +1,21 @@
+use rocket::{
+    Request,
+    request::{FromRequest, Outcome},
+};
+
+/// The `Last-Event-ID` header an `EventSource` sends when reconnecting. Missing or
+/// unparseable values mean "start from now".
+pub struct LastEventId(pub Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let id = req
+            .headers()
+            .get_one("Last-Event-ID")
+            .and_then(|v| v.trim().parse().ok());
+        Outcome::Success(LastEventId(id))
+    }
+}
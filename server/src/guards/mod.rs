@@ -1,3 +1,5 @@
 pub mod api_key;
 pub mod auth_or_api_key;
 pub mod current_user;
+pub mod last_event_id;
+pub mod role;
@@ -0,0 +1,61 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use rocket::{
+    Request,
+    http::Status,
+    request::{FromRequest, Outcome},
+};
+
+use shared::TeamRole;
+
+use crate::guards::current_user::CurrentUser;
+
+/// The least privileged role a route accepts.
+pub trait MinimumRole: Send + Sync + 'static {
+    const ROLE: TeamRole;
+}
+
+pub struct Brewer;
+
+impl MinimumRole for Brewer {
+    const ROLE: TeamRole = TeamRole::Brewer;
+}
+
+/// A signed-in user whose role in the team they are acting in is at least `R`; anyone else
+/// gets 403. Without `X-Team-Id` users act on their own data and always pass.
+pub struct RequireRole<R: MinimumRole> {
+    pub user: CurrentUser,
+    role: PhantomData<R>,
+}
+
+/// Viewers may read dashboards but not change brews, hydrometers, calibrations or API keys.
+pub type CanEdit = RequireRole<Brewer>;
+
+impl<R: MinimumRole> Deref for RequireRole<R> {
+    type Target = CurrentUser;
+
+    fn deref(&self) -> &CurrentUser {
+        &self.user
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, R: MinimumRole> FromRequest<'r> for RequireRole<R> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let user = match req.guard::<CurrentUser>().await {
+            Outcome::Success(user) => user,
+            Outcome::Error(e) => return Outcome::Error(e),
+            Outcome::Forward(s) => return Outcome::Forward(s),
+        };
+        if user.role() < R::ROLE {
+            return Outcome::Error((Status::Forbidden, ()));
+        }
+        Outcome::Success(RequireRole {
+            user,
+            role: PhantomData,
+        })
+    }
+}
@@ -1,9 +1,15 @@
+mod client_ip;
 mod fairings;
+mod events;
 mod guards;
+mod metrics;
 mod models;
+mod notifications;
 mod oidc;
+mod pagination;
 mod routes;
 mod services;
+mod tenant;
 
 use rocket::fs::{FileServer, NamedFile};
 use rocket::serde::json::Json;
@@ -154,16 +160,36 @@ async fn rocket() -> Rocket<Build> {
 
     let mut rocket = rocket::build()
         .manage(db)
+        .manage(notifications::Notifier::from_env())
+        .manage(events::EventBus::new())
+        .manage(services::gateway_service::GatewayConfig::from_env())
+        .manage(services::team_service::TeamConfig::from_env())
+        .manage(services::audit_service::AuditConfig::from_env())
+        .manage(metrics::Metrics::from_env())
+        .manage(client_ip::TrustedProxies::from_env())
         .attach(cors)
         .attach(fairings::rate_limit::RateLimit::new())
         .attach(fairings::request_logger::RequestLogger)
+        .attach(fairings::http_metrics::HttpMetrics)
         .attach(fairings::security_headers::SecurityHeaders)
         .attach(fairings::session_cleanup::SessionCleanup)
+        .attach(fairings::audit_retention::AuditRetention)
+        .attach(fairings::fermentation_monitor::FermentationMonitor)
+        .attach(fairings::alert_sweeper::AlertSweeper)
+        .attach(fairings::gateway_monitor::GatewayMonitor)
+        .attach(fairings::notification_dispatcher::NotificationDispatcher)
         .mount("/api/v1", routes![health])
         .mount("/", routes![preflight])
+        .mount("/", routes::metrics::routes())
         .mount("/api/v1", routes::hydrometers::routes())
         .mount("/api/v1", routes::brews::routes())
+        .mount("/api/v1", routes::exports::routes())
         .mount("/api/v1", routes::readings::routes())
+        .mount("/api/v1", routes::alerts::routes())
+        .mount("/api/v1", routes::notifications::routes())
+        .mount("/api/v1", routes::gateways::routes())
+        .mount("/api/v1", routes::teams::routes())
+        .mount("/api/v1", routes::audit::routes())
         .mount("/", FileServer::from(PathBuf::from(&web_dist)))
         .mount("/", routes![spa_fallback])
         .register(
@@ -0,0 +1,291 @@
+//! Prometheus metrics served at `GET /metrics`. Counters are updated as requests and
+//! readings arrive; database pool usage and the latest reading gauges are refreshed on each
+//! scrape. Series naming individual hydrometers cover every user's hydrometers, so they are
+//! only exported when `METRICS_PER_HYDROMETER` is set.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use prometheus::{
+    GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sea_orm::{DatabaseConnection, DbErr};
+use uuid::Uuid;
+
+use shared::TiltColor;
+
+use crate::services::reading_service;
+
+/// Request latency buckets, in seconds.
+const REQUEST_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub struct Metrics {
+    registry: Registry,
+    /// Bearer token required to scrape, from `METRICS_TOKEN`. Without one, only loopback
+    /// clients may scrape.
+    token: Option<String>,
+    /// Export series labelled by hydrometer, from `METRICS_PER_HYDROMETER`.
+    per_hydrometer: bool,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    readings_ingested: IntCounterVec,
+    rate_limited: IntCounterVec,
+    db_pool: IntGaugeVec,
+    gravity: GaugeVec,
+    temperature: GaugeVec,
+    reading_time: GaugeVec,
+}
+
+impl Metrics {
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("METRICS_TOKEN")
+                .ok()
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty()),
+            std::env::var("METRICS_PER_HYDROMETER")
+                .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+        )
+    }
+
+    pub fn new(token: Option<String>, per_hydrometer: bool) -> Self {
+        let counter = |name: &str, help: &str, labels: &[&str]| {
+            IntCounterVec::new(Opts::new(name, help), labels).expect("valid metric")
+        };
+        let gauge = |name: &str, help: &str| {
+            GaugeVec::new(Opts::new(name, help), &["hydrometer_id", "color", "name"])
+                .expect("valid metric")
+        };
+        let metrics = Self {
+            registry: Registry::new(),
+            token,
+            per_hydrometer,
+            http_requests: counter(
+                "http_requests_total",
+                "HTTP requests by method, route and status",
+                &["method", "route", "status"],
+            ),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by method, route and status",
+                )
+                .buckets(REQUEST_BUCKETS.to_vec()),
+                &["method", "route", "status"],
+            )
+            .expect("valid metric"),
+            readings_ingested: if per_hydrometer {
+                counter(
+                    "tilt_readings_ingested_total",
+                    "Readings stored from uploads, by hydrometer",
+                    &["hydrometer_id", "color"],
+                )
+            } else {
+                counter(
+                    "tilt_readings_ingested_total",
+                    "Readings stored from uploads, by color",
+                    &["color"],
+                )
+            },
+            rate_limited: counter(
+                "rate_limit_rejections_total",
+                "Requests over a rate limit, by limiter (auth or api)",
+                &["limiter"],
+            ),
+            db_pool: IntGaugeVec::new(
+                Opts::new(
+                    "db_pool_connections",
+                    "Database pool connections by state (idle, in_use or max)",
+                ),
+                &["state"],
+            )
+            .expect("valid metric"),
+            gravity: gauge("tilt_gravity", "Latest specific gravity of each hydrometer"),
+            temperature: gauge(
+                "tilt_temperature_fahrenheit",
+                "Latest temperature of each hydrometer in °F",
+            ),
+            reading_time: gauge(
+                "tilt_last_reading_timestamp_seconds",
+                "Unix time of each hydrometer's latest reading",
+            ),
+        };
+        let mut collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.readings_ingested.clone()),
+            Box::new(metrics.rate_limited.clone()),
+            Box::new(metrics.db_pool.clone()),
+        ];
+        if per_hydrometer {
+            collectors.push(Box::new(metrics.gravity.clone()));
+            collectors.push(Box::new(metrics.temperature.clone()));
+            collectors.push(Box::new(metrics.reading_time.clone()));
+        }
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+        metrics
+    }
+
+    pub fn requires_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// With a token configured, whether `bearer` matches it; otherwise whether the client
+    /// connected from loopback.
+    pub fn authorized(&self, bearer: Option<&str>, peer: Option<IpAddr>) -> bool {
+        match &self.token {
+            Some(token) => bearer.is_some_and(|b| constant_time_eq(b.as_bytes(), token.as_bytes())),
+            None => peer.is_some_and(|ip| ip.to_canonical().is_loopback()),
+        }
+    }
+
+    /// `route` is the matched route's URI template, so ids don't multiply the series.
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn readings_ingested(&self, hydrometer_id: Uuid, color: TiltColor, count: usize) {
+        let color = format!("{color:?}");
+        let counter = if self.per_hydrometer {
+            self.readings_ingested
+                .with_label_values(&[&hydrometer_id.to_string(), &color])
+        } else {
+            self.readings_ingested.with_label_values(&[&color])
+        };
+        counter.inc_by(count as u64);
+    }
+
+    pub fn rate_limited(&self, limiter: &str) {
+        self.rate_limited.with_label_values(&[limiter]).inc();
+    }
+
+    /// Refreshes the scrape-time gauges and encodes every metric in the text format.
+    pub async fn render(&self, db: &DatabaseConnection) -> Result<String, DbErr> {
+        let pool = db.get_postgres_connection_pool();
+        let idle = pool.num_idle() as i64;
+        let size = i64::from(pool.size());
+        self.db_pool.with_label_values(&["idle"]).set(idle);
+        self.db_pool.with_label_values(&["in_use"]).set(size - idle);
+        self.db_pool
+            .with_label_values(&["max"])
+            .set(i64::from(pool.options().get_max_connections()));
+
+        if self.per_hydrometer {
+            self.refresh_latest(db).await?;
+        }
+
+        Ok(TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                tracing::error!(error = %e, "Failed to encode metrics");
+                String::new()
+            }))
+    }
+
+    async fn refresh_latest(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
+        let latest = reading_service::latest_per_hydrometer(db).await?;
+        for gauge in [&self.gravity, &self.temperature, &self.reading_time] {
+            gauge.reset();
+        }
+        for reading in latest {
+            let id = reading.hydrometer_id.to_string();
+            let labels = [
+                id.as_str(),
+                reading.color.as_str(),
+                reading.name.as_deref().unwrap_or(""),
+            ];
+            self.gravity.with_label_values(&labels).set(reading.gravity);
+            self.temperature
+                .with_label_values(&labels)
+                .set(reading.temperature_f);
+            self.reading_time
+                .with_label_values(&labels)
+                .set(reading.recorded_at.timestamp() as f64);
+        }
+        Ok(())
+    }
+}
+
+/// Compares without returning early, so response timing doesn't reveal how much of a
+/// guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_a_token_only_loopback_may_scrape() {
+        let metrics = Metrics::new(None, false);
+        assert!(!metrics.requires_token());
+        assert!(metrics.authorized(None, Some("127.0.0.1".parse().unwrap())));
+        assert!(metrics.authorized(None, Some("::1".parse().unwrap())));
+        assert!(metrics.authorized(None, Some("::ffff:127.0.0.1".parse().unwrap())));
+        assert!(!metrics.authorized(None, Some("203.0.113.7".parse().unwrap())));
+        assert!(!metrics.authorized(None, None));
+    }
+
+    #[test]
+    fn configured_token_is_required_from_anywhere() {
+        let metrics = Metrics::new(Some("secret".into()), false);
+        let remote = Some("203.0.113.7".parse().unwrap());
+        let loopback = Some("127.0.0.1".parse().unwrap());
+        assert!(metrics.requires_token());
+        assert!(metrics.authorized(Some("secret"), remote));
+        assert!(!metrics.authorized(Some("secre"), remote));
+        assert!(!metrics.authorized(Some("other!"), remote));
+        assert!(!metrics.authorized(None, loopback));
+    }
+
+    #[test]
+    fn hydrometer_series_are_opt_in() {
+        let metrics = Metrics::new(None, false);
+        metrics.readings_ingested(Uuid::nil(), TiltColor::Red, 2);
+        metrics
+            .gravity
+            .with_label_values(&["id", "Red", ""])
+            .set(1.010);
+        let text = TextEncoder::new()
+            .encode_to_string(&metrics.registry.gather())
+            .unwrap();
+        assert!(text.contains("tilt_readings_ingested_total{color=\"Red\"} 2"));
+        assert!(!text.contains("hydrometer_id"));
+        assert!(!text.contains("tilt_gravity"));
+    }
+
+    #[test]
+    fn counters_are_labelled_by_route_and_hydrometer() {
+        let metrics = Metrics::new(None, true);
+        metrics.observe_request("GET", "/api/v1/brews/<id>", 200, Duration::from_millis(3));
+        metrics.observe_request("GET", "/api/v1/brews/<id>", 200, Duration::from_millis(7));
+        let id = Uuid::nil();
+        metrics.readings_ingested(id, TiltColor::Red, 4);
+        metrics.rate_limited("auth");
+
+        let text = TextEncoder::new()
+            .encode_to_string(&metrics.registry.gather())
+            .unwrap();
+        assert!(text.contains(
+            "http_requests_total{method=\"GET\",route=\"/api/v1/brews/<id>\",status=\"200\"} 2"
+        ));
+        assert!(text.contains(&format!(
+            "tilt_readings_ingested_total{{color=\"Red\",hydrometer_id=\"{id}\"}} 4"
+        )));
+        assert!(text.contains("rate_limit_rejections_total{limiter=\"auth\"} 1"));
+    }
+}
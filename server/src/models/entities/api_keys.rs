@@ -16,9 +16,45 @@ pub struct Model {
     pub last_used_at: Option<DateTimeWithTimeZone>,
     pub expires_at: Option<DateTimeWithTimeZone>,
     pub created_at: DateTimeWithTimeZone,
+    pub team_id: Option<Uuid>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub scopes: Json,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub allowed_colors: Option<Json>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub allowed_ips: Option<Json>,
+    pub previous_key_hash: Option<String>,
+    pub previous_expires_at: Option<DateTimeWithTimeZone>,
+    pub rotated_at: Option<DateTimeWithTimeZone>,
+    pub request_count: i64,
+    pub last_ip: Option<String>,
+    pub last_user_agent: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::gateways::Entity")]
+    Gateways,
+    #[sea_orm(
+        belongs_to = "super::teams::Entity",
+        from = "Column::TeamId",
+        to = "super::teams::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Teams,
+}
+
+impl Related<super::gateways::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Gateways.def()
+    }
+}
+
+impl Related<super::teams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teams.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
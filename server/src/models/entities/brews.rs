@@ -26,10 +26,15 @@ pub struct Model {
     pub hydrometer_id: Uuid,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub stable_since: Option<DateTimeWithTimeZone>,
+    pub owner: String,
+    pub team_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::alert_rules::Entity")]
+    AlertRules,
     #[sea_orm(
         belongs_to = "super::hydrometers::Entity",
         from = "Column::HydrometerId",
@@ -40,6 +45,20 @@ pub enum Relation {
     Hydrometers,
     #[sea_orm(has_many = "super::readings::Entity")]
     Readings,
+    #[sea_orm(
+        belongs_to = "super::teams::Entity",
+        from = "Column::TeamId",
+        to = "super::teams::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Teams,
+}
+
+impl Related<super::alert_rules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AlertRules.def()
+    }
 }
 
 impl Related<super::hydrometers::Entity> for Entity {
@@ -54,4 +73,10 @@ impl Related<super::readings::Entity> for Entity {
     }
 }
 
+impl Related<super::teams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teams.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
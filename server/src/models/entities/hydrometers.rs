@@ -8,7 +8,6 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
-    #[sea_orm(unique)]
     pub color: String,
     pub name: Option<String>,
     #[sea_orm(column_type = "Double")]
@@ -16,14 +15,34 @@ pub struct Model {
     #[sea_orm(column_type = "Double")]
     pub gravity_offset: f64,
     pub created_at: DateTimeWithTimeZone,
+    pub owner: String,
+    pub team_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::alert_rules::Entity")]
+    AlertRules,
     #[sea_orm(has_many = "super::brews::Entity")]
     Brews,
+    #[sea_orm(has_many = "super::calibration_points::Entity")]
+    CalibrationPoints,
     #[sea_orm(has_many = "super::readings::Entity")]
     Readings,
+    #[sea_orm(
+        belongs_to = "super::teams::Entity",
+        from = "Column::TeamId",
+        to = "super::teams::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Teams,
+}
+
+impl Related<super::alert_rules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AlertRules.def()
+    }
 }
 
 impl Related<super::brews::Entity> for Entity {
@@ -32,10 +51,22 @@ impl Related<super::brews::Entity> for Entity {
     }
 }
 
+impl Related<super::calibration_points::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CalibrationPoints.def()
+    }
+}
+
 impl Related<super::readings::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Readings.def()
     }
 }
 
+impl Related<super::teams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teams.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
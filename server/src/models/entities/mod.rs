@@ -2,8 +2,18 @@
 
 pub mod prelude;
 
+pub mod alert_rules;
+pub mod alerts;
 pub mod api_keys;
+pub mod audit_events;
 pub mod brews;
+pub mod calibration_points;
+pub mod gateways;
 pub mod hydrometers;
+pub mod notification_channels;
+pub mod notification_deliveries;
 pub mod readings;
+pub mod team_invitations;
+pub mod team_members;
+pub mod teams;
 pub mod user_sessions;
@@ -2,8 +2,18 @@
 
 #![allow(unused_imports)]
 
+pub use super::alert_rules::Entity as AlertRules;
+pub use super::alerts::Entity as Alerts;
 pub use super::api_keys::Entity as ApiKeys;
+pub use super::audit_events::Entity as AuditEvents;
 pub use super::brews::Entity as Brews;
+pub use super::calibration_points::Entity as CalibrationPoints;
+pub use super::gateways::Entity as Gateways;
 pub use super::hydrometers::Entity as Hydrometers;
+pub use super::notification_channels::Entity as NotificationChannels;
+pub use super::notification_deliveries::Entity as NotificationDeliveries;
 pub use super::readings::Entity as Readings;
+pub use super::team_invitations::Entity as TeamInvitations;
+pub use super::team_members::Entity as TeamMembers;
+pub use super::teams::Entity as Teams;
 pub use super::user_sessions::Entity as UserSessions;
@@ -17,6 +17,15 @@ pub struct Model {
     pub rssi: Option<i16>,
     pub recorded_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "Double")]
+    pub raw_temperature_f: f64,
+    #[sea_orm(column_type = "Double")]
+    pub raw_gravity: f64,
+    pub device_kind: String,
+    pub tx_power: Option<i16>,
+    pub gateway_id: Option<Uuid>,
+    pub owner: String,
+    pub team_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -29,6 +38,14 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     Brews,
+    #[sea_orm(
+        belongs_to = "super::gateways::Entity",
+        from = "Column::GatewayId",
+        to = "super::gateways::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Gateways,
     #[sea_orm(
         belongs_to = "super::hydrometers::Entity",
         from = "Column::HydrometerId",
@@ -37,6 +54,14 @@ pub enum Relation {
         on_delete = "Restrict"
     )]
     Hydrometers,
+    #[sea_orm(
+        belongs_to = "super::teams::Entity",
+        from = "Column::TeamId",
+        to = "super::teams::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Teams,
 }
 
 impl Related<super::brews::Entity> for Entity {
@@ -45,10 +70,22 @@ impl Related<super::brews::Entity> for Entity {
     }
 }
 
+impl Related<super::gateways::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Gateways.def()
+    }
+}
+
 impl Related<super::hydrometers::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Hydrometers.def()
     }
 }
 
+impl Related<super::teams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teams.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
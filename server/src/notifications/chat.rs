@@ -0,0 +1,77 @@
+use shared::Notification;
+
+use super::{Channel, DeliveryError, post};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ChatStyle {
+    Slack,
+    Discord,
+}
+
+/// Incoming-webhook integrations for chat apps; only the JSON shape differs.
+pub struct ChatChannel {
+    pub http: reqwest::Client,
+    pub url: String,
+    pub style: ChatStyle,
+}
+
+pub fn body(style: ChatStyle, notification: &Notification) -> serde_json::Value {
+    match style {
+        ChatStyle::Slack => serde_json::json!({
+            "text": format!("*{}*\n{}", notification.title, notification.message),
+        }),
+        ChatStyle::Discord => serde_json::json!({
+            "content": format!("**{}**\n{}", notification.title, notification.message),
+        }),
+    }
+}
+
+#[rocket::async_trait]
+impl Channel for ChatChannel {
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        post(
+            self.http
+                .post(&self.url)
+                .json(&body(self.style, notification)),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shared::NotificationEvent;
+
+    fn notification() -> Notification {
+        Notification {
+            event: NotificationEvent::AlertFiring,
+            title: "Too warm".to_string(),
+            message: "Temperature 74.0°F is above 72.0°F".to_string(),
+            brew_id: None,
+            hydrometer_id: None,
+            alert: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn slack_uses_text_with_bold_title() {
+        let body = body(ChatStyle::Slack, &notification());
+        assert_eq!(
+            body["text"],
+            "*Too warm*\nTemperature 74.0°F is above 72.0°F"
+        );
+        assert!(body.get("content").is_none());
+    }
+
+    #[test]
+    fn discord_uses_content_with_markdown_title() {
+        let body = body(ChatStyle::Discord, &notification());
+        assert_eq!(
+            body["content"],
+            "**Too warm**\nTemperature 74.0°F is above 72.0°F"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use shared::Notification;
+
+use super::{Channel, DeliveryError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpSecurity {
+    StartTls,
+    Tls,
+    /// Plain connection, for local relays and test servers.
+    None,
+}
+
+/// Server-wide SMTP relay used by every email channel.
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+impl SmtpConfig {
+    /// Returns `None` when `SMTP_HOST` isn't set.
+    pub fn from_env() -> Option<Self> {
+        let host = std::env::var("SMTP_HOST")
+            .ok()
+            .filter(|h| !h.trim().is_empty())?;
+        let security = match std::env::var("SMTP_SECURITY").as_deref().map(str::trim) {
+            Ok("tls") => SmtpSecurity::Tls,
+            Ok("none") => SmtpSecurity::None,
+            _ => SmtpSecurity::StartTls,
+        };
+        let default_port = match security {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        };
+        Some(Self {
+            port: std::env::var("SMTP_PORT")
+                .ok()
+                .and_then(|p| p.trim().parse().ok())
+                .unwrap_or(default_port),
+            security,
+            username: std::env::var("SMTP_USERNAME")
+                .ok()
+                .filter(|u| !u.is_empty()),
+            password: std::env::var("SMTP_PASSWORD").ok(),
+            from: std::env::var("SMTP_FROM")
+                .unwrap_or_else(|_| format!("Tilt Hydrometer <tilt@{}>", host.trim())),
+            host: host.trim().to_string(),
+        })
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, DeliveryError> {
+        let builder = match self.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+                    .map_err(|e| DeliveryError(e.to_string()))?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+                .map_err(|e| DeliveryError(e.to_string()))?,
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)
+            }
+        };
+        let mut builder = builder.port(self.port);
+        if let Some(username) = &self.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                self.password.clone().unwrap_or_default(),
+            ));
+        }
+        Ok(builder.build())
+    }
+}
+
+pub fn parse_mailbox(address: &str) -> Result<Mailbox, DeliveryError> {
+    address
+        .parse()
+        .map_err(|e: lettre::address::AddressError| DeliveryError(e.to_string()))
+}
+
+pub struct EmailChannel {
+    pub smtp: Option<SmtpConfig>,
+    pub to: String,
+}
+
+#[rocket::async_trait]
+impl Channel for EmailChannel {
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        let Some(smtp) = &self.smtp else {
+            return Err(DeliveryError(
+                "SMTP is not configured on this server".to_string(),
+            ));
+        };
+        let message = Message::builder()
+            .from(parse_mailbox(&smtp.from)?)
+            .to(parse_mailbox(&self.to)?)
+            .subject(&notification.title)
+            .header(ContentType::TEXT_PLAIN)
+            .body(format!(
+                "{}\n\n{}",
+                notification.message,
+                notification.occurred_at.to_rfc2822()
+            ))
+            .map_err(|e| DeliveryError(e.to_string()))?;
+
+        smtp.transport()?
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| DeliveryError(e.to_string()))
+    }
+}
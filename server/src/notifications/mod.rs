@@ -0,0 +1,188 @@
+//! Outbound notification channels. Each [`ChannelConfig`] variant maps to a [`Channel`]
+//! implementation; [`Notifier`] holds the shared HTTP client and SMTP relay settings.
+
+mod chat;
+mod email;
+mod ntfy;
+mod webhook;
+
+use std::fmt;
+use std::time::Duration;
+
+use shared::{ChannelConfig, Notification};
+
+pub use email::SmtpConfig;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryError(pub String);
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<reqwest::Error> for DeliveryError {
+    fn from(e: reqwest::Error) -> Self {
+        DeliveryError(e.to_string())
+    }
+}
+
+#[rocket::async_trait]
+pub trait Channel: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError>;
+}
+
+#[derive(Clone)]
+pub struct Notifier {
+    http: reqwest::Client,
+    smtp: Option<SmtpConfig>,
+}
+
+impl Notifier {
+    pub fn from_env() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("Failed to build notification HTTP client");
+        let smtp = SmtpConfig::from_env();
+        if smtp.is_none() {
+            tracing::info!("SMTP_HOST not set — email notification channels will fail to deliver");
+        }
+        Self { http, smtp }
+    }
+
+    pub fn channel(&self, config: &ChannelConfig) -> Box<dyn Channel> {
+        match config.clone() {
+            ChannelConfig::Webhook { url, secret } => Box::new(webhook::WebhookChannel {
+                http: self.http.clone(),
+                url,
+                secret,
+            }),
+            ChannelConfig::Ntfy {
+                url,
+                topic,
+                token,
+                priority,
+            } => Box::new(ntfy::NtfyChannel {
+                http: self.http.clone(),
+                url,
+                topic,
+                token,
+                priority,
+            }),
+            ChannelConfig::Email { to } => Box::new(email::EmailChannel {
+                smtp: self.smtp.clone(),
+                to,
+            }),
+            ChannelConfig::Slack { url } => Box::new(chat::ChatChannel {
+                http: self.http.clone(),
+                url,
+                style: chat::ChatStyle::Slack,
+            }),
+            ChannelConfig::Discord { url } => Box::new(chat::ChatChannel {
+                http: self.http.clone(),
+                url,
+                style: chat::ChatStyle::Discord,
+            }),
+        }
+    }
+
+    pub async fn send(
+        &self,
+        config: &ChannelConfig,
+        notification: &Notification,
+    ) -> Result<(), DeliveryError> {
+        self.channel(config).send(notification).await
+    }
+}
+
+/// Rejects configs that can never deliver: bad URLs, empty ntfy topics, unparseable addresses.
+pub fn validate(config: &ChannelConfig) -> Result<(), &'static str> {
+    match config {
+        ChannelConfig::Webhook { url, .. }
+        | ChannelConfig::Slack { url }
+        | ChannelConfig::Discord { url } => validate_url(url),
+        ChannelConfig::Ntfy {
+            url,
+            topic,
+            priority,
+            ..
+        } => {
+            validate_url(url)?;
+            if topic.is_empty() || topic.contains('/') {
+                return Err("ntfy topic must be a single non-empty path segment");
+            }
+            if priority.is_some_and(|p| !(1..=5).contains(&p)) {
+                return Err("ntfy priority must be between 1 and 5");
+            }
+            Ok(())
+        }
+        ChannelConfig::Email { to } => email::parse_mailbox(to)
+            .map(|_| ())
+            .map_err(|_| "invalid email address"),
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), &'static str> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err("url must be an absolute http(s) URL"),
+    }
+}
+
+/// Sends a prepared request and treats any non-2xx response as a failed delivery.
+async fn post(request: reqwest::RequestBuilder) -> Result<(), DeliveryError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    let snippet: String = body.chars().take(200).collect();
+    Err(DeliveryError(format!(
+        "HTTP {}: {}",
+        status.as_u16(),
+        snippet
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_non_http_urls() {
+        let slack = |url: &str| ChannelConfig::Slack {
+            url: url.to_string(),
+        };
+        assert!(validate(&slack("https://hooks.slack.com/services/T/B/X")).is_ok());
+        assert!(validate(&slack("http://127.0.0.1:9000/hook")).is_ok());
+        assert!(validate(&slack("ftp://example.com")).is_err());
+        assert!(validate(&slack("not a url")).is_err());
+    }
+
+    #[test]
+    fn validate_ntfy_topic_and_priority() {
+        let ntfy = |topic: &str, priority| ChannelConfig::Ntfy {
+            url: "https://ntfy.sh".to_string(),
+            topic: topic.to_string(),
+            token: None,
+            priority,
+        };
+        assert!(validate(&ntfy("brewery", Some(4))).is_ok());
+        assert!(validate(&ntfy("", None)).is_err());
+        assert!(validate(&ntfy("a/b", None)).is_err());
+        assert!(validate(&ntfy("brewery", Some(9))).is_err());
+    }
+
+    #[test]
+    fn validate_email_address() {
+        let email = |to: &str| ChannelConfig::Email { to: to.to_string() };
+        assert!(validate(&email("brewer@example.com")).is_ok());
+        assert!(validate(&email("Brewer <brewer@example.com>")).is_ok());
+        assert!(validate(&email("nope")).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use shared::{Notification, NotificationEvent};
+
+use super::{Channel, DeliveryError, post};
+
+/// ntfy-style push: the message is the plain-text body and metadata travels in headers.
+pub struct NtfyChannel {
+    pub http: reqwest::Client,
+    pub url: String,
+    pub topic: String,
+    pub token: Option<String>,
+    pub priority: Option<u8>,
+}
+
+pub fn topic_url(url: &str, topic: &str) -> String {
+    format!("{}/{}", url.trim_end_matches('/'), topic)
+}
+
+/// ntfy renders known tag names as emoji in the notification title.
+pub fn tags(event: NotificationEvent) -> &'static str {
+    match event {
+        NotificationEvent::AlertFiring => "warning",
+        NotificationEvent::AlertResolved => "white_check_mark",
+        NotificationEvent::BrewCompleted => "beer",
+        NotificationEvent::GatewayOffline => "electric_plug",
+        NotificationEvent::GatewayOnline => "satellite",
+        NotificationEvent::Test => "test_tube",
+    }
+}
+
+#[rocket::async_trait]
+impl Channel for NtfyChannel {
+    async fn send(&self, notification: &Notification) -> Result<(), DeliveryError> {
+        let mut request = self
+            .http
+            .post(topic_url(&self.url, &self.topic))
+            .header("Title", &notification.title)
+            .header("Tags", tags(notification.event));
+        if let Some(priority) = self.priority {
+            request = request.header("Priority", priority.to_string());
+        }
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        post(request.body(notification.message.clone())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_url_joins_without_double_slash() {
+        assert_eq!(
+            topic_url("https://ntfy.sh", "brewery"),
+            "https://ntfy.sh/brewery"
+        );
+        assert_eq!(
+            topic_url("https://ntfy.sh/", "brewery"),
+            "https://ntfy.sh/brewery"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Keyset pagination for the list endpoints. Cursors are opaque to clients: they encode
+//! the sort timestamp and id of the last item on a page, and the next page starts strictly
+//! after that pair so inserts between requests never shift or repeat rows.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use rocket::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use sea_orm::sea_query::Expr;
+use sea_orm::{EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Select};
+use serde::Serialize;
+use uuid::Uuid;
+
+use shared::{Page, SortOrder};
+
+/// Page size for brews and hydrometers when `limit` is omitted.
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+pub const MAX_PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.at.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(s: &str) -> Option<Cursor> {
+        let bytes = URL_SAFE_NO_PAD.decode(s).ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let (micros, id) = raw.split_once(':')?;
+        Some(Cursor {
+            at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    pub cursor: Option<Cursor>,
+    pub order: SortOrder,
+    pub limit: u64,
+}
+
+impl PageRequest {
+    /// From raw query parameters; `None` if the cursor or order doesn't parse.
+    pub fn parse(cursor: Option<&str>, order: Option<&str>, limit: Option<u64>) -> Option<Self> {
+        Some(Self {
+            cursor: match cursor {
+                Some(cursor) => Some(Cursor::decode(cursor)?),
+                None => None,
+            },
+            order: match order {
+                Some(order) => SortOrder::parse(order)?,
+                None => SortOrder::default(),
+            },
+            limit: limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+        })
+    }
+
+    /// Orders by `(at, id)`, skips past the cursor and fetches one extra row so
+    /// [`PageRequest::finish`] can tell whether another page exists.
+    pub fn apply<E: EntityTrait>(
+        &self,
+        select: Select<E>,
+        at: E::Column,
+        id: E::Column,
+    ) -> Select<E> {
+        let order = sql_order(self.order);
+        let mut select = select;
+        if let Some(cursor) = self.cursor {
+            let keys = Expr::tuple([Expr::col(at).into(), Expr::col(id).into()]);
+            let after = Expr::tuple([
+                Expr::value(cursor.at.fixed_offset()),
+                Expr::value(cursor.id),
+            ]);
+            select = select.filter(match self.order {
+                SortOrder::Asc => keys.gt(after),
+                SortOrder::Desc => keys.lt(after),
+            });
+        }
+        select
+            .order_by(at, order.clone())
+            .order_by(id, order)
+            .limit(self.limit + 1)
+    }
+
+    /// Trims the extra row fetched by [`PageRequest::apply`] and returns the cursor for the
+    /// next page, taken from the last row kept.
+    pub fn finish<M>(
+        &self,
+        mut rows: Vec<M>,
+        key: impl Fn(&M) -> Cursor,
+    ) -> (Vec<M>, Option<String>) {
+        if rows.len() as u64 <= self.limit {
+            return (rows, None);
+        }
+        rows.truncate(self.limit as usize);
+        let next = rows.last().map(|row| key(row).encode());
+        (rows, next)
+    }
+}
+
+pub fn sql_order(order: SortOrder) -> Order {
+    match order {
+        SortOrder::Asc => Order::Asc,
+        SortOrder::Desc => Order::Desc,
+    }
+}
+
+/// `Link` header value pointing at the next page: the request's own path and query with
+/// `cursor` replaced.
+pub fn next_link(path: &str, query: Option<&str>, cursor: &str) -> String {
+    let mut params = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+        if key != "cursor" {
+            params.append_pair(&key, &value);
+        }
+    }
+    params.append_pair("cursor", cursor);
+    format!("<{path}?{}>; rel=\"next\"", params.finish())
+}
+
+/// JSON [`Page`] response that also advertises the next page in a `Link` header.
+pub struct Paginated<T>(pub Page<T>);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Paginated<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let link = self.0.next_cursor.as_deref().map(|cursor| {
+            let uri = req.uri();
+            next_link(uri.path().as_str(), uri.query().map(|q| q.as_str()), cursor)
+        });
+        let mut response = Json(self.0).respond_to(req)?;
+        if let Some(link) = link {
+            response.set_raw_header("Link", link);
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            at: DateTime::from_timestamp_micros(1_760_000_000_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert!(Cursor::decode("not a cursor").is_none());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("123:nope")).is_none());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("123")).is_none());
+    }
+
+    #[test]
+    fn page_request_defaults_and_clamps() {
+        let page = PageRequest::parse(None, None, None).unwrap();
+        assert_eq!(page.order, SortOrder::Desc);
+        assert_eq!(page.limit, DEFAULT_PAGE_SIZE);
+        assert_eq!(
+            PageRequest::parse(None, Some("asc"), Some(0))
+                .unwrap()
+                .limit,
+            1
+        );
+        assert_eq!(
+            PageRequest::parse(None, None, Some(1_000_000))
+                .unwrap()
+                .limit,
+            MAX_PAGE_SIZE
+        );
+        assert!(PageRequest::parse(None, Some("sideways"), None).is_none());
+        assert!(PageRequest::parse(Some("???"), None, None).is_none());
+    }
+
+    #[test]
+    fn finish_only_sets_cursor_when_more_rows_exist() {
+        let page = PageRequest::parse(None, None, Some(2)).unwrap();
+        let at = DateTime::from_timestamp(0, 0).unwrap();
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let key = |id: &Uuid| Cursor { at, id: *id };
+
+        let (rows, next) = page.finish(ids[..2].to_vec(), key);
+        assert_eq!(rows.len(), 2);
+        assert!(next.is_none());
+
+        let (rows, next) = page.finish(ids.clone(), key);
+        assert_eq!(rows, ids[..2]);
+        assert_eq!(Cursor::decode(&next.unwrap()).unwrap().id, ids[1]);
+    }
+
+    #[test]
+    fn next_link_replaces_cursor() {
+        let link = next_link(
+            "/api/v1/brews",
+            Some("status=Active&cursor=old&limit=10"),
+            "new",
+        );
+        assert_eq!(
+            link,
+            "</api/v1/brews?status=Active&limit=10&cursor=new>; rel=\"next\""
+        );
+        assert_eq!(
+            next_link("/api/v1/hydrometers", None, "c"),
+            "</api/v1/hydrometers?cursor=c>; rel=\"next\""
+        );
+    }
+}
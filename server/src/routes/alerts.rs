@@ -0,0 +1,126 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State, delete, get, post, put, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::{AlertResponse, AlertRuleResponse, AlertState, CreateAlertRule, UpdateAlertRule};
+
+use crate::guards::current_user::CurrentUser;
+use crate::services::alert_service::{self, AlertRuleError};
+
+fn rule_error_status(e: AlertRuleError) -> Status {
+    match e {
+        AlertRuleError::Invalid(reason) => {
+            tracing::debug!(reason, "Rejected alert rule");
+            Status::UnprocessableEntity
+        }
+        AlertRuleError::Db(e) => {
+            tracing::error!(error = %e, "Failed to save alert rule");
+            Status::InternalServerError
+        }
+    }
+}
+
+#[get("/alert-rules")]
+async fn list_rules(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+) -> Result<Json<Vec<AlertRuleResponse>>, Status> {
+    alert_service::list_rules(db.inner(), &user.user_sub)
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[get("/alert-rules/<id>")]
+async fn get_rule(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<AlertRuleResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match alert_service::find_rule(db.inner(), id, &user.user_sub).await {
+        Ok(Some(r)) => Ok(Json(r)),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/alert-rules", data = "<input>")]
+async fn create_rule(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    input: Json<CreateAlertRule>,
+) -> Result<(Status, Json<AlertRuleResponse>), Status> {
+    alert_service::create_rule(db.inner(), input.into_inner(), &user.tenant())
+        .await
+        .map(|r| (Status::Created, Json(r)))
+        .map_err(rule_error_status)
+}
+
+#[put("/alert-rules/<id>", data = "<input>")]
+async fn update_rule(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    input: Json<UpdateAlertRule>,
+) -> Result<Json<AlertRuleResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match alert_service::update_rule(db.inner(), id, input.into_inner(), &user.user_sub).await {
+        Ok(Some(r)) => Ok(Json(r)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => Err(rule_error_status(e)),
+    }
+}
+
+#[delete("/alert-rules/<id>")]
+async fn delete_rule(user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Status {
+    let Ok(id) = Uuid::parse_str(id) else {
+        return Status::UnprocessableEntity;
+    };
+    match alert_service::delete_rule(db.inner(), id, &user.user_sub).await {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
+
+#[get("/alerts?<state>&<rule_id>&<limit>")]
+async fn list_alerts(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    state: Option<&str>,
+    rule_id: Option<&str>,
+    limit: Option<u64>,
+) -> Result<Json<Vec<AlertResponse>>, Status> {
+    let state = match state {
+        Some("Firing") => Some(AlertState::Firing),
+        Some("Resolved") => Some(AlertState::Resolved),
+        Some(_) => return Err(Status::UnprocessableEntity),
+        None => None,
+    };
+    let rule_id = rule_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+
+    alert_service::list_alerts(db.inner(), &user.user_sub, state, rule_id, limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to query alerts");
+            Status::InternalServerError
+        })
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        list_rules,
+        get_rule,
+        create_rule,
+        update_rule,
+        delete_rule,
+        list_alerts
+    ]
+}
@@ -9,8 +9,8 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::{
-    guards::current_user::CurrentUser,
-    services::api_keys::{self, ApiKeyError},
+    guards::auth_or_api_key::{AdminReader, Administrator},
+    services::api_keys::{self, ApiKeyError, ApiKeyRestrictions},
 };
 
 #[derive(Debug, Deserialize)]
@@ -18,42 +18,93 @@ use crate::{
 pub struct CreateApiKeyRequest {
     pub name: String,
     pub expires_at: Option<DateTime<chrono::FixedOffset>>,
+    /// Scopes default to `readings:write`; colors and addresses to unrestricted.
+    #[serde(flatten)]
+    pub restrictions: ApiKeyRestrictions,
 }
 
+/// The caller's own keys, and every key of the teams they own.
 #[get("/api-keys")]
 pub async fn list(
-    user: CurrentUser,
+    auth: AdminReader,
     db: &State<DatabaseConnection>,
 ) -> Result<Json<serde_json::Value>, Status> {
-    api_keys::list_api_keys(db.inner(), &user.user_sub)
+    api_keys::list_api_keys(db.inner(), &auth.tenant)
         .await
         .map(|keys| Json(serde_json::json!(keys)))
         .map_err(|_| Status::InternalServerError)
 }
 
+/// Readings sent with the key belong to its creator and the team they are acting in.
 #[post("/api-keys", data = "<input>")]
 pub async fn create(
-    user: CurrentUser,
+    auth: Administrator,
     db: &State<DatabaseConnection>,
     input: Json<CreateApiKeyRequest>,
-) -> Result<(Status, Json<serde_json::Value>), Status> {
+) -> Result<(Status, Json<serde_json::Value>), (Status, Json<serde_json::Value>)> {
     let req = input.into_inner();
-    api_keys::create_api_key(db.inner(), req.name, user.user_sub, req.expires_at)
-        .await
-        .map(|created| (Status::Created, Json(serde_json::json!(created))))
-        .map_err(|_| Status::InternalServerError)
+    if let Err(message) = req.restrictions.validate() {
+        return Err((
+            Status::UnprocessableEntity,
+            Json(serde_json::json!({ "error": message })),
+        ));
+    }
+    api_keys::create_api_key(
+        db.inner(),
+        &auth.tenant,
+        req.name,
+        req.expires_at,
+        req.restrictions,
+    )
+    .await
+    .map(|created| (Status::Created, Json(serde_json::json!(created))))
+    .map_err(|_| {
+        (
+            Status::InternalServerError,
+            Json(serde_json::json!({ "error": "internal server error" })),
+        )
+    })
+}
+
+/// Issues a new secret for the key. The old one keeps working for `grace_hours` (a day by
+/// default, 0 to revoke it immediately) while clients are updated.
+#[post("/api-keys/<id>/rotate?<grace_hours>")]
+pub async fn rotate(
+    auth: Administrator,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    grace_hours: Option<u32>,
+) -> Result<Json<serde_json::Value>, (Status, Json<serde_json::Value>)> {
+    let error = |status: Status, message: &str| {
+        (status, Json(serde_json::json!({ "error": message })))
+    };
+    let id = Uuid::parse_str(id)
+        .map_err(|_| error(Status::UnprocessableEntity, "invalid API key id"))?;
+    let grace = api_keys::grace_period(grace_hours)
+        .map_err(|message| error(Status::UnprocessableEntity, message))?;
+    match api_keys::rotate_api_key(db.inner(), &auth.tenant, id, grace).await {
+        Ok(Some(rotated)) => Ok(Json(serde_json::json!(rotated))),
+        Ok(None) => Err(error(Status::NotFound, "API key not found")),
+        Err(ApiKeyError::Invalid) => Err(error(
+            Status::Forbidden,
+            "only the key's creator or a team owner can do that",
+        )),
+        Err(_) => Err(error(Status::InternalServerError, "internal server error")),
+    }
 }
 
+/// Anyone may delete their own keys, even after being demoted to viewer; team owners may
+/// also delete the team's keys.
 #[delete("/api-keys/<id>")]
 pub async fn delete(
-    user: CurrentUser,
+    auth: AdminReader,
     db: &State<DatabaseConnection>,
     id: &str,
 ) -> Status {
     let Ok(id) = Uuid::parse_str(id) else {
         return Status::UnprocessableEntity;
     };
-    match api_keys::delete_api_key(db.inner(), id, &user.user_sub).await {
+    match api_keys::delete_api_key(db.inner(), &auth.tenant, id).await {
         Ok(()) => Status::NoContent,
         Err(ApiKeyError::Invalid) => Status::Forbidden,
         Err(_) => Status::InternalServerError,
@@ -61,5 +112,5 @@ pub async fn delete(
 }
 
 pub fn routes() -> Vec<rocket::Route> {
-    rocket::routes![list, create, delete]
+    rocket::routes![list, create, rotate, delete]
 }
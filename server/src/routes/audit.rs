@@ -0,0 +1,60 @@
+use rocket::http::Status;
+use rocket::{Route, State, get, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::AuditEvent;
+
+use crate::guards::current_user::CurrentUser;
+use crate::pagination::{PageRequest, Paginated};
+use crate::services::audit_service::{self, AuditQuery};
+
+/// Changes to the caller's brews, hydrometers, API keys and sessions, and to those of their
+/// teams. `since` and `until` are RFC 3339 timestamps.
+#[get(
+    "/audit-events?<action>&<target_type>&<target_id>&<actor>&<since>&<until>&<cursor>&<limit>&<order>"
+)]
+#[allow(clippy::too_many_arguments)]
+async fn list(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    action: Option<&str>,
+    target_type: Option<&str>,
+    target_id: Option<&str>,
+    actor: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+    cursor: Option<&str>,
+    limit: Option<u64>,
+    order: Option<&str>,
+) -> Result<Paginated<AuditEvent>, Status> {
+    let page = PageRequest::parse(cursor, order, limit).ok_or(Status::UnprocessableEntity)?;
+    let query = AuditQuery {
+        action: action.map(str::to_string),
+        target_type: target_type.map(str::to_string),
+        target_id: target_id
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|_| Status::UnprocessableEntity)?,
+        actor: actor.map(str::to_string),
+        since: since
+            .map(str::parse)
+            .transpose()
+            .map_err(|_| Status::UnprocessableEntity)?,
+        until: until
+            .map(str::parse)
+            .transpose()
+            .map_err(|_| Status::UnprocessableEntity)?,
+    };
+    audit_service::list(db.inner(), &user.tenant(), &query, &page)
+        .await
+        .map(Paginated)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to query audit events");
+            Status::InternalServerError
+        })
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![list]
+}
@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use openidconnect::{AuthorizationCode, Nonce, PkceCodeVerifier, TokenResponse, core::CoreTokenResponse};
 use rocket::{
     State, get, post,
@@ -70,6 +72,7 @@ pub async fn callback(
     oidc: &State<Option<OidcState>>,
     db: &State<DatabaseConnection>,
     cookies: &CookieJar<'_>,
+    ip: Option<IpAddr>,
 ) -> Result<Redirect, (Status, Json<serde_json::Value>)> {
     let oidc = oidc.as_ref().ok_or_else(|| {
         (
@@ -148,6 +151,7 @@ pub async fn callback(
             name,
             id_token_hash,
             expires_in_secs: 86400,
+            ip,
         },
     )
     .await
@@ -176,7 +180,7 @@ pub async fn logout(
     db: &State<DatabaseConnection>,
     cookies: &CookieJar<'_>,
 ) -> Json<serde_json::Value> {
-    let _ = sessions::delete_session(db, user.session_id).await;
+    let _ = sessions::delete_session(db, &user.tenant().actor, user.session_id).await;
     cookies.remove_private(SESSION_COOKIE);
     Json(serde_json::json!({ "ok": true }))
 }
@@ -4,27 +4,34 @@ use rocket::{Route, State, delete, get, post, put, routes};
 use sea_orm::DatabaseConnection;
 use uuid::Uuid;
 
-use shared::{BrewResponse, CreateBrew, UpdateBrew};
+use shared::{BrewAnalytics, BrewResponse, CreateBrew, UpdateBrew};
 
+use crate::events::{EventBus, StreamEvent};
+use crate::guards::auth_or_api_key::{Administrator, BrewsReader};
 use crate::guards::current_user::CurrentUser;
-use crate::services::brew_service;
+use crate::pagination::{PageRequest, Paginated};
+use crate::services::{analytics_service, brew_service, calibration_service};
 
-#[get("/brews?<status>")]
+#[get("/brews?<status>&<cursor>&<limit>&<order>")]
 async fn list(
-    _user: CurrentUser,
+    auth: BrewsReader,
     db: &State<DatabaseConnection>,
     status: Option<&str>,
-) -> Result<Json<Vec<BrewResponse>>, Status> {
-    brew_service::find_all(db.inner(), status)
+    cursor: Option<&str>,
+    limit: Option<u64>,
+    order: Option<&str>,
+) -> Result<Paginated<BrewResponse>, Status> {
+    let page = PageRequest::parse(cursor, order, limit).ok_or(Status::UnprocessableEntity)?;
+    brew_service::find_all(db.inner(), &auth.tenant, status, &page)
         .await
-        .map(Json)
+        .map(Paginated)
         .map_err(|_| Status::InternalServerError)
 }
 
 #[get("/brews/<id>")]
-async fn get_by_id(_user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Result<Json<BrewResponse>, Status> {
+async fn get_by_id(auth: BrewsReader, db: &State<DatabaseConnection>, id: &str) -> Result<Json<BrewResponse>, Status> {
     let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
-    match brew_service::find_by_id(db.inner(), id).await {
+    match brew_service::find_by_id(db.inner(), &auth.tenant, id).await {
         Ok(Some(b)) => Ok(Json(b)),
         Ok(None) => Err(Status::NotFound),
         Err(_) => Err(Status::InternalServerError),
@@ -33,43 +40,82 @@ async fn get_by_id(_user: CurrentUser, db: &State<DatabaseConnection>, id: &str)
 
 #[post("/brews", data = "<input>")]
 async fn create(
-    _user: CurrentUser,
+    auth: Administrator,
     db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
     input: Json<CreateBrew>,
 ) -> Result<(Status, Json<BrewResponse>), Status> {
-    brew_service::create(db.inner(), input.into_inner())
-        .await
-        .map(|b| (Status::Created, Json(b)))
-        .map_err(|_| Status::UnprocessableEntity)
+    match brew_service::create(db.inner(), &auth.tenant, input.into_inner()).await {
+        Ok(Some(b)) => {
+            bus.visibility_changed();
+            Ok((Status::Created, Json(b)))
+        }
+        Ok(None) | Err(_) => Err(Status::UnprocessableEntity),
+    }
 }
 
 #[put("/brews/<id>", data = "<input>")]
 async fn update(
-    _user: CurrentUser,
+    auth: Administrator,
     db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
     id: &str,
     input: Json<UpdateBrew>,
 ) -> Result<Json<BrewResponse>, Status> {
     let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
-    match brew_service::update(db.inner(), id, input.into_inner()).await {
-        Ok(Some(b)) => Ok(Json(b)),
+    match brew_service::update(db.inner(), &auth.tenant, id, input.into_inner()).await {
+        Ok(Some(b)) => {
+            bus.publish(StreamEvent::BrewStatus(b.clone()));
+            Ok(Json(b))
+        }
         Ok(None) => Err(Status::NotFound),
         Err(_) => Err(Status::InternalServerError),
     }
 }
 
 #[delete("/brews/<id>")]
-async fn delete(_user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Status {
+async fn delete(auth: Administrator, db: &State<DatabaseConnection>, id: &str) -> Status {
     let Ok(id) = Uuid::parse_str(id) else {
         return Status::UnprocessableEntity;
     };
-    match brew_service::delete(db.inner(), id).await {
+    match brew_service::delete(db.inner(), &auth.tenant, id).await {
         Ok(true) => Status::NoContent,
         Ok(false) => Status::NotFound,
         Err(_) => Status::InternalServerError,
     }
 }
 
+#[get("/brews/<id>/analytics")]
+async fn analytics(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<BrewAnalytics>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match analytics_service::for_brew(db.inner(), &user.tenant(), id).await {
+        Ok(Some(a)) => Ok(Json(a)),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/brews/<id>/recalibrate")]
+async fn recalibrate(
+    auth: Administrator,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<serde_json::Value>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match calibration_service::recalibrate_brew(db.inner(), &auth.tenant, id).await {
+        Ok(Some(count)) => Ok(Json(serde_json::json!({ "count": count }))),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            tracing::error!(brew_id = %id, error = %e, "Failed to recalibrate brew");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 pub fn routes() -> Vec<Route> {
-    routes![list, get_by_id, create, update, delete]
+    routes![list, get_by_id, create, update, delete, analytics, recalibrate]
 }
@@ -0,0 +1,129 @@
+use chrono::Utc;
+use rocket::http::{ContentType, Header, Status};
+use rocket::response::stream::TextStream;
+use rocket::{Responder, Route, State, get, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::{ExportFormat, ExportRecord};
+
+use crate::guards::current_user::CurrentUser;
+use crate::pagination::Cursor;
+use crate::services::{brew_service, export_service};
+
+/// A response the browser saves as `filename` instead of displaying.
+#[derive(Responder)]
+struct Download<R> {
+    inner: R,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+impl<R> Download<R> {
+    fn new(inner: R, content_type: ContentType, filename: &str) -> Self {
+        Download {
+            inner,
+            content_type,
+            disposition: Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        }
+    }
+}
+
+fn content_type(format: ExportFormat) -> ContentType {
+    match format {
+        ExportFormat::Csv => ContentType::CSV,
+        ExportFormat::Jsonl => ContentType::new("application", "x-ndjson"),
+        ExportFormat::BeerJson => ContentType::JSON,
+    }
+}
+
+/// Every reading for a brew, oldest first, streamed a page at a time. CSV is the default.
+/// Headers are sent before the first page is read, so a database error part-way through
+/// ends the download early rather than changing the status.
+#[get("/brews/<id>/export?<format>")]
+async fn brew_export(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    format: Option<&str>,
+) -> Result<Download<TextStream![String + 'static]>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    let tenant = user.tenant();
+    let format = match format {
+        Some(format) => ExportFormat::parse(format).ok_or(Status::UnprocessableEntity)?,
+        None => ExportFormat::Csv,
+    };
+    let brew = match brew_service::find_by_id(db.inner(), &tenant, id).await {
+        Ok(Some(b)) => b,
+        Ok(None) => return Err(Status::NotFound),
+        Err(_) => return Err(Status::InternalServerError),
+    };
+    let filename = format!(
+        "{}.{}",
+        export_service::file_stem(&brew.name, Utc::now()),
+        format.extension()
+    );
+    let db = db.inner().clone();
+
+    let stream = TextStream! {
+        let mut chunk = match format {
+            ExportFormat::Csv => export_service::CSV_HEADER.to_string(),
+            ExportFormat::Jsonl => export_service::jsonl_line(&ExportRecord::Brew(brew.clone())),
+            ExportFormat::BeerJson => {
+                match export_service::brew_beerjson(&db, &brew).await {
+                    Ok(document) => yield document.to_string(),
+                    Err(e) => tracing::error!(brew_id = %brew.id, error = %e, "Failed to export brew"),
+                }
+                return;
+            }
+        };
+        let mut after = None;
+        loop {
+            let (readings, next_cursor) = match export_service::readings_page(&db, &tenant, Some(brew.id), after).await {
+                Ok(page) => page,
+                Err(e) => {
+                    tracing::error!(brew_id = %brew.id, error = %e, "Failed to export readings");
+                    break;
+                }
+            };
+            for reading in readings {
+                chunk.push_str(&match format {
+                    ExportFormat::Csv => export_service::csv_row(&reading, Some(&brew.name)),
+                    _ => export_service::jsonl_line(&ExportRecord::Reading(reading)),
+                });
+            }
+            yield std::mem::take(&mut chunk);
+            after = next_cursor.as_deref().and_then(Cursor::decode);
+            if after.is_none() {
+                break;
+            }
+        }
+    };
+    Ok(Download::new(stream, content_type(format), &filename))
+}
+
+/// Zip archive of the caller's hydrometers, brews and readings.
+#[get("/export")]
+async fn account_export(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+) -> Result<Download<Vec<u8>>, Status> {
+    let archive = export_service::account_archive(db.inner(), &user.tenant())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to build export archive");
+            Status::InternalServerError
+        })?;
+    let filename = format!(
+        "{}.zip",
+        export_service::file_stem("tilt export", Utc::now())
+    );
+    Ok(Download::new(archive, ContentType::ZIP, &filename))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![brew_export, account_export]
+}
@@ -0,0 +1,82 @@
+use std::net::IpAddr;
+
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State, delete, get, post, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::{GatewayHeartbeat, GatewayResponse};
+
+use crate::guards::api_key::ApiKeyGuard;
+use crate::guards::current_user::CurrentUser;
+use crate::services::gateway_service::{self, GatewayConfig};
+
+#[post("/gateways/heartbeat", data = "<input>")]
+async fn heartbeat(
+    key: ApiKeyGuard,
+    db: &State<DatabaseConnection>,
+    ip: Option<IpAddr>,
+    input: Json<GatewayHeartbeat>,
+) -> Result<Status, (Status, Json<serde_json::Value>)> {
+    let input = input.into_inner();
+    if let Err(message) = gateway_service::validate(&input) {
+        return Err((
+            Status::UnprocessableEntity,
+            Json(serde_json::json!({ "error": message })),
+        ));
+    }
+    gateway_service::heartbeat(db.inner(), &key.key, input, ip.map(|ip| ip.to_string()))
+        .await
+        .map(|()| Status::NoContent)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to record gateway heartbeat");
+            (
+                Status::InternalServerError,
+                Json(serde_json::json!({ "error": "internal server error" })),
+            )
+        })
+}
+
+#[get("/gateways")]
+async fn list(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    config: &State<GatewayConfig>,
+) -> Result<Json<Vec<GatewayResponse>>, Status> {
+    gateway_service::list(db.inner(), &user.user_sub, config.inner())
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[get("/gateways/<id>")]
+async fn get(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    config: &State<GatewayConfig>,
+    id: &str,
+) -> Result<Json<GatewayResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match gateway_service::find(db.inner(), id, &user.user_sub, config.inner()).await {
+        Ok(Some(g)) => Ok(Json(g)),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[delete("/gateways/<id>")]
+async fn delete(user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Status {
+    let Ok(id) = Uuid::parse_str(id) else {
+        return Status::UnprocessableEntity;
+    };
+    match gateway_service::delete(db.inner(), id, &user.user_sub).await {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![heartbeat, list, get, delete]
+}
@@ -4,27 +4,44 @@ use rocket::{Route, State, delete, get, post, put, routes};
 use sea_orm::DatabaseConnection;
 use uuid::Uuid;
 
-use shared::{CreateHydrometer, HydrometerResponse, UpdateHydrometer};
+use shared::{
+    CalibrationPointResponse, CalibrationResponse, CreateCalibrationPoint, CreateHydrometer,
+    HydrometerResponse, SignalSample, UpdateHydrometer,
+};
 
+use crate::events::EventBus;
+use crate::guards::auth_or_api_key::{AdminReader, Administrator};
 use crate::guards::current_user::CurrentUser;
-use crate::services::hydrometer_service;
+use crate::guards::role::CanEdit;
+use crate::pagination::{PageRequest, Paginated};
+use crate::services::{calibration_service, hydrometer_service, reading_service};
 
-#[get("/hydrometers")]
-async fn list(_user: CurrentUser, db: &State<DatabaseConnection>) -> Result<Json<Vec<HydrometerResponse>>, Status> {
-    hydrometer_service::find_all(db.inner())
+/// Upper bound on `limit` for the signal history, so one request can't pull a whole table.
+const MAX_SIGNAL_SAMPLES: u64 = 10_000;
+
+#[get("/hydrometers?<cursor>&<limit>&<order>")]
+async fn list(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    cursor: Option<&str>,
+    limit: Option<u64>,
+    order: Option<&str>,
+) -> Result<Paginated<HydrometerResponse>, Status> {
+    let page = PageRequest::parse(cursor, order, limit).ok_or(Status::UnprocessableEntity)?;
+    hydrometer_service::find_all(db.inner(), &user.tenant(), &page)
         .await
-        .map(Json)
+        .map(Paginated)
         .map_err(|_| Status::InternalServerError)
 }
 
 #[get("/hydrometers/<id>")]
 async fn get_by_id(
-    _user: CurrentUser,
+    user: CurrentUser,
     db: &State<DatabaseConnection>,
     id: &str,
 ) -> Result<Json<HydrometerResponse>, Status> {
     let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
-    match hydrometer_service::find_by_id(db.inner(), id).await {
+    match hydrometer_service::find_by_id(db.inner(), &user.tenant(), id).await {
         Ok(Some(h)) => Ok(Json(h)),
         Ok(None) => Err(Status::NotFound),
         Err(_) => Err(Status::InternalServerError),
@@ -33,25 +50,27 @@ async fn get_by_id(
 
 #[post("/hydrometers", data = "<input>")]
 async fn create(
-    _user: CurrentUser,
+    user: CanEdit,
     db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
     input: Json<CreateHydrometer>,
 ) -> Result<(Status, Json<HydrometerResponse>), Status> {
-    hydrometer_service::create(db.inner(), input.into_inner())
+    let hydrometer = hydrometer_service::create(db.inner(), &user.tenant(), input.into_inner())
         .await
-        .map(|h| (Status::Created, Json(h)))
-        .map_err(|_| Status::UnprocessableEntity)
+        .map_err(|_| Status::UnprocessableEntity)?;
+    bus.visibility_changed();
+    Ok((Status::Created, Json(hydrometer)))
 }
 
 #[put("/hydrometers/<id>", data = "<input>")]
 async fn update(
-    _user: CurrentUser,
+    user: CanEdit,
     db: &State<DatabaseConnection>,
     id: &str,
     input: Json<UpdateHydrometer>,
 ) -> Result<Json<HydrometerResponse>, Status> {
     let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
-    match hydrometer_service::update(db.inner(), id, input.into_inner()).await {
+    match hydrometer_service::update(db.inner(), &user.tenant(), id, input.into_inner()).await {
         Ok(Some(h)) => Ok(Json(h)),
         Ok(None) => Err(Status::NotFound),
         Err(_) => Err(Status::InternalServerError),
@@ -59,17 +78,119 @@ async fn update(
 }
 
 #[delete("/hydrometers/<id>")]
-async fn delete(_user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Status {
+async fn delete(user: CanEdit, db: &State<DatabaseConnection>, id: &str) -> Status {
     let Ok(id) = Uuid::parse_str(id) else {
         return Status::UnprocessableEntity;
     };
-    match hydrometer_service::delete(db.inner(), id).await {
+    match hydrometer_service::delete(db.inner(), &user.tenant(), id).await {
         Ok(true) => Status::NoContent,
         Ok(false) => Status::NotFound,
         Err(_) => Status::InternalServerError,
     }
 }
 
+#[get("/hydrometers/<id>/calibration")]
+async fn get_calibration(
+    auth: AdminReader,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<CalibrationResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match calibration_service::find_for_hydrometer(db.inner(), &auth.tenant, id).await {
+        Ok(Some(c)) => Ok(Json(c)),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/hydrometers/<id>/calibration", data = "<input>")]
+async fn add_calibration_point(
+    auth: Administrator,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    input: Json<CreateCalibrationPoint>,
+) -> Result<(Status, Json<CalibrationPointResponse>), Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    let input = input.into_inner();
+    if !is_plausible_gravity(input.raw_gravity) || !is_plausible_gravity(input.actual_gravity) {
+        return Err(Status::UnprocessableEntity);
+    }
+    match calibration_service::add_point(db.inner(), &auth.tenant, id, input).await {
+        Ok(Some(p)) => Ok((Status::Created, Json(p))),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[delete("/hydrometers/<id>/calibration/<point_id>")]
+async fn delete_calibration_point(
+    auth: Administrator,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    point_id: &str,
+) -> Status {
+    let (Ok(id), Ok(point_id)) = (Uuid::parse_str(id), Uuid::parse_str(point_id)) else {
+        return Status::UnprocessableEntity;
+    };
+    match calibration_service::delete_point(db.inner(), &auth.tenant, id, point_id).await {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
+
+#[get("/hydrometers/<id>/signal?<since>&<until>&<limit>")]
+async fn signal_history(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    since: Option<&str>,
+    until: Option<&str>,
+    limit: Option<u64>,
+) -> Result<Json<Vec<SignalSample>>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    let since = since
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+    let until = until
+        .map(str::parse)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+    match hydrometer_service::find_visible(db.inner(), &user.tenant(), id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(Status::NotFound),
+        Err(_) => return Err(Status::InternalServerError),
+    }
+    reading_service::find_signal_history(
+        db.inner(),
+        id,
+        since,
+        until,
+        limit.unwrap_or(1_000).min(MAX_SIGNAL_SAMPLES),
+    )
+    .await
+    .map(Json)
+    .map_err(|e| {
+        tracing::error!(hydrometer_id = %id, error = %e, "Failed to query signal history");
+        Status::InternalServerError
+    })
+}
+
+fn is_plausible_gravity(sg: f64) -> bool {
+    (0.900..=1.200).contains(&sg)
+}
+
 pub fn routes() -> Vec<Route> {
-    routes![list, get_by_id, create, update, delete]
+    routes![
+        list,
+        get_by_id,
+        create,
+        update,
+        delete,
+        get_calibration,
+        add_calibration_point,
+        delete_calibration_point,
+        signal_history
+    ]
 }
@@ -0,0 +1,55 @@
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Route, State, get, routes};
+use sea_orm::DatabaseConnection;
+
+use crate::client_ip::client_ip;
+use crate::metrics::Metrics;
+
+/// Passes when the request carries `METRICS_TOKEN` as a bearer token or, when none is
+/// configured, comes from loopback. Without a token the endpoint is reported as missing to
+/// everyone else.
+struct MetricsAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for MetricsAccess {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(metrics) = req.rocket().state::<Metrics>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        let bearer = req
+            .headers()
+            .get_one("Authorization")
+            .and_then(|v| v.strip_prefix("Bearer "));
+        // Through a trusted proxy on the same host this is the proxied client, not loopback.
+        if metrics.authorized(bearer, client_ip(req)) {
+            Outcome::Success(MetricsAccess)
+        } else if metrics.requires_token() {
+            Outcome::Error((Status::Unauthorized, ()))
+        } else {
+            Outcome::Error((Status::NotFound, ()))
+        }
+    }
+}
+
+#[get("/metrics")]
+async fn metrics(
+    _access: MetricsAccess,
+    db: &State<DatabaseConnection>,
+    metrics: &State<Metrics>,
+) -> Result<(ContentType, String), Status> {
+    let body = metrics.render(db.inner()).await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to collect metrics");
+        Status::InternalServerError
+    })?;
+    Ok((
+        ContentType::new("text", "plain").with_params(("version", "0.0.4")),
+        body,
+    ))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![metrics]
+}
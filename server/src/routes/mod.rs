@@ -1,5 +1,12 @@
+pub mod alerts;
 pub mod api_keys;
+pub mod audit;
 pub mod auth;
 pub mod brews;
+pub mod exports;
+pub mod gateways;
 pub mod hydrometers;
+pub mod metrics;
+pub mod notifications;
 pub mod readings;
+pub mod teams;
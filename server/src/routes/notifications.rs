@@ -0,0 +1,145 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State, delete, get, post, put, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::{
+    CreateNotificationChannel, DeliveryStatus, NotificationChannelResponse,
+    NotificationDeliveryResponse, UpdateNotificationChannel,
+};
+
+use crate::guards::current_user::CurrentUser;
+use crate::notifications::Notifier;
+use crate::services::notification_service::{self, NotificationError};
+
+fn channel_error_status(e: NotificationError) -> Status {
+    match e {
+        NotificationError::Invalid(reason) => {
+            tracing::debug!(reason, "Rejected notification channel");
+            Status::UnprocessableEntity
+        }
+        NotificationError::Db(e) => {
+            tracing::error!(error = %e, "Failed to save notification channel");
+            Status::InternalServerError
+        }
+    }
+}
+
+#[get("/notification-channels")]
+async fn list(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+) -> Result<Json<Vec<NotificationChannelResponse>>, Status> {
+    notification_service::list_channels(db.inner(), &user.user_sub)
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[get("/notification-channels/<id>")]
+async fn get_by_id(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<NotificationChannelResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match notification_service::find_channel(db.inner(), id, &user.user_sub).await {
+        Ok(Some(c)) => Ok(Json(c)),
+        Ok(None) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
+#[post("/notification-channels", data = "<input>")]
+async fn create(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    input: Json<CreateNotificationChannel>,
+) -> Result<(Status, Json<NotificationChannelResponse>), Status> {
+    notification_service::create_channel(db.inner(), input.into_inner(), &user.user_sub)
+        .await
+        .map(|c| (Status::Created, Json(c)))
+        .map_err(channel_error_status)
+}
+
+#[put("/notification-channels/<id>", data = "<input>")]
+async fn update(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    input: Json<UpdateNotificationChannel>,
+) -> Result<Json<NotificationChannelResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match notification_service::update_channel(db.inner(), id, input.into_inner(), &user.user_sub)
+        .await
+    {
+        Ok(Some(c)) => Ok(Json(c)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => Err(channel_error_status(e)),
+    }
+}
+
+#[delete("/notification-channels/<id>")]
+async fn delete(user: CurrentUser, db: &State<DatabaseConnection>, id: &str) -> Status {
+    let Ok(id) = Uuid::parse_str(id) else {
+        return Status::UnprocessableEntity;
+    };
+    match notification_service::delete_channel(db.inner(), id, &user.user_sub).await {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(_) => Status::InternalServerError,
+    }
+}
+
+/// Sends immediately and returns the logged delivery; a failed send is reported in its
+/// `status`/`lastError` rather than as an HTTP error.
+#[post("/notification-channels/<id>/test")]
+async fn test_send(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    notifier: &State<Notifier>,
+    id: &str,
+) -> Result<Json<NotificationDeliveryResponse>, Status> {
+    let id = Uuid::parse_str(id).map_err(|_| Status::UnprocessableEntity)?;
+    match notification_service::send_test(db.inner(), notifier.inner(), id, &user.user_sub).await {
+        Ok(Some(d)) => Ok(Json(d)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            tracing::error!(channel_id = %id, error = %e, "Failed to send test notification");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/notification-deliveries?<channel_id>&<status>&<limit>")]
+async fn deliveries(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    channel_id: Option<&str>,
+    status: Option<&str>,
+    limit: Option<u64>,
+) -> Result<Json<Vec<NotificationDeliveryResponse>>, Status> {
+    let channel_id = channel_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+    let status = match status {
+        Some(s) => Some(DeliveryStatus::parse(s).ok_or(Status::UnprocessableEntity)?),
+        None => None,
+    };
+
+    notification_service::list_deliveries(db.inner(), &user.user_sub, channel_id, status, limit)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to query notification deliveries");
+            Status::InternalServerError
+        })
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        list, get_by_id, create, update, delete, test_send, deliveries
+    ]
+}
@@ -1,27 +1,70 @@
+use chrono::FixedOffset;
+use rocket::data::{Data, ToByteUnit};
 use rocket::http::Status;
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::Json;
-use rocket::{Route, State, get, post, routes};
-use sea_orm::DatabaseConnection;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Either, Route, Shutdown, State, get, post, routes};
+use sea_orm::{DatabaseConnection, DbErr};
 use uuid::Uuid;
 
-use shared::{CreateReadingsBatch, ReadingResponse, ReadingsQuery, TiltReading};
+use shared::tilt_csv::{self, LogOptions};
+use shared::{
+    CreateReadingsBatch, ImportReport, Page, ReadingBucket, ReadingResponse,
+    ReadingsBatchResult, ReadingsEvent, ReadingsQuery, SortOrder, TiltColor, TiltReading,
+};
 
-use crate::guards::auth_or_api_key::AuthOrApiKey;
+use crate::events::{Audience, EventBus, StreamEvent};
+use crate::guards::auth_or_api_key::{AuthOrApiKey, ReadingsReader};
 use crate::guards::current_user::CurrentUser;
-use crate::services::{brew_service, hydrometer_service, reading_service};
+use crate::guards::last_event_id::LastEventId;
+use crate::metrics::Metrics;
+use crate::pagination::{Cursor, PageRequest, Paginated};
+use crate::services::gateway_service::{self, GatewayConfig};
+use crate::services::reading_service::{BatchError, GatewaySource};
+use crate::services::{
+    alert_service, brew_service, calibration_service, hydrometer_service, import_service,
+    reading_service, team_service,
+};
+use crate::tenant::Tenant;
 
+/// Stores a batch of readings for the caller: the API key's owner, or the signed-in user.
+/// Batches sent with the API key of a registered gateway are attributed to it and collapsed
+/// against other gateways' readings of the same broadcasts. Keys limited to some colors
+/// have the whole batch refused if it contains any other.
 #[post("/readings", data = "<batch>")]
 async fn create_batch(
-    _auth: AuthOrApiKey,
+    auth: AuthOrApiKey,
     db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    gateways: &State<GatewayConfig>,
+    metrics: &State<Metrics>,
     batch: Json<CreateReadingsBatch>,
-) -> Result<(Status, Json<serde_json::Value>), Status> {
+) -> Result<(Status, Json<ReadingsBatchResult>), Status> {
     let readings: Vec<TiltReading> = batch.into_inner().0;
+    let mut result = ReadingsBatchResult::default();
     if readings.is_empty() {
-        return Ok((Status::Created, Json(serde_json::json!({ "count": 0 }))));
+        return Ok((Status::Created, Json(result)));
+    }
+    if !readings.iter().all(|r| auth.tenant.allows_color(&r.color)) {
+        return Err(Status::Forbidden);
     }
 
-    let mut total_count: u64 = 0;
+    let source = match &auth.api_key {
+        Some(key) => gateway_service::find_by_api_key(db.inner(), key.id)
+            .await
+            .map_err(|e| {
+                tracing::error!(api_key_id = %key.id, error = %e, "Failed to look up gateway");
+                Status::InternalServerError
+            })?
+            .map(|gateway| GatewaySource {
+                gateway_id: gateway.id,
+                window: gateways.dedup_window,
+            }),
+        None => None,
+    };
+
+    let mut hydrometer_ids = Vec::new();
 
     let mut grouped: std::collections::HashMap<shared::TiltColor, Vec<TiltReading>> =
         std::collections::HashMap::new();
@@ -30,12 +73,16 @@ async fn create_batch(
     }
 
     for (color, batch_readings) in grouped {
-        let hydrometer = hydrometer_service::find_or_create_by_color(db.inner(), &color)
-            .await
-            .map_err(|e| {
-                tracing::error!(color = ?color, error = %e, "Failed to find/create hydrometer");
-                Status::InternalServerError
-            })?;
+        let (hydrometer, created) =
+            hydrometer_service::find_or_create_by_color(db.inner(), &auth.tenant, &color)
+                .await
+                .map_err(|e| {
+                    tracing::error!(color = ?color, error = %e, "Failed to find/create hydrometer");
+                    Status::InternalServerError
+                })?;
+        if created {
+            bus.visibility_changed();
+        }
 
         let active_brew = brew_service::find_active_for_hydrometer(db.inner(), hydrometer.id).await;
         let brew_id = match active_brew {
@@ -44,50 +91,297 @@ async fn create_batch(
             Err(_) => None,
         };
 
-        let count =
-            reading_service::batch_create(db.inner(), batch_readings, hydrometer.id, brew_id)
-                .await
-                .map_err(|e| {
-                    tracing::error!(hydrometer_id = %hydrometer.id, error = %e, "Failed to batch create readings");
-                    Status::InternalServerError
-                })?;
+        let calibration = calibration_service::load_for_hydrometer(db.inner(), &hydrometer)
+            .await
+            .map_err(|e| {
+                tracing::error!(hydrometer_id = %hydrometer.id, error = %e, "Failed to load calibration");
+                Status::InternalServerError
+            })?;
+
+        let sent = batch_readings.len() as u64;
+        let created = reading_service::batch_create(
+            db.inner(),
+            batch_readings,
+            &hydrometer,
+            brew_id,
+            &calibration,
+            source,
+        )
+        .await
+        .map_err(|e| match e {
+            BatchError::IdConflict => Status::Conflict,
+            BatchError::Db(e) => {
+                tracing::error!(hydrometer_id = %hydrometer.id, error = %e, "Failed to batch create readings");
+                Status::InternalServerError
+            }
+        })?;
 
-        total_count += count;
+        let inserted = created.inserted;
+        result.inserted += inserted.len() as u64;
+        result.count = result.inserted;
+        result.replaced += created.replaced;
+        metrics.readings_ingested(hydrometer.id, color, inserted.len());
+        result.duplicates += sent - inserted.len() as u64;
+        if inserted.is_empty() {
+            continue;
+        }
+        hydrometer_ids.push(hydrometer.id);
+        bus.publish(StreamEvent::Readings(ReadingsEvent {
+            hydrometer_id: hydrometer.id,
+            brew_id,
+            readings: inserted
+                .into_iter()
+                .map(|m| reading_service::model_to_response(m, color))
+                .collect(),
+        }));
     }
 
-    Ok((
-        Status::Created,
-        Json(serde_json::json!({ "count": total_count })),
-    ))
+    // Readings are already stored; a failed rule check shouldn't make the client resend them.
+    match alert_service::evaluate_for_hydrometers(db.inner(), &hydrometer_ids).await {
+        Ok(transitions) => {
+            for alert in transitions {
+                bus.publish(StreamEvent::Alert(alert));
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to evaluate alert rules"),
+    }
+
+    Ok((Status::Created, Json(result)))
 }
 
-#[get("/readings?<brew_id>&<hydrometer_id>&<since>&<until>&<limit>")]
+/// Largest log accepted by `POST /readings/import`.
+const IMPORT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Imports a Tilt app / TiltPi CSV log sent as the request body. `color` is used for logs
+/// without a color column and `utc_offset_minutes` for timestamps without a zone. Beer names
+/// that match no brew get a new completed brew unless `create_brews=false`.
+#[post("/readings/import?<color>&<utc_offset_minutes>&<create_brews>", data = "<data>")]
+async fn import(
+    auth: AuthOrApiKey,
+    db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    color: Option<&str>,
+    utc_offset_minutes: Option<i32>,
+    create_brews: Option<bool>,
+    data: Data<'_>,
+) -> Result<Json<ImportReport>, (Status, Json<serde_json::Value>)> {
+    let invalid = |msg: &str| {
+        (
+            Status::UnprocessableEntity,
+            Json(serde_json::json!({ "error": msg })),
+        )
+    };
+    let options = LogOptions {
+        color: match color {
+            Some(c) => Some(TiltColor::parse(c).ok_or_else(|| invalid("unknown color"))?),
+            None => None,
+        },
+        utc_offset: FixedOffset::east_opt(utc_offset_minutes.unwrap_or(0) * 60)
+            .ok_or_else(|| invalid("utc_offset_minutes out of range"))?,
+    };
+
+    let body = data
+        .open(IMPORT_MAX_BYTES.bytes())
+        .into_string()
+        .await
+        .map_err(|_| invalid("body is not valid UTF-8"))?;
+    if !body.is_complete() {
+        return Err((
+            Status::PayloadTooLarge,
+            Json(serde_json::json!({ "error": "log too large" })),
+        ));
+    }
+
+    let log = tilt_csv::parse(&body, &options).map_err(|e| invalid(&e))?;
+    if !log.rows.iter().all(|r| auth.tenant.allows_color(&r.color)) {
+        return Err((
+            Status::Forbidden,
+            Json(serde_json::json!({ "error": "API key is not allowed to write these colors" })),
+        ));
+    }
+    let report =
+        import_service::import(db.inner(), &auth.tenant, log, create_brews.unwrap_or(true))
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Failed to import readings");
+                (
+                    Status::InternalServerError,
+                    Json(serde_json::json!({ "error": "import failed" })),
+                )
+            })?;
+    // The import may have registered hydrometers or created brews.
+    bus.visibility_changed();
+    Ok(Json(report))
+}
+
+/// Raw readings by default, paged with `cursor`. `bucket` switches to per-bucket aggregates
+/// and `max_points` to a shape-preserving downsample; neither can be combined with the
+/// other or with `cursor`, and both return a single page.
+#[get(
+    "/readings?<brew_id>&<hydrometer_id>&<since>&<until>&<limit>&<bucket>&<max_points>&<cursor>&<order>"
+)]
+#[allow(clippy::too_many_arguments)]
 async fn query(
-    _user: CurrentUser,
+    auth: ReadingsReader,
     db: &State<DatabaseConnection>,
     brew_id: Option<&str>,
     hydrometer_id: Option<&str>,
     since: Option<&str>,
     until: Option<&str>,
     limit: Option<u64>,
-) -> Result<Json<Vec<ReadingResponse>>, Status> {
+    bucket: Option<&str>,
+    max_points: Option<u64>,
+    cursor: Option<&str>,
+    order: Option<&str>,
+) -> Result<Either<Paginated<ReadingResponse>, Paginated<ReadingBucket>>, Status> {
+    let order = order
+        .map(|o| SortOrder::parse(o).ok_or(Status::UnprocessableEntity))
+        .transpose()?;
     let query = ReadingsQuery {
         brew_id: brew_id.and_then(|s| Uuid::parse_str(s).ok()),
         hydrometer_id: hydrometer_id.and_then(|s| Uuid::parse_str(s).ok()),
         since: since.and_then(|s| s.parse().ok()),
         until: until.and_then(|s| s.parse().ok()),
         limit,
+        bucket: bucket.map(str::to_string),
+        max_points,
+        cursor: cursor.map(str::to_string),
+        order,
+    };
+    let tenant = auth.tenant;
+    let log_error = |e: sea_orm::DbErr| {
+        tracing::error!(error = %e, "Failed to query readings");
+        Status::InternalServerError
     };
 
-    reading_service::find_filtered(db.inner(), &query)
+    match (query.bucket.is_some(), query.max_points) {
+        (true, Some(_)) | (false, Some(0)) => Err(Status::UnprocessableEntity),
+        (true, None) | (false, Some(_)) if query.cursor.is_some() => {
+            Err(Status::UnprocessableEntity)
+        }
+        (true, None) => {
+            let seconds = query.bucket_seconds().ok_or(Status::UnprocessableEntity)?;
+            reading_service::find_bucketed(db.inner(), &tenant, &query, seconds)
+                .await
+                .map(|b| Either::Right(Paginated(Page::last(b))))
+                .map_err(log_error)
+        }
+        (false, Some(max_points)) => {
+            reading_service::find_downsampled(db.inner(), &tenant, &query, max_points as usize)
+                .await
+                .map(|r| Either::Left(Paginated(Page::last(r))))
+                .map_err(log_error)
+        }
+        (false, None) => {
+            let page = PageRequest {
+                cursor: query
+                    .cursor
+                    .as_deref()
+                    .map(|c| Cursor::decode(c).ok_or(Status::UnprocessableEntity))
+                    .transpose()?,
+                order: query.order.unwrap_or_default(),
+                limit: query.limit_or_default(),
+            };
+            reading_service::find_filtered(db.inner(), &tenant, &query, &page)
+                .await
+                .map(|r| Either::Left(Paginated(r)))
+                .map_err(log_error)
+        }
+    }
+}
+
+/// Server-sent events for new readings, alert transitions and brew status changes,
+/// optionally narrowed to one brew and/or hydrometer. Only events about brews and
+/// hydrometers the caller can currently see are sent. Reconnecting clients get any missed
+/// events still in memory via `Last-Event-ID`.
+#[get("/readings/stream?<brew_id>&<hydrometer_id>")]
+async fn stream(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    last_event_id: LastEventId,
+    brew_id: Option<&str>,
+    hydrometer_id: Option<&str>,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![Event + 'static], Status> {
+    let brew_id = brew_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+    let hydrometer_id = hydrometer_id
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| Status::UnprocessableEntity)?;
+    let db = db.inner().clone();
+    let bus = bus.inner().clone();
+    let (user_sub, team_id) = (user.user_sub, user.team_id);
+    let mut version = bus.visibility_version();
+    let mut audience = resolve_audience(&db, &user_sub, team_id)
         .await
-        .map(Json)
-        .map_err(|e| {
-            tracing::error!(error = %e, "Failed to query readings");
-            Status::InternalServerError
-        })
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::Forbidden)?;
+    let (replay, mut receiver) = bus.subscribe(last_event_id.0);
+
+    Ok(EventStream! {
+        for envelope in replay {
+            if audience.admits(&envelope.event) && envelope.event.matches(brew_id, hydrometer_id) {
+                yield envelope.to_sse();
+            }
+        }
+        loop {
+            let envelope = rocket::tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(envelope) => envelope,
+                    // Dropping a lagging client makes its EventSource reconnect and
+                    // catch up from history with Last-Event-ID.
+                    Err(RecvError::Lagged(_)) | Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            let current = bus.visibility_version();
+            if current != version {
+                version = current;
+                match resolve_audience(&db, &user_sub, team_id).await {
+                    Ok(Some(resolved)) => audience = resolved,
+                    // No longer a member of the team the stream was opened in.
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to refresh stream audience");
+                        break;
+                    }
+                }
+            }
+            if audience.admits(&envelope.event) && envelope.event.matches(brew_id, hydrometer_id) {
+                yield envelope.to_sse();
+            }
+        }
+    })
+}
+
+/// What a stream subscriber can see, from their current team memberships. `None` if they
+/// are acting in a team they no longer belong to.
+async fn resolve_audience(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    team_id: Option<Uuid>,
+) -> Result<Option<Audience>, DbErr> {
+    let memberships = team_service::memberships_for(db, user_sub).await?;
+    if team_id.is_some_and(|id| !memberships.iter().any(|m| m.team_id == id)) {
+        return Ok(None);
+    }
+    let tenant = Tenant::member(user_sub, &memberships, team_id);
+    Ok(Some(Audience {
+        brews: brew_service::visible_ids(db, &tenant)
+            .await?
+            .into_iter()
+            .collect(),
+        hydrometers: hydrometer_service::visible_ids(db, &tenant)
+            .await?
+            .into_iter()
+            .collect(),
+    }))
 }
 
 pub fn routes() -> Vec<Route> {
-    routes![create_batch, query]
+    routes![create_batch, import, query, stream]
 }
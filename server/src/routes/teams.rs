@@ -0,0 +1,198 @@
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State, delete, get, post, put, routes};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use shared::{
+    CreateInvitation, CreateTeam, InvitationResponse, TeamMemberResponse, TeamResponse, TeamRole,
+    UpdateTeamMember,
+};
+
+use crate::events::EventBus;
+use crate::guards::current_user::CurrentUser;
+use crate::services::team_service::{self, TeamConfig, TeamError};
+
+type ApiError = (Status, Json<serde_json::Value>);
+
+fn error(status: Status, message: &str) -> ApiError {
+    (status, Json(serde_json::json!({ "error": message })))
+}
+
+fn internal(e: impl std::fmt::Display) -> ApiError {
+    tracing::error!(error = %e, "Team request failed");
+    error(Status::InternalServerError, "internal server error")
+}
+
+fn team_error(e: TeamError) -> ApiError {
+    match e {
+        TeamError::LastOwner => error(
+            Status::Conflict,
+            "a team needs at least one owner; delete the team instead",
+        ),
+        TeamError::InvalidInvitation => error(
+            Status::NotFound,
+            "invitation not found, already used or expired",
+        ),
+        TeamError::Db(e) => internal(e),
+    }
+}
+
+/// Members may look at a team; only its owners may manage it. Teams the user isn't in are
+/// reported as missing.
+fn authorize(user: &CurrentUser, id: &str, required: TeamRole) -> Result<Uuid, ApiError> {
+    let id =
+        Uuid::parse_str(id).map_err(|_| error(Status::UnprocessableEntity, "invalid team id"))?;
+    match user.role_in(id) {
+        Some(role) if role >= required => Ok(id),
+        Some(_) => Err(error(Status::Forbidden, "only team owners can do that")),
+        None => Err(error(Status::NotFound, "team not found")),
+    }
+}
+
+#[get("/teams")]
+async fn list(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+) -> Result<Json<Vec<TeamResponse>>, Status> {
+    team_service::list_teams(db.inner(), &user)
+        .await
+        .map(Json)
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[post("/teams", data = "<input>")]
+async fn create(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    input: Json<CreateTeam>,
+) -> Result<(Status, Json<TeamResponse>), ApiError> {
+    let name = input.into_inner().name.trim().to_string();
+    if name.is_empty() {
+        return Err(error(Status::UnprocessableEntity, "name is required"));
+    }
+    team_service::create_team(db.inner(), &user, name)
+        .await
+        .map(|team| (Status::Created, Json(team)))
+        .map_err(internal)
+}
+
+#[delete("/teams/<id>")]
+async fn delete_team(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    id: &str,
+) -> Result<Status, ApiError> {
+    let id = authorize(&user, id, TeamRole::Owner)?;
+    match team_service::delete_team(db.inner(), id).await {
+        Ok(true) => {
+            bus.visibility_changed();
+            Ok(Status::NoContent)
+        }
+        Ok(false) => Err(error(Status::NotFound, "team not found")),
+        Err(e) => Err(internal(e)),
+    }
+}
+
+#[get("/teams/<id>/members")]
+async fn members(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+) -> Result<Json<Vec<TeamMemberResponse>>, ApiError> {
+    let id = authorize(&user, id, TeamRole::Viewer)?;
+    team_service::list_members(db.inner(), id)
+        .await
+        .map(Json)
+        .map_err(internal)
+}
+
+#[put("/teams/<id>/members/<user_sub>", data = "<input>")]
+async fn update_member(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    id: &str,
+    user_sub: &str,
+    input: Json<UpdateTeamMember>,
+) -> Result<Json<TeamMemberResponse>, ApiError> {
+    let id = authorize(&user, id, TeamRole::Owner)?;
+    match team_service::update_member(db.inner(), id, user_sub, input.into_inner().role).await {
+        Ok(Some(member)) => Ok(Json(member)),
+        Ok(None) => Err(error(Status::NotFound, "member not found")),
+        Err(e) => Err(team_error(e)),
+    }
+}
+
+/// Owners remove members; anyone may remove themselves to leave the team.
+#[delete("/teams/<id>/members/<user_sub>")]
+async fn remove_member(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    id: &str,
+    user_sub: &str,
+) -> Result<Status, ApiError> {
+    let required = if user_sub == user.user_sub {
+        TeamRole::Viewer
+    } else {
+        TeamRole::Owner
+    };
+    let id = authorize(&user, id, required)?;
+    match team_service::remove_member(db.inner(), id, user_sub).await {
+        Ok(true) => {
+            bus.visibility_changed();
+            Ok(Status::NoContent)
+        }
+        Ok(false) => Err(error(Status::NotFound, "member not found")),
+        Err(e) => Err(team_error(e)),
+    }
+}
+
+#[post("/teams/<id>/invitations", data = "<input>")]
+async fn invite(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    config: &State<TeamConfig>,
+    id: &str,
+    input: Json<CreateInvitation>,
+) -> Result<(Status, Json<InvitationResponse>), ApiError> {
+    let id = authorize(&user, id, TeamRole::Owner)?;
+    team_service::create_invitation(
+        db.inner(),
+        config.inner(),
+        id,
+        input.into_inner().role,
+        &user.user_sub,
+    )
+    .await
+    .map(|invitation| (Status::Created, Json(invitation)))
+    .map_err(internal)
+}
+
+#[post("/invitations/<token>/accept")]
+async fn accept(
+    user: CurrentUser,
+    db: &State<DatabaseConnection>,
+    bus: &State<EventBus>,
+    token: &str,
+) -> Result<Json<TeamResponse>, ApiError> {
+    let team = team_service::accept_invitation(db.inner(), token, &user)
+        .await
+        .map_err(team_error)?;
+    bus.visibility_changed();
+    Ok(Json(team))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        list,
+        create,
+        delete_team,
+        members,
+        update_member,
+        remove_member,
+        invite,
+        accept
+    ]
+}
@@ -0,0 +1,746 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::alert_rules::{self, Entity as AlertRule};
+use crate::models::entities::alerts::{self, Entity as Alert};
+use crate::models::entities::brews::{self, Entity as Brew};
+use crate::models::entities::readings::{self, Entity as Reading};
+use crate::services::analytics_service::Sample;
+use crate::services::{brew_service, hydrometer_service, notification_service};
+use crate::tenant::Tenant;
+use shared::{
+    AlertResponse, AlertRuleKind, AlertRuleResponse, AlertState, CreateAlertRule, Notification,
+    NotificationEvent, UpdateAlertRule,
+};
+
+/// Default movement (gravity points) below which a fermentation counts as stalled.
+const DEFAULT_STALL_POINTS: f64 = 1.0;
+const DEFAULT_ALERT_LIMIT: u64 = 100;
+
+#[derive(Debug)]
+pub enum AlertRuleError {
+    Invalid(&'static str),
+    Db(DbErr),
+}
+
+impl From<DbErr> for AlertRuleError {
+    fn from(e: DbErr) -> Self {
+        AlertRuleError::Db(e)
+    }
+}
+
+/// What a rule is checked against: the readings for its brew or hydrometer.
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    pub latest: Option<Sample>,
+    /// Readings inside the rule's lookback window, oldest first. Only loaded for stall rules.
+    pub window: Vec<Sample>,
+    /// Whether any reading predates the lookback window, i.e. the window is fully covered.
+    pub history_before_window: bool,
+    pub target_fg: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breach {
+    pub value: Option<f64>,
+    pub message: String,
+}
+
+/// Checks the fields a rule kind needs and that exactly one scope is set.
+pub fn validate(
+    kind: AlertRuleKind,
+    brew_id: Option<Uuid>,
+    hydrometer_id: Option<Uuid>,
+    threshold: Option<f64>,
+    duration_minutes: Option<i32>,
+) -> Result<(), &'static str> {
+    if brew_id.is_some() == hydrometer_id.is_some() {
+        return Err("exactly one of brewId or hydrometerId is required");
+    }
+    if duration_minutes.is_some_and(|m| m <= 0) {
+        return Err("durationMinutes must be positive");
+    }
+    match kind {
+        AlertRuleKind::TemperatureAbove | AlertRuleKind::TemperatureBelow
+            if threshold.is_none() =>
+        {
+            Err("temperature rules require a threshold")
+        }
+        AlertRuleKind::GravityStalled | AlertRuleKind::NoReading if duration_minutes.is_none() => {
+            Err("this rule requires durationMinutes")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Evaluates a single rule against `observation`. Returns `None` when the condition is clear
+/// or there isn't enough data to decide.
+pub fn check(
+    kind: AlertRuleKind,
+    threshold: Option<f64>,
+    duration: Option<Duration>,
+    observation: &Observation,
+    now: DateTime<Utc>,
+) -> Option<Breach> {
+    match kind {
+        AlertRuleKind::TemperatureAbove => {
+            let (latest, threshold) = (observation.latest?, threshold?);
+            (latest.temperature_f > threshold).then(|| Breach {
+                value: Some(latest.temperature_f),
+                message: format!(
+                    "Temperature {:.1}°F is above {:.1}°F",
+                    latest.temperature_f, threshold
+                ),
+            })
+        }
+        AlertRuleKind::TemperatureBelow => {
+            let (latest, threshold) = (observation.latest?, threshold?);
+            (latest.temperature_f < threshold).then(|| Breach {
+                value: Some(latest.temperature_f),
+                message: format!(
+                    "Temperature {:.1}°F is below {:.1}°F",
+                    latest.temperature_f, threshold
+                ),
+            })
+        }
+        AlertRuleKind::GravityReachedTarget => {
+            let latest = observation.latest?;
+            let target = threshold.or(observation.target_fg)?;
+            (latest.gravity <= target).then(|| Breach {
+                value: Some(latest.gravity),
+                message: format!("Gravity {:.3} reached target {:.3}", latest.gravity, target),
+            })
+        }
+        AlertRuleKind::NoReading => {
+            let duration = duration?;
+            match observation.latest {
+                None => Some(Breach {
+                    value: None,
+                    message: "No readings received yet".to_string(),
+                }),
+                Some(latest) if now - latest.recorded_at >= duration => {
+                    let minutes = (now - latest.recorded_at).num_minutes();
+                    Some(Breach {
+                        value: Some(minutes as f64),
+                        message: format!("No reading for {} minutes", minutes),
+                    })
+                }
+                Some(_) => None,
+            }
+        }
+        AlertRuleKind::GravityStalled => {
+            let duration = duration?;
+            let latest = observation.latest?;
+            // A window that isn't fully covered yet, or has gone quiet, isn't a stall.
+            if !observation.history_before_window || observation.window.len() < 2 {
+                return None;
+            }
+            if observation.target_fg.is_some_and(|fg| latest.gravity <= fg) {
+                return None;
+            }
+            let (min, max) = observation
+                .window
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), s| {
+                    (min.min(s.gravity), max.max(s.gravity))
+                });
+            let moved = (max - min) * 1000.0;
+            let limit = threshold.unwrap_or(DEFAULT_STALL_POINTS);
+            (moved <= limit).then(|| Breach {
+                value: Some(moved),
+                message: format!(
+                    "Gravity moved {:.1} points in {} hours (now {:.3})",
+                    moved,
+                    duration.num_hours(),
+                    latest.gravity
+                ),
+            })
+        }
+    }
+}
+
+fn rule_kind(rule: &alert_rules::Model) -> AlertRuleKind {
+    AlertRuleKind::parse(&rule.kind).unwrap_or(AlertRuleKind::NoReading)
+}
+
+fn rule_to_response(rule: alert_rules::Model, firing: bool) -> AlertRuleResponse {
+    AlertRuleResponse {
+        id: rule.id,
+        kind: rule_kind(&rule),
+        name: rule.name,
+        brew_id: rule.brew_id,
+        hydrometer_id: rule.hydrometer_id,
+        threshold: rule.threshold,
+        duration_minutes: rule.duration_minutes,
+        enabled: rule.enabled,
+        firing,
+        created_at: rule.created_at.into(),
+        updated_at: rule.updated_at.into(),
+    }
+}
+
+fn alert_to_response(alert: alerts::Model, rule: &alert_rules::Model) -> AlertResponse {
+    AlertResponse {
+        id: alert.id,
+        rule_id: rule.id,
+        rule_name: rule.name.clone(),
+        kind: rule_kind(rule),
+        brew_id: rule.brew_id,
+        hydrometer_id: rule.hydrometer_id,
+        state: match alert.state.as_str() {
+            "Firing" => AlertState::Firing,
+            _ => AlertState::Resolved,
+        },
+        message: alert.message,
+        value: alert.value,
+        fired_at: alert.fired_at.into(),
+        resolved_at: alert.resolved_at.map(Into::into),
+    }
+}
+
+async fn open_alert_for(
+    db: &DatabaseConnection,
+    rule_id: Uuid,
+) -> Result<Option<alerts::Model>, DbErr> {
+    Alert::find()
+        .filter(alerts::Column::RuleId.eq(rule_id))
+        .filter(alerts::Column::State.eq("Firing"))
+        .one(db)
+        .await
+}
+
+/// Loads the readings a rule looks at. Returns `None` when the rule shouldn't be checked
+/// at all: it's disabled, or its brew is gone or no longer active.
+async fn observe(
+    db: &DatabaseConnection,
+    rule: &alert_rules::Model,
+    now: DateTime<Utc>,
+) -> Result<Option<Observation>, DbErr> {
+    if !rule.enabled {
+        return Ok(None);
+    }
+
+    let (scope, target_fg) = if let Some(brew_id) = rule.brew_id {
+        let Some(brew) = Brew::find_by_id(brew_id).one(db).await? else {
+            return Ok(None);
+        };
+        if brew.status != "Active" {
+            return Ok(None);
+        }
+        (readings::Column::BrewId.eq(brew_id), brew.target_fg)
+    } else if let Some(hydrometer_id) = rule.hydrometer_id {
+        let active = Brew::find()
+            .filter(brews::Column::HydrometerId.eq(hydrometer_id))
+            .filter(brews::Column::Status.eq("Active"))
+            .one(db)
+            .await?;
+        (
+            readings::Column::HydrometerId.eq(hydrometer_id),
+            active.and_then(|b| b.target_fg),
+        )
+    } else {
+        return Ok(None);
+    };
+
+    let latest = Reading::find()
+        .filter(scope.clone())
+        .order_by_desc(readings::Column::RecordedAt)
+        .one(db)
+        .await?;
+
+    let mut observation = Observation {
+        latest: latest.as_ref().map(Sample::from),
+        target_fg,
+        ..Default::default()
+    };
+
+    if rule_kind(rule) == AlertRuleKind::GravityStalled
+        && let Some(minutes) = rule.duration_minutes
+    {
+        let start = now - Duration::minutes(minutes as i64);
+        observation.window = Reading::find()
+            .filter(scope.clone())
+            .filter(readings::Column::RecordedAt.gte(start))
+            .order_by_asc(readings::Column::RecordedAt)
+            .all(db)
+            .await?
+            .iter()
+            .map(Sample::from)
+            .collect();
+        observation.history_before_window = Reading::find()
+            .filter(scope)
+            .filter(readings::Column::RecordedAt.lt(start))
+            .one(db)
+            .await?
+            .is_some();
+    }
+
+    Ok(Some(observation))
+}
+
+/// Checks one rule and records a state change: a new `Firing` alert when the condition
+/// starts, or resolving the open alert once it clears. Repeated breaches while an alert is
+/// already open are ignored.
+async fn evaluate_rule(
+    db: &DatabaseConnection,
+    rule: &alert_rules::Model,
+    now: DateTime<Utc>,
+) -> Result<Option<AlertResponse>, DbErr> {
+    let breach = observe(db, rule, now).await?.and_then(|observation| {
+        check(
+            rule_kind(rule),
+            rule.threshold,
+            rule.duration_minutes.map(|m| Duration::minutes(m as i64)),
+            &observation,
+            now,
+        )
+    });
+    let open = open_alert_for(db, rule.id).await?;
+
+    let transition = match (breach, open) {
+        (Some(breach), None) => {
+            let alert = alerts::Model {
+                id: Uuid::new_v4(),
+                rule_id: rule.id,
+                state: "Firing".to_string(),
+                message: breach.message,
+                value: breach.value,
+                fired_at: now.into(),
+                resolved_at: None,
+            };
+            // The partial unique index on open alerts turns a concurrent double-fire into a no-op.
+            let inserted = Alert::insert(alert.clone().into_active_model())
+                .on_conflict(OnConflict::new().do_nothing().to_owned())
+                .exec_without_returning(db)
+                .await?;
+            if inserted == 0 {
+                return Ok(None);
+            }
+            tracing::info!(rule_id = %rule.id, message = %alert.message, "Alert firing");
+            alert_to_response(alert, rule)
+        }
+        (None, Some(open)) => {
+            let mut active: alerts::ActiveModel = open.into();
+            active.state = Set("Resolved".to_string());
+            active.resolved_at = Set(Some(now.into()));
+            let alert = active.update(db).await?;
+            tracing::info!(rule_id = %rule.id, "Alert resolved");
+            alert_to_response(alert, rule)
+        }
+        _ => return Ok(None),
+    };
+
+    // The alert is already recorded; a queueing failure only costs the notification.
+    if let Err(e) =
+        notification_service::notify_user(db, &rule.created_by, &notification_for(&transition))
+            .await
+    {
+        tracing::warn!(rule_id = %rule.id, error = %e, "Failed to queue alert notification");
+    }
+    Ok(Some(transition))
+}
+
+fn notification_for(alert: &AlertResponse) -> Notification {
+    let (event, title, occurred_at) = match alert.state {
+        AlertState::Firing => (
+            NotificationEvent::AlertFiring,
+            format!("{} firing", alert.rule_name),
+            alert.fired_at,
+        ),
+        AlertState::Resolved => (
+            NotificationEvent::AlertResolved,
+            format!("{} resolved", alert.rule_name),
+            alert.resolved_at.unwrap_or(alert.fired_at),
+        ),
+    };
+    Notification {
+        event,
+        title,
+        message: alert.message.clone(),
+        brew_id: alert.brew_id,
+        hydrometer_id: alert.hydrometer_id,
+        alert: Some(alert.clone()),
+        occurred_at,
+    }
+}
+
+async fn evaluate_rules(
+    db: &DatabaseConnection,
+    rules: Vec<alert_rules::Model>,
+) -> Result<Vec<AlertResponse>, DbErr> {
+    let now = Utc::now();
+    let mut transitions = Vec::new();
+    for rule in rules {
+        if let Some(transition) = evaluate_rule(db, &rule, now).await? {
+            transitions.push(transition);
+        }
+    }
+    Ok(transitions)
+}
+
+/// Evaluates the rules touched by new readings from these hydrometers: rules on the
+/// hydrometers themselves and on their active brews. Returns the alerts that changed state.
+pub async fn evaluate_for_hydrometers(
+    db: &DatabaseConnection,
+    hydrometer_ids: &[Uuid],
+) -> Result<Vec<AlertResponse>, DbErr> {
+    if hydrometer_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let brew_ids: Vec<Uuid> = Brew::find()
+        .filter(brews::Column::HydrometerId.is_in(hydrometer_ids.to_vec()))
+        .filter(brews::Column::Status.eq("Active"))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+
+    let rules = AlertRule::find()
+        .filter(
+            Condition::any()
+                .add(alert_rules::Column::HydrometerId.is_in(hydrometer_ids.to_vec()))
+                .add(alert_rules::Column::BrewId.is_in(brew_ids)),
+        )
+        .all(db)
+        .await?;
+    evaluate_rules(db, rules).await
+}
+
+/// Evaluates every rule. Run periodically so time-based rules fire without new readings.
+pub async fn evaluate_all(db: &DatabaseConnection) -> Result<Vec<AlertResponse>, DbErr> {
+    let rules = AlertRule::find().all(db).await?;
+    evaluate_rules(db, rules).await
+}
+
+async fn firing_rule_ids(
+    db: &DatabaseConnection,
+    rule_ids: Vec<Uuid>,
+) -> Result<HashSet<Uuid>, DbErr> {
+    Ok(Alert::find()
+        .filter(alerts::Column::RuleId.is_in(rule_ids))
+        .filter(alerts::Column::State.eq("Firing"))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|a| a.rule_id)
+        .collect())
+}
+
+async fn find_owned_rule(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<Option<alert_rules::Model>, DbErr> {
+    AlertRule::find_by_id(id)
+        .filter(alert_rules::Column::CreatedBy.eq(user_sub))
+        .one(db)
+        .await
+}
+
+pub async fn list_rules(
+    db: &DatabaseConnection,
+    user_sub: &str,
+) -> Result<Vec<AlertRuleResponse>, DbErr> {
+    let rules = AlertRule::find()
+        .filter(alert_rules::Column::CreatedBy.eq(user_sub))
+        .order_by_asc(alert_rules::Column::CreatedAt)
+        .all(db)
+        .await?;
+    let firing = firing_rule_ids(db, rules.iter().map(|r| r.id).collect()).await?;
+    Ok(rules
+        .into_iter()
+        .map(|r| {
+            let is_firing = firing.contains(&r.id);
+            rule_to_response(r, is_firing)
+        })
+        .collect())
+}
+
+pub async fn find_rule(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<Option<AlertRuleResponse>, DbErr> {
+    let Some(rule) = find_owned_rule(db, id, user_sub).await? else {
+        return Ok(None);
+    };
+    let firing = open_alert_for(db, rule.id).await?.is_some();
+    Ok(Some(rule_to_response(rule, firing)))
+}
+
+/// Creates a rule owned by the caller on one of their brews or hydrometers.
+pub async fn create_rule(
+    db: &DatabaseConnection,
+    input: CreateAlertRule,
+    tenant: &Tenant,
+) -> Result<AlertRuleResponse, AlertRuleError> {
+    validate(
+        input.kind,
+        input.brew_id,
+        input.hydrometer_id,
+        input.threshold,
+        input.duration_minutes,
+    )
+    .map_err(AlertRuleError::Invalid)?;
+    if let Some(brew_id) = input.brew_id
+        && brew_service::find_visible(db, tenant, brew_id).await?.is_none()
+    {
+        return Err(AlertRuleError::Invalid("brew not found"));
+    }
+    if let Some(hydrometer_id) = input.hydrometer_id
+        && hydrometer_service::find_visible(db, tenant, hydrometer_id)
+            .await?
+            .is_none()
+    {
+        return Err(AlertRuleError::Invalid("hydrometer not found"));
+    }
+
+    let now = Utc::now();
+    let model = alert_rules::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(input.name),
+        brew_id: Set(input.brew_id),
+        hydrometer_id: Set(input.hydrometer_id),
+        kind: Set(format!("{:?}", input.kind)),
+        threshold: Set(input.threshold),
+        duration_minutes: Set(input.duration_minutes),
+        enabled: Set(input.enabled.unwrap_or(true)),
+        created_by: Set(tenant.owner.clone()),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    };
+    let rule = AlertRule::insert(model).exec_with_returning(db).await?;
+    Ok(rule_to_response(rule, false))
+}
+
+pub async fn update_rule(
+    db: &DatabaseConnection,
+    id: Uuid,
+    input: UpdateAlertRule,
+    user_sub: &str,
+) -> Result<Option<AlertRuleResponse>, AlertRuleError> {
+    let Some(rule) = find_owned_rule(db, id, user_sub).await? else {
+        return Ok(None);
+    };
+
+    let threshold = input.threshold.or(rule.threshold);
+    let duration_minutes = input.duration_minutes.or(rule.duration_minutes);
+    validate(
+        rule_kind(&rule),
+        rule.brew_id,
+        rule.hydrometer_id,
+        threshold,
+        duration_minutes,
+    )
+    .map_err(AlertRuleError::Invalid)?;
+
+    let mut active: alert_rules::ActiveModel = rule.into();
+    if let Some(name) = input.name {
+        active.name = Set(name);
+    }
+    active.threshold = Set(threshold);
+    active.duration_minutes = Set(duration_minutes);
+    if let Some(enabled) = input.enabled {
+        active.enabled = Set(enabled);
+    }
+    active.updated_at = Set(Utc::now().into());
+    let rule = active.update(db).await?;
+
+    // Re-check straight away so a disabled or loosened rule doesn't leave a stale alert open.
+    evaluate_rule(db, &rule, Utc::now()).await?;
+    let firing = open_alert_for(db, rule.id).await?.is_some();
+    Ok(Some(rule_to_response(rule, firing)))
+}
+
+pub async fn delete_rule(db: &DatabaseConnection, id: Uuid, user_sub: &str) -> Result<bool, DbErr> {
+    let result = AlertRule::delete_many()
+        .filter(alert_rules::Column::Id.eq(id))
+        .filter(alert_rules::Column::CreatedBy.eq(user_sub))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Alert history for the caller's rules, newest first.
+pub async fn list_alerts(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    state: Option<AlertState>,
+    rule_id: Option<Uuid>,
+    limit: Option<u64>,
+) -> Result<Vec<AlertResponse>, DbErr> {
+    let rules: HashMap<Uuid, alert_rules::Model> = AlertRule::find()
+        .filter(alert_rules::Column::CreatedBy.eq(user_sub))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| (r.id, r))
+        .collect();
+
+    let mut query = Alert::find().filter(alerts::Column::RuleId.is_in(rules.keys().copied()));
+    if let Some(state) = state {
+        query = query.filter(alerts::Column::State.eq(format!("{:?}", state)));
+    }
+    if let Some(rule_id) = rule_id {
+        query = query.filter(alerts::Column::RuleId.eq(rule_id));
+    }
+    let models = query
+        .order_by_desc(alerts::Column::FiredAt)
+        .limit(limit.unwrap_or(DEFAULT_ALERT_LIMIT))
+        .all(db)
+        .await?;
+
+    Ok(models
+        .into_iter()
+        .filter_map(|alert| {
+            let rule = rules.get(&alert.rule_id)?;
+            Some(alert_to_response(alert, rule))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::hours(hours)
+    }
+
+    fn sample(hours: i64, gravity: f64, temperature_f: f64) -> Sample {
+        Sample {
+            recorded_at: at(hours),
+            gravity,
+            temperature_f,
+        }
+    }
+
+    fn latest(hours: i64, gravity: f64, temperature_f: f64) -> Observation {
+        Observation {
+            latest: Some(sample(hours, gravity, temperature_f)),
+            ..Default::default()
+        }
+    }
+
+    fn stalled_window(gravities: &[f64]) -> Observation {
+        let window: Vec<Sample> = gravities
+            .iter()
+            .enumerate()
+            .map(|(h, g)| sample(h as i64, *g, 66.0))
+            .collect();
+        Observation {
+            latest: window.last().copied(),
+            window,
+            history_before_window: true,
+            target_fg: Some(1.010),
+        }
+    }
+
+    #[test]
+    fn validate_requires_single_scope() {
+        let id = Some(Uuid::new_v4());
+        let kind = AlertRuleKind::GravityReachedTarget;
+        assert!(validate(kind, None, None, None, None).is_err());
+        assert!(validate(kind, id, id, None, None).is_err());
+        assert!(validate(kind, id, None, None, None).is_ok());
+    }
+
+    #[test]
+    fn validate_requires_kind_fields() {
+        let id = Some(Uuid::new_v4());
+        assert!(validate(AlertRuleKind::TemperatureAbove, None, id, None, None).is_err());
+        assert!(validate(AlertRuleKind::TemperatureAbove, None, id, Some(72.0), None).is_ok());
+        assert!(validate(AlertRuleKind::NoReading, None, id, None, None).is_err());
+        assert!(validate(AlertRuleKind::NoReading, None, id, None, Some(0)).is_err());
+        assert!(validate(AlertRuleKind::GravityStalled, id, None, None, Some(2880)).is_ok());
+    }
+
+    #[test]
+    fn temperature_thresholds() {
+        let observation = latest(0, 1.050, 74.0);
+        let breach = check(
+            AlertRuleKind::TemperatureAbove,
+            Some(72.0),
+            None,
+            &observation,
+            at(0),
+        )
+        .unwrap();
+        assert_eq!(breach.value, Some(74.0));
+        assert!(
+            check(
+                AlertRuleKind::TemperatureBelow,
+                Some(72.0),
+                None,
+                &observation,
+                at(0)
+            )
+            .is_none()
+        );
+        assert!(
+            check(
+                AlertRuleKind::TemperatureBelow,
+                Some(75.0),
+                None,
+                &observation,
+                at(0)
+            )
+            .is_some()
+        );
+    }
+
+    #[test]
+    fn no_reading_fires_after_duration() {
+        let kind = AlertRuleKind::NoReading;
+        let duration = Some(Duration::minutes(30));
+        let observation = latest(0, 1.050, 66.0);
+        assert!(check(kind, None, duration, &observation, at(0)).is_none());
+        let breach = check(kind, None, duration, &observation, at(1)).unwrap();
+        assert_eq!(breach.value, Some(60.0));
+        assert!(check(kind, None, duration, &Observation::default(), at(0)).is_some());
+    }
+
+    #[test]
+    fn gravity_reached_target_uses_brew_target_by_default() {
+        let kind = AlertRuleKind::GravityReachedTarget;
+        let mut observation = latest(0, 1.011, 66.0);
+        assert!(check(kind, None, None, &observation, at(0)).is_none());
+        observation.target_fg = Some(1.012);
+        assert!(check(kind, None, None, &observation, at(0)).is_some());
+        assert!(check(kind, Some(1.010), None, &observation, at(0)).is_none());
+    }
+
+    #[test]
+    fn gravity_stalled_above_target() {
+        let kind = AlertRuleKind::GravityStalled;
+        let duration = Some(Duration::hours(4));
+        let stalled = stalled_window(&[1.030, 1.0302, 1.0298, 1.0301]);
+        let breach = check(kind, None, duration, &stalled, at(4)).unwrap();
+        assert!(breach.value.unwrap() < 1.0);
+
+        let moving = stalled_window(&[1.030, 1.028, 1.026, 1.024]);
+        assert!(check(kind, None, duration, &moving, at(4)).is_none());
+        assert!(check(kind, Some(10.0), duration, &moving, at(4)).is_some());
+    }
+
+    #[test]
+    fn gravity_stalled_needs_full_window_above_target() {
+        let kind = AlertRuleKind::GravityStalled;
+        let duration = Some(Duration::hours(4));
+
+        let mut young = stalled_window(&[1.030, 1.030, 1.030]);
+        young.history_before_window = false;
+        assert!(check(kind, None, duration, &young, at(4)).is_none());
+
+        let finished = stalled_window(&[1.009, 1.009, 1.009]);
+        assert!(check(kind, None, duration, &finished, at(4)).is_none());
+    }
+}
@@ -0,0 +1,353 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::brews;
+use crate::models::entities::readings::{self, Entity as Reading};
+use crate::services::brew_service;
+use crate::tenant::Tenant;
+use shared::{BrewAnalytics, GravityVelocity};
+
+/// Rolling windows (hours, ending at the latest reading) for gravity velocity.
+const VELOCITY_WINDOWS_HOURS: [u32; 3] = [6, 24, 72];
+/// Consecutive readings that must agree before the start of a fermentation counts as stable.
+const OG_STABLE_WINDOW: usize = 5;
+/// Maximum spread (SG) allowed within the OG detection window.
+const OG_STABLE_TOLERANCE: f64 = 0.002;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub recorded_at: DateTime<Utc>,
+    pub gravity: f64,
+    pub temperature_f: f64,
+}
+
+impl From<&readings::Model> for Sample {
+    fn from(model: &readings::Model) -> Self {
+        Self {
+            recorded_at: model.recorded_at.into(),
+            gravity: model.gravity,
+            temperature_f: model.temperature_f,
+        }
+    }
+}
+
+/// Degrees Plato from specific gravity (ASBC cubic approximation).
+pub fn plato(sg: f64) -> f64 {
+    -616.868 + 1111.14 * sg - 630.272 * sg.powi(2) + 135.997 * sg.powi(3)
+}
+
+pub fn abv(og: f64, sg: f64) -> f64 {
+    (og - sg) * 131.25
+}
+
+pub fn apparent_attenuation(og: f64, sg: f64) -> Option<f64> {
+    if og <= 1.0 {
+        return None;
+    }
+    Some((og - sg) / (og - 1.0) * 100.0)
+}
+
+/// Real attenuation from Balling's real-extract approximation: RE = 0.1808·OE + 0.8192·AE.
+pub fn real_attenuation(og: f64, sg: f64) -> Option<f64> {
+    let original_extract = plato(og);
+    if original_extract <= 0.0 {
+        return None;
+    }
+    let real_extract = 0.1808 * original_extract + 0.8192 * plato(sg);
+    Some((original_extract - real_extract) / original_extract * 100.0)
+}
+
+/// Mean of the first run of `OG_STABLE_WINDOW` consecutive readings whose spread is within
+/// `OG_STABLE_TOLERANCE`. Skips the noisy period right after the Tilt is dropped in.
+/// `samples` must be sorted oldest first.
+pub fn detect_og(samples: &[Sample]) -> Option<f64> {
+    stable_run(samples, OG_STABLE_WINDOW, OG_STABLE_TOLERANCE)
+        .map(|run| run.iter().map(|s| s.gravity).sum::<f64>() / run.len() as f64)
+}
+
+fn stable_run(samples: &[Sample], len: usize, tolerance: f64) -> Option<&[Sample]> {
+    samples
+        .windows(len)
+        .find(|window| gravity_spread(window) <= tolerance)
+}
+
+fn gravity_spread(samples: &[Sample]) -> f64 {
+    let (min, max) = samples
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), s| {
+            (min.min(s.gravity), max.max(s.gravity))
+        });
+    max - min
+}
+
+/// Least-squares slope of gravity over time within `window` ending at the last sample,
+/// expressed as points dropped per day. `samples` must be sorted oldest first.
+pub fn points_per_day(samples: &[Sample], window: Duration) -> Option<f64> {
+    let last = samples.last()?;
+    let start = last.recorded_at - window;
+    let in_window: Vec<&Sample> = samples.iter().filter(|s| s.recorded_at >= start).collect();
+    if in_window.len() < 2 {
+        return None;
+    }
+
+    let xs: Vec<f64> = in_window
+        .iter()
+        .map(|s| (s.recorded_at - start).num_seconds() as f64 / 86_400.0)
+        .collect();
+    let ys: Vec<f64> = in_window.iter().map(|s| s.gravity * 1000.0).collect();
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (cov, var) = xs.iter().zip(&ys).fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x).powi(2),
+        )
+    });
+    if var == 0.0 {
+        return None;
+    }
+    Some(-cov / var)
+}
+
+pub fn compute(brew: &brews::Model, samples: &[Sample], now: DateTime<Utc>) -> BrewAnalytics {
+    let (og, og_detected) = match brew.og {
+        Some(og) => (Some(og), false),
+        None => match detect_og(samples) {
+            Some(og) => (Some(og), true),
+            None => (None, false),
+        },
+    };
+    let latest = samples.last();
+    let current_gravity = latest.map(|s| s.gravity);
+
+    let (apparent, real, live_abv) = match (og, current_gravity) {
+        (Some(og), Some(sg)) => (
+            apparent_attenuation(og, sg),
+            real_attenuation(og, sg),
+            Some(abv(og, sg)),
+        ),
+        _ => (None, None, None),
+    };
+
+    let pitched_at = brew
+        .start_date
+        .map(Into::into)
+        .or_else(|| samples.first().map(|s| s.recorded_at));
+
+    BrewAnalytics {
+        brew_id: brew.id,
+        og,
+        og_detected,
+        current_gravity,
+        current_temperature_f: latest.map(|s| s.temperature_f),
+        apparent_attenuation: apparent,
+        real_attenuation: real,
+        abv: live_abv,
+        gravity_velocity: VELOCITY_WINDOWS_HOURS
+            .iter()
+            .map(|hours| GravityVelocity {
+                window_hours: *hours,
+                points_per_day: points_per_day(samples, Duration::hours(*hours as i64)),
+            })
+            .collect(),
+        hours_since_pitch: pitched_at
+            .map(|at: DateTime<Utc>| (now - at).num_seconds() as f64 / 3600.0),
+        reading_count: samples.len() as u64,
+        latest_reading_at: latest.map(|s| s.recorded_at),
+    }
+}
+
+pub async fn samples_for_brew(
+    db: &DatabaseConnection,
+    brew_id: Uuid,
+) -> Result<Vec<Sample>, DbErr> {
+    let models = Reading::find()
+        .filter(readings::Column::BrewId.eq(brew_id))
+        .order_by_asc(readings::Column::RecordedAt)
+        .all(db)
+        .await?;
+    Ok(models.iter().map(Sample::from).collect())
+}
+
+/// The brew's readings over `period` up to its latest one, plus the reading just before, oldest
+/// first. Enough to tell whether gravity has held for `period` without loading the whole brew.
+pub async fn recent_samples(
+    db: &DatabaseConnection,
+    brew_id: Uuid,
+    period: Duration,
+) -> Result<Vec<Sample>, DbErr> {
+    let by_brew = || Reading::find().filter(readings::Column::BrewId.eq(brew_id));
+    let Some(latest) = by_brew()
+        .order_by_desc(readings::Column::RecordedAt)
+        .one(db)
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+    let since = latest.recorded_at - period;
+    let earlier = by_brew()
+        .filter(readings::Column::RecordedAt.lt(since))
+        .order_by_desc(readings::Column::RecordedAt)
+        .one(db)
+        .await?;
+    let window = by_brew()
+        .filter(readings::Column::RecordedAt.gte(since))
+        .order_by_asc(readings::Column::RecordedAt)
+        .all(db)
+        .await?;
+    Ok(earlier.iter().chain(&window).map(Sample::from).collect())
+}
+
+pub async fn for_brew(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    brew_id: Uuid,
+) -> Result<Option<BrewAnalytics>, DbErr> {
+    let Some(brew) = brew_service::find_visible(db, tenant, brew_id).await? else {
+        return Ok(None);
+    };
+    let samples = samples_for_brew(db, brew_id).await?;
+    Ok(Some(compute(&brew, &samples, Utc::now())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(hours: i64, gravity: f64) -> Sample {
+        let base = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Sample {
+            recorded_at: base + Duration::hours(hours),
+            gravity,
+            temperature_f: 66.0,
+        }
+    }
+
+    fn brew(og: Option<f64>) -> brews::Model {
+        let now = Utc::now().fixed_offset();
+        brews::Model {
+            id: Uuid::new_v4(),
+            name: "Test".to_string(),
+            style: None,
+            og,
+            fg: None,
+            target_fg: None,
+            abv: None,
+            status: "Active".to_string(),
+            start_date: Some(sample(0, 1.0).recorded_at.fixed_offset()),
+            end_date: None,
+            notes: None,
+            hydrometer_id: Uuid::new_v4(),
+            created_at: now,
+            updated_at: now,
+            stable_since: None,
+            owner: "user-1".to_string(),
+            team_id: None,
+        }
+    }
+
+    #[test]
+    fn plato_matches_reference_values() {
+        assert!(plato(1.000).abs() < 0.05);
+        assert!((plato(1.040) - 10.0).abs() < 0.1);
+        assert!((plato(1.050) - 12.4).abs() < 0.1);
+    }
+
+    #[test]
+    fn abv_and_attenuation_for_typical_ale() {
+        assert!((abv(1.050, 1.010) - 5.25).abs() < 1e-9);
+        assert!((apparent_attenuation(1.050, 1.010).unwrap() - 80.0).abs() < 1e-9);
+        let real = real_attenuation(1.050, 1.010).unwrap();
+        assert!(real > 60.0 && real < 70.0, "real attenuation {real}");
+    }
+
+    #[test]
+    fn apparent_attenuation_needs_og_above_water() {
+        assert!(apparent_attenuation(1.000, 0.998).is_none());
+    }
+
+    #[test]
+    fn detect_og_skips_noisy_start() {
+        let samples = vec![
+            sample(0, 1.060),
+            sample(1, 1.048),
+            sample(2, 1.055),
+            sample(3, 1.052),
+            sample(4, 1.052),
+            sample(5, 1.053),
+            sample(6, 1.052),
+            sample(7, 1.052),
+            sample(30, 1.040),
+        ];
+        let og = detect_og(&samples).unwrap();
+        assert!((og - 1.052).abs() < 0.0005, "detected og {og}");
+    }
+
+    #[test]
+    fn detect_og_needs_enough_readings() {
+        let samples = vec![sample(0, 1.050), sample(1, 1.050)];
+        assert!(detect_og(&samples).is_none());
+    }
+
+    #[test]
+    fn points_per_day_linear_drop() {
+        let samples: Vec<Sample> = (0..=24)
+            .map(|h| sample(h, 1.050 - 0.010 * h as f64 / 24.0))
+            .collect();
+        let velocity = points_per_day(&samples, Duration::hours(24)).unwrap();
+        assert!((velocity - 10.0).abs() < 1e-6, "velocity {velocity}");
+    }
+
+    #[test]
+    fn points_per_day_only_uses_window() {
+        let mut samples = vec![sample(0, 1.060)];
+        samples.extend((40..=48).map(|h| sample(h, 1.020)));
+        let velocity = points_per_day(&samples, Duration::hours(24)).unwrap();
+        assert!(velocity.abs() < 1e-9);
+    }
+
+    #[test]
+    fn points_per_day_single_reading_is_none() {
+        assert!(points_per_day(&[sample(0, 1.050)], Duration::hours(24)).is_none());
+    }
+
+    #[test]
+    fn compute_prefers_recorded_og() {
+        let samples = vec![sample(0, 1.040), sample(24, 1.030)];
+        let analytics = compute(&brew(Some(1.050)), &samples, sample(48, 1.0).recorded_at);
+        assert_eq!(analytics.og, Some(1.050));
+        assert!(!analytics.og_detected);
+        assert!((analytics.abv.unwrap() - 2.625).abs() < 1e-9);
+        assert!((analytics.hours_since_pitch.unwrap() - 48.0).abs() < 1e-9);
+        assert_eq!(analytics.reading_count, 2);
+        assert_eq!(
+            analytics.gravity_velocity.len(),
+            VELOCITY_WINDOWS_HOURS.len()
+        );
+    }
+
+    #[test]
+    fn compute_detects_og_when_unset() {
+        let samples: Vec<Sample> = (0..6).map(|h| sample(h, 1.050)).collect();
+        let analytics = compute(&brew(None), &samples, sample(6, 1.0).recorded_at);
+        assert!(analytics.og_detected);
+        assert!((analytics.og.unwrap() - 1.050).abs() < 1e-9);
+    }
+
+    #[test]
+    fn compute_without_readings() {
+        let analytics = compute(&brew(Some(1.050)), &[], Utc::now());
+        assert!(analytics.current_gravity.is_none());
+        assert!(analytics.abv.is_none());
+        assert!(
+            analytics
+                .gravity_velocity
+                .iter()
+                .all(|v| v.points_per_day.is_none())
+        );
+    }
+}
@@ -1,17 +1,33 @@
-use chrono::Utc;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use chrono::{Duration, Utc};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
-    QueryFilter,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, sea_query::Expr,
 };
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::models::entities::api_keys::{self, ActiveModel, Entity as ApiKey};
+use crate::services::audit_service::{self, Target};
+use crate::services::team_service;
+use crate::tenant::{Membership, Tenant};
+use shared::{TeamRole, TiltColor};
+
+/// How long a rotated-out secret keeps working unless the caller picks another period.
+const DEFAULT_GRACE_HOURS: u32 = 24;
+/// Longest grace period a rotation may ask for.
+const MAX_GRACE_HOURS: u32 = 30 * 24;
+/// Longest user agent stored from a request.
+const MAX_USER_AGENT_LEN: usize = 255;
 
 #[derive(Debug)]
 pub enum ApiKeyError {
     Expired,
     Invalid,
+    /// The key is valid but lacks the scope, or is used from an address it isn't allowed from.
+    Forbidden,
     #[allow(dead_code)]
     Db(DbErr),
 }
@@ -22,6 +38,148 @@ impl From<DbErr> for ApiKeyError {
     }
 }
 
+/// What a key may be used for. `admin` grants every other scope and also lets the key manage
+/// brews, calibrations and its creator's API keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ApiKeyScope {
+    #[serde(rename = "readings:write")]
+    ReadingsWrite,
+    #[serde(rename = "readings:read")]
+    ReadingsRead,
+    #[serde(rename = "brews:read")]
+    BrewsRead,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub fn grants(self, required: ApiKeyScope) -> bool {
+        self == ApiKeyScope::Admin || self == required
+    }
+}
+
+/// An allowed source address: a single IP or a CIDR block such as `192.168.1.0/24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRule {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for IpRule {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let network: IpAddr = addr.parse().map_err(|_| ())?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse::<u8>().map_err(|_| ())?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(());
+        }
+        Ok(IpRule { network, prefix })
+    }
+}
+
+impl IpRule {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // Proxies may report IPv4 clients as IPv4-mapped IPv6 addresses.
+        let bits = |ip: IpAddr| match ip.to_canonical() {
+            IpAddr::V4(v4) => (true, u128::from(v4.to_bits()) << 96),
+            IpAddr::V6(v6) => (false, v6.to_bits()),
+        };
+        let (network_v4, network) = bits(self.network);
+        let (ip_v4, ip) = bits(ip);
+        let mask = u128::MAX
+            .checked_shl(128 - u32::from(self.prefix))
+            .unwrap_or(0);
+        network_v4 == ip_v4 && network & mask == ip & mask
+    }
+}
+
+/// Limits chosen when a key is created. Colors and addresses are unrestricted when `None`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyRestrictions {
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<ApiKeyScope>,
+    #[serde(default)]
+    pub allowed_colors: Option<Vec<TiltColor>>,
+    #[serde(default)]
+    pub allowed_ips: Option<Vec<String>>,
+}
+
+/// Keys are made for the Pi client unless asked otherwise.
+fn default_scopes() -> Vec<ApiKeyScope> {
+    vec![ApiKeyScope::ReadingsWrite]
+}
+
+impl Default for ApiKeyRestrictions {
+    fn default() -> Self {
+        Self {
+            scopes: default_scopes(),
+            allowed_colors: None,
+            allowed_ips: None,
+        }
+    }
+}
+
+impl ApiKeyRestrictions {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.scopes.is_empty() {
+            return Err("at least one scope is required");
+        }
+        if self.allowed_colors.as_ref().is_some_and(Vec::is_empty) {
+            return Err("allowedColors must not be empty; omit it to allow every color");
+        }
+        match &self.allowed_ips {
+            Some(ips) if ips.is_empty() => {
+                Err("allowedIps must not be empty; omit it to allow every address")
+            }
+            Some(ips) if ips.iter().any(|ip| ip.parse::<IpRule>().is_err()) => {
+                Err("allowedIps must be IP addresses or CIDR blocks")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The restrictions stored on `key`. Unreadable entries grant nothing.
+    pub fn of(key: &api_keys::Model) -> Self {
+        fn decode<T: serde::de::DeserializeOwned>(value: &serde_json::Value) -> Vec<T> {
+            serde_json::from_value::<Vec<serde_json::Value>>(value.clone())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|v| serde_json::from_value(v).ok())
+                .collect()
+        }
+        Self {
+            scopes: decode(&key.scopes),
+            allowed_colors: key.allowed_colors.as_ref().map(decode),
+            allowed_ips: key.allowed_ips.as_ref().map(decode),
+        }
+    }
+
+    /// Whether a request from `ip` may use the key for `scope`.
+    pub fn permits(&self, scope: ApiKeyScope, ip: Option<IpAddr>) -> bool {
+        let scoped = self.scopes.iter().any(|s| s.grants(scope));
+        let from_allowed_ip = match &self.allowed_ips {
+            None => true,
+            Some(rules) => ip.is_some_and(|ip| {
+                rules
+                    .iter()
+                    .filter_map(|r| r.parse::<IpRule>().ok())
+                    .any(|rule| rule.contains(ip))
+            }),
+        };
+        scoped && from_allowed_ip
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiKeyCreated {
@@ -29,7 +187,12 @@ pub struct ApiKeyCreated {
     pub name: String,
     pub prefix: String,
     pub key: String,
+    #[serde(flatten)]
+    pub restrictions: ApiKeyRestrictions,
     pub created_at: chrono::DateTime<chrono::FixedOffset>,
+    /// After a rotation, when the replaced secret stops working.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
     pub message: &'static str,
 }
 
@@ -40,11 +203,29 @@ pub struct ApiKeySummary {
     pub name: String,
     pub prefix: String,
     pub created_by: String,
+    pub team_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub restrictions: ApiKeyRestrictions,
     pub last_used_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub request_count: i64,
+    pub last_ip: Option<String>,
+    pub last_user_agent: Option<String>,
+    pub rotated_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Set while the secret replaced by the last rotation still works.
+    pub previous_expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
     pub expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
     pub created_at: chrono::DateTime<chrono::FixedOffset>,
 }
 
+fn audit_target(key: &api_keys::Model) -> Target<'_> {
+    Target {
+        kind: "api_key",
+        id: key.id,
+        owner: &key.created_by,
+        team_id: key.team_id,
+    }
+}
+
 pub fn generate_api_key() -> (String, String, String) {
     let raw_bytes: [u8; 32] = rand::random();
     let raw = hex::encode(raw_bytes);
@@ -53,55 +234,13 @@ pub fn generate_api_key() -> (String, String, String) {
     (raw, hash, prefix)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn generate_api_key_length() {
-        let (raw, hash, prefix) = generate_api_key();
-        assert_eq!(raw.len(), 64, "raw key should be 64 hex chars (32 bytes)");
-        assert_eq!(hash.len(), 64, "sha256 hash should be 64 hex chars");
-        assert_eq!(prefix.len(), 8, "prefix should be first 8 chars of raw key");
-        assert!(raw.starts_with(&prefix));
-    }
-
-    #[test]
-    fn generate_api_key_uniqueness() {
-        let (raw1, hash1, _) = generate_api_key();
-        let (raw2, hash2, _) = generate_api_key();
-        assert_ne!(raw1, raw2, "two generated keys should differ");
-        assert_ne!(hash1, hash2, "two generated hashes should differ");
-    }
-
-    #[test]
-    fn generate_api_key_hash_is_deterministic() {
-        let raw = "deadbeef".repeat(8);
-        let hash1 = format!("{:x}", Sha256::digest(raw.as_bytes()));
-        let hash2 = format!("{:x}", Sha256::digest(raw.as_bytes()));
-        assert_eq!(hash1, hash2, "same input should produce same hash");
-    }
-
-    #[test]
-    fn generate_api_key_hash_differs_from_raw() {
-        let (raw, hash, _) = generate_api_key();
-        assert_ne!(raw, hash, "hash should differ from raw key");
-    }
-
-    #[test]
-    fn generate_api_key_is_hex() {
-        let (raw, hash, prefix) = generate_api_key();
-        assert!(raw.chars().all(|c| c.is_ascii_hexdigit()));
-        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
-        assert!(prefix.chars().all(|c| c.is_ascii_hexdigit()));
-    }
-}
-
+/// Creates a key for the caller, in the team they are acting in.
 pub async fn create_api_key(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     name: String,
-    created_by: String,
     expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    restrictions: ApiKeyRestrictions,
 ) -> Result<ApiKeyCreated, DbErr> {
     let (raw, hash, prefix) = generate_api_key();
     let now = Utc::now().fixed_offset();
@@ -111,51 +250,156 @@ pub async fn create_api_key(
         name: Set(name.clone()),
         key_hash: Set(hash),
         prefix: Set(prefix.clone()),
-        created_by: Set(created_by),
+        created_by: Set(tenant.owner.clone()),
         last_used_at: Set(None),
         expires_at: Set(expires_at),
         created_at: Set(now),
+        team_id: Set(tenant.team_id),
+        scopes: Set(serde_json::json!(restrictions.scopes)),
+        allowed_colors: Set(restrictions
+            .allowed_colors
+            .as_ref()
+            .map(|c| serde_json::json!(c))),
+        allowed_ips: Set(restrictions
+            .allowed_ips
+            .as_ref()
+            .map(|ips| serde_json::json!(ips.iter().map(|ip| ip.trim()).collect::<Vec<_>>()))),
+        previous_key_hash: Set(None),
+        previous_expires_at: Set(None),
+        rotated_at: Set(None),
+        request_count: Set(0),
+        last_ip: Set(None),
+        last_user_agent: Set(None),
     };
 
     let inserted = model.insert(db).await?;
+    let after = audit_service::snapshot(&inserted);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "create",
+        audit_target(&inserted),
+        None,
+        Some(after),
+    )
+    .await;
 
     Ok(ApiKeyCreated {
         id: inserted.id,
         name: inserted.name,
         prefix,
         key: raw,
+        restrictions,
         created_at: inserted.created_at,
+        previous_expires_at: None,
         message: "Store this key securely — it will not be shown again.",
     })
 }
 
+/// Whether the caller may rotate or delete the key: their own keys, and any key of a team
+/// they own so a leaked one can be revoked.
+fn may_manage(key: &api_keys::Model, tenant: &Tenant) -> bool {
+    key.created_by == tenant.owner
+        || key
+            .team_id
+            .is_some_and(|team_id| tenant.owned_team_ids.contains(&team_id))
+}
+
+/// The caller's own keys and those of teams they own.
 pub async fn list_api_keys(
     db: &DatabaseConnection,
-    user_sub: &str,
+    tenant: &Tenant,
 ) -> Result<Vec<ApiKeySummary>, DbErr> {
-    let keys = ApiKey::find()
-        .filter(api_keys::Column::CreatedBy.eq(user_sub))
-        .all(db)
-        .await?;
+    let mut visible = Condition::any().add(api_keys::Column::CreatedBy.eq(tenant.owner.as_str()));
+    if !tenant.owned_team_ids.is_empty() {
+        visible = visible.add(api_keys::Column::TeamId.is_in(tenant.owned_team_ids.iter().copied()));
+    }
+    let keys = ApiKey::find().filter(visible).all(db).await?;
+    let now = Utc::now().fixed_offset();
 
     Ok(keys
         .into_iter()
         .map(|k| ApiKeySummary {
+            restrictions: ApiKeyRestrictions::of(&k),
             id: k.id,
             name: k.name,
             prefix: k.prefix,
             created_by: k.created_by,
+            team_id: k.team_id,
             last_used_at: k.last_used_at,
+            request_count: k.request_count,
+            last_ip: k.last_ip,
+            last_user_agent: k.last_user_agent,
+            rotated_at: k.rotated_at,
+            previous_expires_at: k.previous_expires_at.filter(|at| *at > now),
             expires_at: k.expires_at,
             created_at: k.created_at,
         })
         .collect())
 }
 
+/// The grace period for a rotation: `hours`, or a day by default, up to 30 days.
+pub fn grace_period(hours: Option<u32>) -> Result<Duration, &'static str> {
+    match hours.unwrap_or(DEFAULT_GRACE_HOURS) {
+        h if h > MAX_GRACE_HOURS => Err("grace_hours must be at most 720"),
+        h => Ok(Duration::hours(i64::from(h))),
+    }
+}
+
+/// Issues a new secret for the key. The old one keeps working for `grace` so clients can be
+/// updated; it replaces any secret still in the grace period of an earlier rotation.
+/// `None` if the key doesn't exist.
+pub async fn rotate_api_key(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+    grace: Duration,
+) -> Result<Option<ApiKeyCreated>, ApiKeyError> {
+    let Some(key) = ApiKey::find_by_id(id).one(db).await? else {
+        return Ok(None);
+    };
+    if !may_manage(&key, tenant) {
+        return Err(ApiKeyError::Invalid);
+    }
+
+    let before = audit_service::snapshot(&key);
+    let (raw, hash, prefix) = generate_api_key();
+    let now = Utc::now().fixed_offset();
+    let previous_expires_at = (grace > Duration::zero()).then(|| now + grace);
+    let mut active: api_keys::ActiveModel = key.clone().into();
+    active.previous_key_hash = Set(previous_expires_at.map(|_| key.key_hash.clone()));
+    active.previous_expires_at = Set(previous_expires_at);
+    active.key_hash = Set(hash);
+    active.prefix = Set(prefix.clone());
+    active.rotated_at = Set(Some(now));
+    let rotated = active.update(db).await?;
+    let after = audit_service::snapshot(&rotated);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "rotate",
+        audit_target(&rotated),
+        Some(before),
+        Some(after),
+    )
+    .await;
+
+    Ok(Some(ApiKeyCreated {
+        restrictions: ApiKeyRestrictions::of(&rotated),
+        id: rotated.id,
+        name: rotated.name,
+        prefix,
+        key: raw,
+        created_at: rotated.created_at,
+        previous_expires_at,
+        message: "Store this key securely — it will not be shown again.",
+    }))
+}
+
 pub async fn delete_api_key(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     id: Uuid,
-    user_sub: &str,
 ) -> Result<(), ApiKeyError> {
     let key = ApiKey::find_by_id(id)
         .one(db)
@@ -164,39 +408,344 @@ pub async fn delete_api_key(
 
     match key {
         None => Ok(()),
-        Some(k) if k.created_by != user_sub => Err(ApiKeyError::Invalid),
-        Some(_) => {
+        Some(k) if !may_manage(&k, tenant) => Err(ApiKeyError::Invalid),
+        Some(k) => {
             ApiKey::delete_by_id(id)
                 .exec(db)
                 .await
                 .map_err(ApiKeyError::Db)?;
+            let before = audit_service::snapshot(&k);
+            audit_service::record(
+                db,
+                &tenant.actor,
+                "delete",
+                audit_target(&k),
+                Some(before),
+                None,
+            )
+            .await;
             Ok(())
         }
     }
 }
 
+/// A key accepted by [`validate_api_key`].
+pub struct ValidKey {
+    pub key: api_keys::Model,
+    /// The creator's current role in the key's team; `None` for keys without a team.
+    pub team_role: Option<TeamRole>,
+}
+
+/// The creator's current role in the key's team. A key made in a team stops working once
+/// its creator is no longer a member.
+fn team_role(
+    key: &api_keys::Model,
+    memberships: &[Membership],
+) -> Result<Option<TeamRole>, ApiKeyError> {
+    let Some(team_id) = key.team_id else {
+        return Ok(None);
+    };
+    memberships
+        .iter()
+        .find(|m| m.team_id == team_id)
+        .map(|m| Some(m.role))
+        .ok_or(ApiKeyError::Invalid)
+}
+
+/// Looks up the key, current or rotated out but still in its grace period, checks it may be
+/// used for `scope` from `ip`, that a team key's creator still has at least `role` in the
+/// team, and records the use.
 pub async fn validate_api_key(
     db: &DatabaseConnection,
     raw_key: &str,
-) -> Result<api_keys::Model, ApiKeyError> {
+    scope: ApiKeyScope,
+    role: TeamRole,
+    ip: Option<IpAddr>,
+    user_agent: Option<&str>,
+) -> Result<ValidKey, ApiKeyError> {
     let hash = format!("{:x}", Sha256::digest(raw_key.as_bytes()));
+    let now = Utc::now().fixed_offset();
 
     let key = ApiKey::find()
-        .filter(api_keys::Column::KeyHash.eq(&hash))
+        .filter(
+            Condition::any()
+                .add(api_keys::Column::KeyHash.eq(&hash))
+                .add(
+                    Condition::all()
+                        .add(api_keys::Column::PreviousKeyHash.eq(&hash))
+                        .add(api_keys::Column::PreviousExpiresAt.gt(now)),
+                ),
+        )
         .one(db)
         .await
         .map_err(ApiKeyError::Db)?
         .ok_or(ApiKeyError::Invalid)?;
 
-    if let Some(expires_at) = key.expires_at {
-        if expires_at < Utc::now().fixed_offset() {
-            return Err(ApiKeyError::Expired);
+    if let Some(expires_at) = key.expires_at
+        && expires_at < now
+    {
+        return Err(ApiKeyError::Expired);
+    }
+    if !ApiKeyRestrictions::of(&key).permits(scope, ip) {
+        return Err(ApiKeyError::Forbidden);
+    }
+    let team_role = match key.team_id {
+        Some(_) => team_role(&key, &team_service::memberships_for(db, &key.created_by).await?)?,
+        None => None,
+    };
+    if team_role.is_some_and(|r| r < role) {
+        return Err(ApiKeyError::Forbidden);
+    }
+
+    // Counted in SQL so concurrent requests don't lose increments.
+    ApiKey::update_many()
+        .col_expr(
+            api_keys::Column::RequestCount,
+            Expr::col(api_keys::Column::RequestCount).add(1),
+        )
+        .col_expr(api_keys::Column::LastUsedAt, Expr::value(now))
+        .col_expr(
+            api_keys::Column::LastIp,
+            Expr::value(ip.map(|ip| ip.to_canonical().to_string())),
+        )
+        .col_expr(
+            api_keys::Column::LastUserAgent,
+            Expr::value(user_agent.map(truncate_user_agent)),
+        )
+        .filter(api_keys::Column::Id.eq(key.id))
+        .exec(db)
+        .await
+        .map_err(ApiKeyError::Db)?;
+
+    Ok(ValidKey { key, team_role })
+}
+
+fn truncate_user_agent(user_agent: &str) -> String {
+    user_agent.chars().take(MAX_USER_AGENT_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_api_key_length() {
+        let (raw, hash, prefix) = generate_api_key();
+        assert_eq!(raw.len(), 64, "raw key should be 64 hex chars (32 bytes)");
+        assert_eq!(hash.len(), 64, "sha256 hash should be 64 hex chars");
+        assert_eq!(prefix.len(), 8, "prefix should be first 8 chars of raw key");
+        assert!(raw.starts_with(&prefix));
+    }
+
+    #[test]
+    fn generate_api_key_uniqueness() {
+        let (raw1, hash1, _) = generate_api_key();
+        let (raw2, hash2, _) = generate_api_key();
+        assert_ne!(raw1, raw2, "two generated keys should differ");
+        assert_ne!(hash1, hash2, "two generated hashes should differ");
+    }
+
+    #[test]
+    fn generate_api_key_hash_is_deterministic() {
+        let raw = "deadbeef".repeat(8);
+        let hash1 = format!("{:x}", Sha256::digest(raw.as_bytes()));
+        let hash2 = format!("{:x}", Sha256::digest(raw.as_bytes()));
+        assert_eq!(hash1, hash2, "same input should produce same hash");
+    }
+
+    #[test]
+    fn generate_api_key_hash_differs_from_raw() {
+        let (raw, hash, _) = generate_api_key();
+        assert_ne!(raw, hash, "hash should differ from raw key");
+    }
+
+    fn rule(s: &str) -> IpRule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn ip_rules_match_single_addresses_and_blocks() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(rule("192.168.1.0/24").contains(ip("192.168.1.77")));
+        assert!(!rule("192.168.1.0/24").contains(ip("192.168.2.1")));
+        assert!(rule("10.0.0.5").contains(ip("10.0.0.5")));
+        assert!(!rule("10.0.0.5").contains(ip("10.0.0.6")));
+        assert!(rule("0.0.0.0/0").contains(ip("203.0.113.9")));
+        assert!(rule("2001:db8::/32").contains(ip("2001:db8:1::1")));
+        assert!(!rule("2001:db8::/32").contains(ip("10.0.0.5")));
+        assert!(rule("192.168.1.0/24").contains(ip("::ffff:192.168.1.4")));
+    }
+
+    #[test]
+    fn ip_rules_reject_bad_input() {
+        for bad in ["", "nope", "10.0.0.0/33", "2001:db8::/129", "10.0.0.0/x"] {
+            assert!(bad.parse::<IpRule>().is_err(), "{bad}");
         }
     }
 
-    let mut active: api_keys::ActiveModel = key.clone().into();
-    active.last_used_at = Set(Some(Utc::now().fixed_offset()));
-    active.update(db).await.map_err(ApiKeyError::Db)?;
+    #[test]
+    fn admin_grants_every_scope() {
+        let restrictions = ApiKeyRestrictions {
+            scopes: vec![ApiKeyScope::Admin],
+            ..ApiKeyRestrictions::default()
+        };
+        assert!(restrictions.permits(ApiKeyScope::ReadingsWrite, None));
+        assert!(restrictions.permits(ApiKeyScope::BrewsRead, None));
+        assert!(restrictions.permits(ApiKeyScope::Admin, None));
+    }
+
+    #[test]
+    fn only_admin_keys_manage() {
+        let everything_else = ApiKeyRestrictions {
+            scopes: vec![
+                ApiKeyScope::ReadingsWrite,
+                ApiKeyScope::ReadingsRead,
+                ApiKeyScope::BrewsRead,
+            ],
+            ..ApiKeyRestrictions::default()
+        };
+        assert!(!everything_else.permits(ApiKeyScope::Admin, None));
+    }
+
+    #[test]
+    fn read_only_keys_cannot_write_readings() {
+        let restrictions = ApiKeyRestrictions {
+            scopes: vec![ApiKeyScope::ReadingsRead, ApiKeyScope::BrewsRead],
+            ..ApiKeyRestrictions::default()
+        };
+        assert!(restrictions.permits(ApiKeyScope::ReadingsRead, None));
+        assert!(!restrictions.permits(ApiKeyScope::ReadingsWrite, None));
+    }
+
+    #[test]
+    fn ip_restrictions_need_a_matching_client_address() {
+        let restrictions = ApiKeyRestrictions {
+            allowed_ips: Some(vec!["192.168.1.0/24".to_string()]),
+            ..ApiKeyRestrictions::default()
+        };
+        let scope = ApiKeyScope::ReadingsWrite;
+        assert!(restrictions.permits(scope, Some("192.168.1.20".parse().unwrap())));
+        assert!(!restrictions.permits(scope, Some("8.8.8.8".parse().unwrap())));
+        assert!(!restrictions.permits(scope, None));
+    }
+
+    #[test]
+    fn validate_rejects_empty_lists_and_bad_addresses() {
+        assert!(ApiKeyRestrictions::default().validate().is_ok());
+        let with = |scopes, colors, ips| ApiKeyRestrictions {
+            scopes,
+            allowed_colors: colors,
+            allowed_ips: ips,
+        };
+        assert!(with(vec![], None, None).validate().is_err());
+        assert!(
+            with(default_scopes(), Some(vec![]), None)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            with(default_scopes(), None, Some(vec![]))
+                .validate()
+                .is_err()
+        );
+        assert!(
+            with(default_scopes(), None, Some(vec!["bogus".to_string()]))
+                .validate()
+                .is_err()
+        );
+    }
 
-    Ok(key)
+    #[test]
+    fn grace_period_defaults_to_a_day_and_is_capped() {
+        assert_eq!(grace_period(None), Ok(Duration::hours(24)));
+        assert_eq!(grace_period(Some(0)), Ok(Duration::zero()));
+        assert_eq!(grace_period(Some(720)), Ok(Duration::days(30)));
+        assert!(grace_period(Some(721)).is_err());
+    }
+
+    #[test]
+    fn long_user_agents_are_truncated() {
+        assert_eq!(truncate_user_agent("tilt-client/0.1"), "tilt-client/0.1");
+        assert_eq!(truncate_user_agent(&"é".repeat(300)).chars().count(), 255);
+    }
+
+    #[test]
+    fn generate_api_key_is_hex() {
+        let (raw, hash, prefix) = generate_api_key();
+        assert!(raw.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(prefix.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    fn key(created_by: &str, team_id: Option<Uuid>) -> api_keys::Model {
+        let now = Utc::now().fixed_offset();
+        api_keys::Model {
+            id: Uuid::new_v4(),
+            name: "pi".to_string(),
+            key_hash: String::new(),
+            prefix: String::new(),
+            created_by: created_by.to_string(),
+            last_used_at: None,
+            expires_at: None,
+            created_at: now,
+            team_id,
+            scopes: serde_json::json!(default_scopes()),
+            allowed_colors: None,
+            allowed_ips: None,
+            previous_key_hash: None,
+            previous_expires_at: None,
+            rotated_at: None,
+            request_count: 0,
+            last_ip: None,
+            last_user_agent: None,
+        }
+    }
+
+    #[test]
+    fn team_keys_follow_the_creators_current_membership() {
+        let team = Uuid::new_v4();
+        let member = |role| Membership {
+            team_id: team,
+            role,
+        };
+        assert_eq!(team_role(&key("alice", None), &[]).unwrap(), None);
+        assert_eq!(
+            team_role(&key("alice", Some(team)), &[member(TeamRole::Brewer)]).unwrap(),
+            Some(TeamRole::Brewer)
+        );
+        assert!(matches!(
+            team_role(&key("alice", Some(team)), &[]),
+            Err(ApiKeyError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn keys_of_demoted_creators_are_read_only() {
+        let team = Uuid::new_v4();
+        let key = key("alice", Some(team));
+        let viewer = Tenant::api_key(&key, Some(TeamRole::Viewer));
+        assert_eq!(viewer.team_ids, vec![team]);
+        assert!(viewer.editable_team_ids.is_empty());
+        let brewer = Tenant::api_key(&key, Some(TeamRole::Brewer));
+        assert_eq!(brewer.editable_team_ids, vec![team]);
+    }
+
+    #[test]
+    fn team_owners_manage_their_teams_keys() {
+        let team = Uuid::new_v4();
+        let owner = |role| {
+            Tenant::member(
+                "bob",
+                &[Membership {
+                    team_id: team,
+                    role,
+                }],
+                None,
+            )
+        };
+        assert!(may_manage(&key("bob", None), &owner(TeamRole::Viewer)));
+        assert!(may_manage(&key("alice", Some(team)), &owner(TeamRole::Owner)));
+        assert!(!may_manage(&key("alice", Some(team)), &owner(TeamRole::Brewer)));
+        assert!(!may_manage(&key("alice", None), &owner(TeamRole::Owner)));
+    }
 }
@@ -0,0 +1,254 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::*;
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+use crate::models::entities::audit_events::{self, Column, Entity as AuditEvent};
+use crate::pagination::{Cursor, PageRequest};
+use crate::tenant::Tenant;
+use shared::Page;
+
+/// Fields whose values never appear in the audit log, only the fact that they changed.
+const REDACTED_FIELDS: [&str; 3] = ["key_hash", "previous_key_hash", "id_token_hash"];
+
+/// Who performed an audited action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor {
+    /// Subject of the signed-in user, or of the user who created the API key used.
+    pub user_sub: String,
+    pub api_key_id: Option<Uuid>,
+    pub ip: Option<IpAddr>,
+}
+
+impl Actor {
+    pub fn user(user_sub: &str) -> Self {
+        Self {
+            user_sub: user_sub.to_string(),
+            api_key_id: None,
+            ip: None,
+        }
+    }
+}
+
+/// What an audited action changed, and who may read about it: the row's owner and team.
+pub struct Target<'a> {
+    pub kind: &'static str,
+    pub id: Uuid,
+    pub owner: &'a str,
+    pub team_id: Option<Uuid>,
+}
+
+/// How long audit events are kept, read from the environment.
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// `None` keeps events forever.
+    pub retention: Option<Duration>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            retention: Some(Duration::days(365)),
+        }
+    }
+}
+
+impl AuditConfig {
+    pub fn from_env() -> Self {
+        match std::env::var("AUDIT_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.trim().parse::<i64>().ok())
+        {
+            Some(days) if days > 0 => Self {
+                retention: Some(Duration::days(days)),
+            },
+            Some(_) => Self { retention: None },
+            None => Self::default(),
+        }
+    }
+}
+
+/// Filters for [`list`]; all are optional.
+#[derive(Debug, Default)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Serializes a row for [`record`].
+pub fn snapshot(model: &impl serde::Serialize) -> Value {
+    serde_json::to_value(model).unwrap_or(Value::Null)
+}
+
+/// The fields that differ between two snapshots, each as `{"before": .., "after": ..}`.
+/// A missing snapshot (before a create, after a delete) counts as every field being null.
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let fields = |v: Option<&Value>| v.and_then(Value::as_object).unwrap_or(&empty).clone();
+    let (before, after) = (fields(before), fields(after));
+
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut changes = Map::new();
+    for key in keys {
+        let old = before.get(key).unwrap_or(&Value::Null);
+        let new = after.get(key).unwrap_or(&Value::Null);
+        if old == new {
+            continue;
+        }
+        let change = if REDACTED_FIELDS.contains(&key.as_str()) {
+            json!({ "before": "[redacted]", "after": "[redacted]" })
+        } else {
+            json!({ "before": old, "after": new })
+        };
+        changes.insert(key.clone(), change);
+    }
+    Value::Object(changes)
+}
+
+/// Writes an audit event. Failures are logged rather than returned: the change itself has
+/// already been made and the caller shouldn't report it as failed.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    actor: &Actor,
+    action: &str,
+    target: Target<'_>,
+    before: Option<Value>,
+    after: Option<Value>,
+) {
+    let event = audit_events::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        occurred_at: Set(Utc::now().fixed_offset()),
+        actor: Set(actor.user_sub.clone()),
+        api_key_id: Set(actor.api_key_id),
+        action: Set(action.to_string()),
+        target_type: Set(target.kind.to_string()),
+        target_id: Set(target.id),
+        changes: Set(diff(before.as_ref(), after.as_ref())),
+        ip: Set(actor.ip.map(|ip| ip.to_canonical().to_string())),
+        owner: Set(target.owner.to_string()),
+        team_id: Set(target.team_id),
+    };
+    if let Err(e) = AuditEvent::insert(event).exec_without_returning(db).await {
+        tracing::error!(action, target = %target.id, error = %e, "Failed to record audit event");
+    }
+}
+
+fn model_to_response(model: audit_events::Model) -> shared::AuditEvent {
+    shared::AuditEvent {
+        id: model.id,
+        occurred_at: model.occurred_at.into(),
+        actor: model.actor,
+        api_key_id: model.api_key_id,
+        action: model.action,
+        target_type: model.target_type,
+        target_id: model.target_id,
+        changes: model.changes,
+        ip: model.ip,
+    }
+}
+
+/// One page of events about rows the caller can see, ordered by `occurred_at`.
+pub async fn list(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    query: &AuditQuery,
+    page: &PageRequest,
+) -> Result<Page<shared::AuditEvent>, DbErr> {
+    let mut select = AuditEvent::find().filter(tenant.condition(Column::Owner, Column::TeamId));
+    if let Some(action) = &query.action {
+        select = select.filter(Column::Action.eq(action.as_str()));
+    }
+    if let Some(target_type) = &query.target_type {
+        select = select.filter(Column::TargetType.eq(target_type.as_str()));
+    }
+    if let Some(target_id) = query.target_id {
+        select = select.filter(Column::TargetId.eq(target_id));
+    }
+    if let Some(actor) = &query.actor {
+        select = select.filter(Column::Actor.eq(actor.as_str()));
+    }
+    if let Some(since) = query.since {
+        select = select.filter(Column::OccurredAt.gte(since.fixed_offset()));
+    }
+    if let Some(until) = query.until {
+        select = select.filter(Column::OccurredAt.lte(until.fixed_offset()));
+    }
+
+    let total = select.clone().count(db).await?;
+    let models = page
+        .apply(select, Column::OccurredAt, Column::Id)
+        .all(db)
+        .await?;
+    let (models, next_cursor) = page.finish(models, |m| Cursor {
+        at: m.occurred_at.into(),
+        id: m.id,
+    });
+    Ok(Page {
+        items: models.into_iter().map(model_to_response).collect(),
+        next_cursor,
+        total,
+    })
+}
+
+/// Deletes events older than the retention period. Returns how many were deleted.
+pub async fn delete_expired(db: &DatabaseConnection, config: &AuditConfig) -> Result<u64, DbErr> {
+    let Some(retention) = config.retention else {
+        return Ok(0);
+    };
+    let cutoff = (Utc::now() - retention).fixed_offset();
+    let result = AuditEvent::delete_many()
+        .filter(Column::OccurredAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lists_only_changed_fields() {
+        let before = json!({ "name": "Pale", "og": 1.050, "status": "Active" });
+        let after = json!({ "name": "Pale", "og": 1.052, "status": "Active" });
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            json!({ "og": { "before": 1.050, "after": 1.052 } })
+        );
+    }
+
+    #[test]
+    fn creates_and_deletes_diff_against_nothing() {
+        let model = json!({ "name": "Pale", "notes": null });
+        assert_eq!(
+            diff(None, Some(&model)),
+            json!({ "name": { "before": null, "after": "Pale" } })
+        );
+        assert_eq!(
+            diff(Some(&model), None),
+            json!({ "name": { "before": "Pale", "after": null } })
+        );
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let before = json!({ "key_hash": "aaa", "prefix": "1234" });
+        let after = json!({ "key_hash": "bbb", "prefix": "5678" });
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            json!({
+                "key_hash": { "before": "[redacted]", "after": "[redacted]" },
+                "prefix": { "before": "1234", "after": "5678" },
+            })
+        );
+    }
+}
@@ -4,7 +4,11 @@ use uuid::Uuid;
 use crate::models::entities::brews::{self, ActiveModel, Column, Entity as Brew};
 use crate::models::entities::hydrometers::Entity as Hydrometer;
 use crate::models::entities::readings::{self, Entity as Reading};
-use shared::{BrewResponse, BrewStatus, CreateBrew, TiltColor, TiltReading, UpdateBrew};
+use crate::pagination::{Cursor, PageRequest};
+use crate::services::audit_service::{self, Target};
+use crate::services::{hydrometer_service, reading_service};
+use crate::tenant::Tenant;
+use shared::{BrewResponse, BrewStatus, CreateBrew, Page, TiltColor, TiltReading, UpdateBrew};
 
 fn model_to_response(model: brews::Model, latest: Option<TiltReading>) -> BrewResponse {
     let status = match model.status.as_str() {
@@ -27,14 +31,14 @@ fn model_to_response(model: brews::Model, latest: Option<TiltReading>) -> BrewRe
         hydrometer_id: model.hydrometer_id,
         created_at: model.created_at.into(),
         updated_at: model.updated_at.into(),
+        stable_since: model.stable_since.map(Into::into),
         latest_reading: latest,
+        owner: model.owner,
+        team_id: model.team_id,
     }
 }
 
-async fn latest_reading_for(
-    db: &DatabaseConnection,
-    brew: &brews::Model,
-) -> Option<TiltReading> {
+async fn latest_reading_for(db: &DatabaseConnection, brew: &brews::Model) -> Option<TiltReading> {
     let hydrometer = Hydrometer::find_by_id(brew.hydrometer_id)
         .one(db)
         .await
@@ -46,41 +50,112 @@ async fn latest_reading_for(
         .one(db)
         .await
         .ok()??;
-    Some(TiltReading::new(
-        color,
-        reading.temperature_f,
-        reading.gravity,
-        reading.rssi,
-        reading.recorded_at.into(),
-    ))
+    Some(reading_service::model_to_tilt_reading(&reading, color))
+}
+
+/// A brew the caller can see.
+pub async fn find_visible(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+) -> Result<Option<brews::Model>, DbErr> {
+    Brew::find_by_id(id)
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .filter(tenant.hydrometer_condition(Column::HydrometerId))
+        .one(db)
+        .await
+}
+
+/// A brew the caller can change.
+pub async fn find_editable(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+) -> Result<Option<brews::Model>, DbErr> {
+    Brew::find_by_id(id)
+        .filter(tenant.write_condition(Column::Owner, Column::TeamId))
+        .one(db)
+        .await
 }
 
+/// Ids of every brew the caller can see.
+pub async fn visible_ids(db: &DatabaseConnection, tenant: &Tenant) -> Result<Vec<Uuid>, DbErr> {
+    Brew::find()
+        .select_only()
+        .column(Column::Id)
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+/// One page of the caller's brews ordered by `created_at`.
 pub async fn find_all(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     status_filter: Option<&str>,
-) -> Result<Vec<BrewResponse>, DbErr> {
-    let mut query = Brew::find();
+    page: &PageRequest,
+) -> Result<Page<BrewResponse>, DbErr> {
+    let mut query = Brew::find()
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .filter(tenant.hydrometer_condition(Column::HydrometerId));
     if let Some(status) = status_filter {
         query = query.filter(Column::Status.eq(status));
     }
-    let models = query.all(db).await?;
-    let mut results = Vec::with_capacity(models.len());
+    let total = query.clone().count(db).await?;
+    let models = page
+        .apply(query, Column::CreatedAt, Column::Id)
+        .all(db)
+        .await?;
+    let (models, next_cursor) = page.finish(models, |m| Cursor {
+        at: m.created_at.into(),
+        id: m.id,
+    });
+    let mut items = Vec::with_capacity(models.len());
     for model in models {
         let latest = latest_reading_for(db, &model).await;
-        results.push(model_to_response(model, latest));
+        items.push(model_to_response(model, latest));
     }
-    Ok(results)
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
 }
 
-pub async fn find_by_id(db: &DatabaseConnection, id: Uuid) -> Result<Option<BrewResponse>, DbErr> {
-    let Some(model) = Brew::find_by_id(id).one(db).await? else {
+pub async fn find_by_id(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+) -> Result<Option<BrewResponse>, DbErr> {
+    let Some(model) = find_visible(db, tenant, id).await? else {
         return Ok(None);
     };
     let latest = latest_reading_for(db, &model).await;
     Ok(Some(model_to_response(model, latest)))
 }
 
-pub async fn create(db: &DatabaseConnection, input: CreateBrew) -> Result<BrewResponse, DbErr> {
+fn audit_target(model: &brews::Model) -> Target<'_> {
+    Target {
+        kind: "brew",
+        id: model.id,
+        owner: &model.owner,
+        team_id: model.team_id,
+    }
+}
+
+/// Starts a brew for the caller. `None` if they can't use the hydrometer.
+pub async fn create(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    input: CreateBrew,
+) -> Result<Option<BrewResponse>, DbErr> {
+    if hydrometer_service::find_editable(db, tenant, input.hydrometer_id)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
     let now = chrono::Utc::now();
     let model = ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -97,21 +172,46 @@ pub async fn create(db: &DatabaseConnection, input: CreateBrew) -> Result<BrewRe
         hydrometer_id: Set(input.hydrometer_id),
         created_at: Set(now.into()),
         updated_at: Set(now.into()),
+        stable_since: Set(None),
+        owner: Set(tenant.owner.clone()),
+        team_id: Set(tenant.team_id),
     };
+    let result = insert(db, tenant, model).await?;
+    Ok(Some(model_to_response(result, None)))
+}
+
+/// Stores a new brew and records its creation in the audit log as done by `tenant`.
+pub async fn insert(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    model: ActiveModel,
+) -> Result<brews::Model, DbErr> {
     let result = Brew::insert(model).exec_with_returning(db).await?;
-    Ok(model_to_response(result, None))
+    let after = audit_service::snapshot(&result);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "create",
+        audit_target(&result),
+        None,
+        Some(after),
+    )
+    .await;
+    Ok(result)
 }
 
 pub async fn update(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     id: Uuid,
     input: UpdateBrew,
 ) -> Result<Option<BrewResponse>, DbErr> {
-    let existing = Brew::find_by_id(id).one(db).await?;
+    let existing = find_editable(db, tenant, id).await?;
     let Some(existing) = existing else {
         return Ok(None);
     };
 
+    let before = audit_service::snapshot(&existing);
     let mut active: ActiveModel = existing.into();
     if let Some(name) = input.name {
         active.name = Set(name);
@@ -143,12 +243,37 @@ pub async fn update(
     active.updated_at = Set(chrono::Utc::now().into());
 
     let updated = active.update(db).await?;
+    let after = audit_service::snapshot(&updated);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "update",
+        audit_target(&updated),
+        Some(before),
+        Some(after),
+    )
+    .await;
     let latest = latest_reading_for(db, &updated).await;
     Ok(Some(model_to_response(updated, latest)))
 }
 
-pub async fn delete(db: &DatabaseConnection, id: Uuid) -> Result<bool, DbErr> {
+pub async fn delete(db: &DatabaseConnection, tenant: &Tenant, id: Uuid) -> Result<bool, DbErr> {
+    let Some(existing) = find_editable(db, tenant, id).await? else {
+        return Ok(false);
+    };
     let result = Brew::delete_by_id(id).exec(db).await?;
+    if result.rows_affected > 0 {
+        let before = audit_service::snapshot(&existing);
+        audit_service::record(
+            db,
+            &tenant.actor,
+            "delete",
+            audit_target(&existing),
+            Some(before),
+            None,
+        )
+        .await;
+    }
     Ok(result.rows_affected > 0)
 }
 
@@ -0,0 +1,367 @@
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::calibration_points::{
+    self, ActiveModel, Column, Entity as CalibrationPoint,
+};
+use crate::models::entities::hydrometers::{self, Entity as Hydrometer};
+use crate::models::entities::readings::{self, Entity as Reading};
+use crate::services::{brew_service, hydrometer_service};
+use crate::tenant::Tenant;
+use shared::{CalibrationPointResponse, CalibrationResponse, CreateCalibrationPoint};
+
+/// Highest degree fitted to a calibration table. Extra points beyond `MAX_DEGREE + 1`
+/// are smoothed by least squares rather than raising the degree.
+const MAX_DEGREE: usize = 2;
+
+/// Everything needed to turn a raw Tilt reading into a corrected one.
+#[derive(Debug, Clone, Default)]
+pub struct Calibration {
+    pub temp_offset_f: f64,
+    pub gravity_offset: f64,
+    /// Correction curve in gravity points; see [`shared::CalibrationResponse`].
+    pub coefficients: Vec<f64>,
+}
+
+impl Calibration {
+    pub fn correct_temperature(&self, raw_temperature_f: f64) -> f64 {
+        raw_temperature_f + self.temp_offset_f
+    }
+
+    /// Applies the multi-point curve first, then the single `gravity_offset` on top.
+    pub fn correct_gravity(&self, raw_gravity: f64) -> f64 {
+        let correction = evaluate(&self.coefficients, to_points(raw_gravity));
+        raw_gravity + correction / 1000.0 + self.gravity_offset
+    }
+}
+
+fn to_points(gravity: f64) -> f64 {
+    (gravity - 1.0) * 1000.0
+}
+
+fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Least-squares fit of the correction (actual - raw, in points) as a polynomial of the raw
+/// reading in points. One point gives a constant shift, two a line, three or more a quadratic.
+/// Falls back to a lower degree when the points can't support the requested one
+/// (e.g. duplicate raw values).
+pub fn fit_correction_curve(points: &[(f64, f64)]) -> Vec<f64> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+
+    let xs: Vec<f64> = points.iter().map(|(raw, _)| to_points(*raw)).collect();
+    let ys: Vec<f64> = points
+        .iter()
+        .map(|(raw, actual)| to_points(*actual) - to_points(*raw))
+        .collect();
+
+    let mut degree = (points.len() - 1).min(MAX_DEGREE);
+    loop {
+        if let Some(coefficients) = least_squares(&xs, &ys, degree) {
+            return coefficients;
+        }
+        if degree == 0 {
+            return Vec::new();
+        }
+        degree -= 1;
+    }
+}
+
+fn least_squares(xs: &[f64], ys: &[f64], degree: usize) -> Option<Vec<f64>> {
+    let n = degree + 1;
+    // Normal equations: (XᵀX) c = Xᵀy, stored as an augmented n x (n + 1) matrix.
+    let mut matrix = vec![vec![0.0; n + 1]; n];
+    for (x, y) in xs.iter().zip(ys) {
+        let powers: Vec<f64> = (0..=2 * degree).map(|p| x.powi(p as i32)).collect();
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().take(n).enumerate() {
+                *cell += powers[row + col];
+            }
+            cells[n] += powers[row] * y;
+        }
+    }
+
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        let pivot_row = matrix[col].clone();
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            if row == col {
+                continue;
+            }
+            let factor = cells[col] / pivot_row[col];
+            for (cell, pivot_cell) in cells.iter_mut().zip(&pivot_row).skip(col) {
+                *cell -= factor * pivot_cell;
+            }
+        }
+    }
+
+    Some((0..n).map(|i| matrix[i][n] / matrix[i][i]).collect())
+}
+
+fn point_to_response(model: calibration_points::Model) -> CalibrationPointResponse {
+    CalibrationPointResponse {
+        id: model.id,
+        hydrometer_id: model.hydrometer_id,
+        raw_gravity: model.raw_gravity,
+        actual_gravity: model.actual_gravity,
+        created_at: model.created_at.into(),
+    }
+}
+
+async fn points_for(
+    db: &impl ConnectionTrait,
+    hydrometer_id: Uuid,
+) -> Result<Vec<calibration_points::Model>, DbErr> {
+    CalibrationPoint::find()
+        .filter(Column::HydrometerId.eq(hydrometer_id))
+        .order_by_asc(Column::RawGravity)
+        .all(db)
+        .await
+}
+
+pub async fn load_for_hydrometer(
+    db: &impl ConnectionTrait,
+    hydrometer: &hydrometers::Model,
+) -> Result<Calibration, DbErr> {
+    let points: Vec<(f64, f64)> = points_for(db, hydrometer.id)
+        .await?
+        .into_iter()
+        .map(|p| (p.raw_gravity, p.actual_gravity))
+        .collect();
+    Ok(Calibration {
+        temp_offset_f: hydrometer.temp_offset_f,
+        gravity_offset: hydrometer.gravity_offset,
+        coefficients: fit_correction_curve(&points),
+    })
+}
+
+pub async fn find_for_hydrometer(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    hydrometer_id: Uuid,
+) -> Result<Option<CalibrationResponse>, DbErr> {
+    if hydrometer_service::find_visible(db, tenant, hydrometer_id)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+    let models = points_for(db, hydrometer_id).await?;
+    let pairs: Vec<(f64, f64)> = models
+        .iter()
+        .map(|p| (p.raw_gravity, p.actual_gravity))
+        .collect();
+    Ok(Some(CalibrationResponse {
+        hydrometer_id,
+        coefficients: fit_correction_curve(&pairs),
+        points: models.into_iter().map(point_to_response).collect(),
+    }))
+}
+
+pub async fn add_point(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    hydrometer_id: Uuid,
+    input: CreateCalibrationPoint,
+) -> Result<Option<CalibrationPointResponse>, DbErr> {
+    if hydrometer_service::find_editable(db, tenant, hydrometer_id)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+    let model = ActiveModel {
+        id: Set(Uuid::new_v4()),
+        hydrometer_id: Set(hydrometer_id),
+        raw_gravity: Set(input.raw_gravity),
+        actual_gravity: Set(input.actual_gravity),
+        created_at: Set(chrono::Utc::now().into()),
+    };
+    let result = CalibrationPoint::insert(model)
+        .exec_with_returning(db)
+        .await?;
+    Ok(Some(point_to_response(result)))
+}
+
+pub async fn delete_point(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    hydrometer_id: Uuid,
+    point_id: Uuid,
+) -> Result<bool, DbErr> {
+    if hydrometer_service::find_editable(db, tenant, hydrometer_id)
+        .await?
+        .is_none()
+    {
+        return Ok(false);
+    }
+    let result = CalibrationPoint::delete_many()
+        .filter(Column::Id.eq(point_id))
+        .filter(Column::HydrometerId.eq(hydrometer_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// A single `UPDATE` that recomputes the corrected columns of a brew's readings from their
+/// raw values, mirroring [`Calibration::correct_temperature`] and
+/// [`Calibration::correct_gravity`] in SQL.
+fn recalibrate_statement(calibration: &Calibration, brew_id: Uuid) -> UpdateMany<Reading> {
+    let raw_gravity = || Expr::col(readings::Column::RawGravity);
+    let points = raw_gravity().sub(1.0).mul(1000.0);
+    let correction = match calibration.coefficients.split_last() {
+        Some((last, rest)) => rest
+            .iter()
+            .rev()
+            .fold(Expr::value(*last), |acc, c| acc.mul(points.clone()).add(*c)),
+        None => Expr::value(0.0),
+    };
+
+    Reading::update_many()
+        .col_expr(
+            readings::Column::TemperatureF,
+            Expr::col(readings::Column::RawTemperatureF).add(calibration.temp_offset_f),
+        )
+        .col_expr(
+            readings::Column::Gravity,
+            raw_gravity()
+                .add(correction.div(1000.0))
+                .add(calibration.gravity_offset),
+        )
+        .filter(readings::Column::BrewId.eq(brew_id))
+}
+
+/// Recomputes corrected values for every reading of a brew from its stored raw values,
+/// using the brew hydrometer's current calibration. Returns `None` if the caller can't edit
+/// the brew.
+pub async fn recalibrate_brew(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    brew_id: Uuid,
+) -> Result<Option<u64>, DbErr> {
+    let Some(brew) = brew_service::find_editable(db, tenant, brew_id).await? else {
+        return Ok(None);
+    };
+    let Some(hydrometer) = Hydrometer::find_by_id(brew.hydrometer_id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let calibration = load_for_hydrometer(db, &hydrometer).await?;
+    let count = recalibrate_statement(&calibration, brew_id)
+        .exec(db)
+        .await?
+        .rows_affected;
+
+    tracing::info!(brew_id = %brew_id, count, "Recalibrated brew readings");
+    Ok(Some(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_points_fits_empty_curve() {
+        assert!(fit_correction_curve(&[]).is_empty());
+    }
+
+    #[test]
+    fn single_point_is_constant_shift() {
+        let coefficients = fit_correction_curve(&[(1.002, 1.000)]);
+        assert_eq!(coefficients.len(), 1);
+        assert!((coefficients[0] - (-2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn two_points_fit_a_line() {
+        let calibration = Calibration {
+            coefficients: fit_correction_curve(&[(1.002, 1.000), (1.054, 1.050)]),
+            ..Default::default()
+        };
+        assert_eq!(calibration.coefficients.len(), 2);
+        assert!((calibration.correct_gravity(1.002) - 1.000).abs() < 1e-9);
+        assert!((calibration.correct_gravity(1.054) - 1.050).abs() < 1e-9);
+        // Halfway between the points the correction is halfway between -2 and -4 points.
+        assert!((calibration.correct_gravity(1.028) - 1.025).abs() < 1e-9);
+    }
+
+    #[test]
+    fn three_points_fit_exactly() {
+        let points = [(1.001, 1.000), (1.052, 1.050), (1.106, 1.100)];
+        let calibration = Calibration {
+            coefficients: fit_correction_curve(&points),
+            ..Default::default()
+        };
+        assert_eq!(calibration.coefficients.len(), 3);
+        for (raw, actual) in points {
+            assert!((calibration.correct_gravity(raw) - actual).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn extra_points_stay_quadratic() {
+        let points = [
+            (1.000, 1.000),
+            (1.025, 1.024),
+            (1.050, 1.048),
+            (1.075, 1.072),
+            (1.100, 1.096),
+        ];
+        let coefficients = fit_correction_curve(&points);
+        assert_eq!(coefficients.len(), MAX_DEGREE + 1);
+    }
+
+    #[test]
+    fn duplicate_raw_values_fall_back_to_lower_degree() {
+        let coefficients = fit_correction_curve(&[(1.050, 1.048), (1.050, 1.046)]);
+        assert_eq!(coefficients.len(), 1);
+        assert!((coefficients[0] - (-3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn offsets_apply_on_top_of_curve() {
+        let calibration = Calibration {
+            temp_offset_f: -1.5,
+            gravity_offset: 0.001,
+            coefficients: vec![-2.0],
+        };
+        assert!((calibration.correct_temperature(68.0) - 66.5).abs() < 1e-9);
+        assert!((calibration.correct_gravity(1.050) - 1.049).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recalibration_is_one_set_based_update() {
+        let calibration = Calibration {
+            temp_offset_f: -1.5,
+            gravity_offset: 0.001,
+            coefficients: vec![-2.0, 0.5],
+        };
+        let brew_id = Uuid::nil();
+        let sql = recalibrate_statement(&calibration, brew_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert_eq!(
+            sql,
+            "UPDATE \"readings\" SET \
+             \"temperature_f\" = \"raw_temperature_f\" + -1.5, \
+             \"gravity\" = \"raw_gravity\" + (((0.5 * ((\"raw_gravity\" - 1) * 1000)) + -2) / 1000) + 0.001 \
+             WHERE \"readings\".\"brew_id\" = '00000000-0000-0000-0000-000000000000'"
+        );
+    }
+
+    #[test]
+    fn default_calibration_is_identity() {
+        let calibration = Calibration::default();
+        assert!((calibration.correct_temperature(68.0) - 68.0).abs() < f64::EPSILON);
+        assert!((calibration.correct_gravity(1.050) - 1.050).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,400 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::*;
+use serde_json::json;
+use uuid::Uuid;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+
+use crate::pagination::{Cursor, MAX_PAGE_SIZE, PageRequest};
+use crate::services::analytics_service::{self, Sample};
+use crate::services::{brew_service, hydrometer_service, reading_service};
+use crate::tenant::Tenant;
+use shared::{
+    BrewResponse, ExportRecord, HydrometerResponse, ReadingResponse, ReadingsQuery, SortOrder,
+};
+
+/// Readings fetched per query while streaming an export.
+const EXPORT_PAGE_SIZE: u64 = 1000;
+
+pub const CSV_HEADER: &str = "recorded_at,brew_id,brew_name,hydrometer_id,color,gravity,\
+raw_gravity,temperature_f,raw_temperature_f,rssi,tx_power\n";
+
+#[derive(Debug)]
+pub enum ExportError {
+    Db(DbErr),
+    Archive(ZipError),
+}
+
+impl From<DbErr> for ExportError {
+    fn from(e: DbErr) -> Self {
+        ExportError::Db(e)
+    }
+}
+
+impl From<ZipError> for ExportError {
+    fn from(e: ZipError) -> Self {
+        ExportError::Archive(e)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Archive(e.into())
+    }
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Db(e) => write!(f, "{e}"),
+            ExportError::Archive(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Quotes a field if it contains a delimiter, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+pub fn csv_row(reading: &ReadingResponse, brew_name: Option<&str>) -> String {
+    let optional = |v: Option<i16>| v.map(|v| v.to_string()).unwrap_or_default();
+    format!(
+        "{},{},{},{},{:?},{},{},{},{},{},{}\n",
+        reading.recorded_at.to_rfc3339(),
+        reading.brew_id.map(|id| id.to_string()).unwrap_or_default(),
+        csv_field(brew_name.unwrap_or_default()),
+        reading.hydrometer_id,
+        reading.color,
+        reading.gravity,
+        reading.raw_gravity,
+        reading.temperature_f,
+        reading.raw_temperature_f,
+        optional(reading.rssi),
+        optional(reading.tx_power),
+    )
+}
+
+pub fn jsonl_line(record: &ExportRecord) -> String {
+    let mut line = serde_json::to_string(record).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// BeerJSON 1.0 document for a brew. BeerJSON has no place for a raw reading log, so the
+/// fermentation is summarised as one step per day since the first reading, each with its
+/// start/end gravity and temperature. `samples` must be sorted oldest first.
+pub fn beerjson(brew: &BrewResponse, samples: &[Sample]) -> serde_json::Value {
+    let mut steps = Vec::new();
+    if let Some(first) = samples.first() {
+        let mut day_start = first.recorded_at;
+        let mut rest = samples;
+        let mut day_number = 1;
+        while !rest.is_empty() {
+            let day_end = day_start + Duration::days(1);
+            let len = rest.iter().take_while(|s| s.recorded_at < day_end).count();
+            let (day, tail) = rest.split_at(len);
+            if let (Some(start), Some(end)) = (day.first(), day.last()) {
+                let days = if tail.is_empty() {
+                    (end.recorded_at - day_start).num_minutes() as f64 / 1440.0
+                } else {
+                    1.0
+                };
+                steps.push(json!({
+                    "name": format!("Day {day_number}"),
+                    "start_temperature": { "unit": "F", "value": start.temperature_f },
+                    "end_temperature": { "unit": "F", "value": end.temperature_f },
+                    "step_time": { "unit": "day", "value": (days * 100.0).round() / 100.0 },
+                    "start_gravity": { "unit": "sg", "value": start.gravity },
+                    "end_gravity": { "unit": "sg", "value": end.gravity },
+                }));
+            }
+            rest = tail;
+            day_start = day_end;
+            day_number += 1;
+        }
+    }
+
+    let mut fermentation = json!({
+        "name": brew.name,
+        "fermentation_steps": steps,
+    });
+    let description = [
+        brew.style.as_ref().map(|s| format!("Style: {s}")),
+        brew.og.map(|og| format!("OG: {og:.3}")),
+        brew.fg.map(|fg| format!("FG: {fg:.3}")),
+        brew.abv.map(|abv| format!("ABV: {abv:.1}%")),
+        brew.notes.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n");
+    if !description.is_empty() {
+        fermentation["description"] = json!(description);
+    }
+
+    json!({
+        "beerjson": {
+            "version": 1.0,
+            "fermentations": [fermentation],
+        }
+    })
+}
+
+/// A page of readings for an export, oldest first, and the cursor for the next one.
+/// `brew_id = None` exports every reading the caller can see.
+pub async fn readings_page(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    brew_id: Option<Uuid>,
+    after: Option<Cursor>,
+) -> Result<(Vec<ReadingResponse>, Option<String>), DbErr> {
+    let query = ReadingsQuery {
+        brew_id,
+        hydrometer_id: None,
+        since: None,
+        until: None,
+        limit: None,
+        bucket: None,
+        max_points: None,
+        cursor: None,
+        order: Some(SortOrder::Asc),
+    };
+    let page = PageRequest {
+        cursor: after,
+        order: SortOrder::Asc,
+        limit: EXPORT_PAGE_SIZE,
+    };
+    reading_service::next_readings(db, tenant, &query, &page).await
+}
+
+pub async fn brew_beerjson(
+    db: &DatabaseConnection,
+    brew: &BrewResponse,
+) -> Result<serde_json::Value, DbErr> {
+    let samples = analytics_service::samples_for_brew(db, brew.id).await?;
+    Ok(beerjson(brew, &samples))
+}
+
+async fn all_brews(db: &DatabaseConnection, tenant: &Tenant) -> Result<Vec<BrewResponse>, DbErr> {
+    let mut page = PageRequest {
+        cursor: None,
+        order: SortOrder::Asc,
+        limit: MAX_PAGE_SIZE,
+    };
+    let mut brews = Vec::new();
+    loop {
+        let next = brew_service::find_all(db, tenant, None, &page).await?;
+        brews.extend(next.items);
+        match next.next_cursor.as_deref().and_then(Cursor::decode) {
+            Some(cursor) => page.cursor = Some(cursor),
+            None => return Ok(brews),
+        }
+    }
+}
+
+async fn all_hydrometers(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+) -> Result<Vec<HydrometerResponse>, DbErr> {
+    let mut page = PageRequest {
+        cursor: None,
+        order: SortOrder::Asc,
+        limit: MAX_PAGE_SIZE,
+    };
+    let mut hydrometers = Vec::new();
+    loop {
+        let next = hydrometer_service::find_all(db, tenant, &page).await?;
+        hydrometers.extend(next.items);
+        match next.next_cursor.as_deref().and_then(Cursor::decode) {
+            Some(cursor) => page.cursor = Some(cursor),
+            None => return Ok(hydrometers),
+        }
+    }
+}
+
+/// Zip of everything the caller can see: `hydrometers.json`, `brews.json`, every reading in
+/// `readings.csv` and a BeerJSON summary per brew under `brews/`. Built in memory.
+pub async fn account_archive(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+) -> Result<Vec<u8>, ExportError> {
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+
+    let hydrometers = all_hydrometers(db, tenant).await?;
+    zip.start_file("hydrometers.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &hydrometers).map_err(std::io::Error::from)?;
+
+    let brews = all_brews(db, tenant).await?;
+    zip.start_file("brews.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &brews).map_err(std::io::Error::from)?;
+
+    for brew in &brews {
+        zip.start_file(format!("brews/{}.beer.json", brew.id), options)?;
+        let document = brew_beerjson(db, brew).await?;
+        serde_json::to_writer_pretty(&mut zip, &document).map_err(std::io::Error::from)?;
+    }
+
+    let brew_names: HashMap<Uuid, &str> = brews.iter().map(|b| (b.id, b.name.as_str())).collect();
+    zip.start_file("readings.csv", options)?;
+    zip.write_all(CSV_HEADER.as_bytes())?;
+    let mut after = None;
+    loop {
+        let (readings, next_cursor) = readings_page(db, tenant, None, after).await?;
+        for reading in &readings {
+            let brew_name = reading.brew_id.and_then(|id| brew_names.get(&id).copied());
+            zip.write_all(csv_row(reading, brew_name).as_bytes())?;
+        }
+        after = next_cursor.as_deref().and_then(Cursor::decode);
+        if after.is_none() {
+            break;
+        }
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// `tilt-export-<date>` style file name stem.
+pub fn file_stem(label: &str, at: DateTime<Utc>) -> String {
+    let slug: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!(
+        "{}-{}",
+        if slug.is_empty() { "export" } else { &slug },
+        at.format("%Y%m%d")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{TiltColor, TiltDeviceKind};
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::hours(hours)
+    }
+
+    fn sample(hours: i64, gravity: f64) -> Sample {
+        Sample {
+            recorded_at: at(hours),
+            gravity,
+            temperature_f: 66.0,
+        }
+    }
+
+    fn brew() -> BrewResponse {
+        BrewResponse {
+            id: Uuid::new_v4(),
+            name: "Pale Ale".to_string(),
+            style: Some("APA".to_string()),
+            og: Some(1.050),
+            fg: None,
+            target_fg: None,
+            abv: None,
+            status: shared::BrewStatus::Active,
+            start_date: None,
+            end_date: None,
+            notes: None,
+            hydrometer_id: Uuid::new_v4(),
+            created_at: at(0),
+            updated_at: at(0),
+            stable_since: None,
+            latest_reading: None,
+            owner: "user-1".to_string(),
+            team_id: None,
+        }
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Pale Ale"), "Pale Ale");
+        assert_eq!(csv_field("Ale, Pale"), "\"Ale, Pale\"");
+        assert_eq!(csv_field("The \"House\" IPA"), "\"The \"\"House\"\" IPA\"");
+    }
+
+    #[test]
+    fn csv_row_matches_header_columns() {
+        let reading = ReadingResponse {
+            id: Uuid::new_v4(),
+            brew_id: None,
+            hydrometer_id: Uuid::new_v4(),
+            color: TiltColor::Red,
+            device_kind: TiltDeviceKind::Standard,
+            temperature_f: 66.5,
+            gravity: 1.012,
+            raw_temperature_f: 66.0,
+            raw_gravity: 1.013,
+            rssi: Some(-70),
+            tx_power: None,
+            gateway_id: None,
+            recorded_at: at(0),
+            created_at: at(0),
+        };
+        let row = csv_row(&reading, Some("Ale, Pale"));
+        assert!(row.ends_with(",1.012,1.013,66.5,66,-70,\n"), "{row}");
+        assert_eq!(
+            row.matches(',').count() - 1,
+            CSV_HEADER.matches(',').count()
+        );
+    }
+
+    #[test]
+    fn beerjson_summarises_one_step_per_day() {
+        let samples = vec![
+            sample(0, 1.050),
+            sample(12, 1.040),
+            sample(24, 1.030),
+            sample(36, 1.020),
+            sample(42, 1.015),
+        ];
+        let document = beerjson(&brew(), &samples);
+        let fermentation = &document["beerjson"]["fermentations"][0];
+        let steps = fermentation["fermentation_steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0]["start_gravity"]["value"], 1.050);
+        assert_eq!(steps[0]["end_gravity"]["value"], 1.040);
+        assert_eq!(steps[0]["step_time"]["value"], 1.0);
+        assert_eq!(steps[1]["end_gravity"]["value"], 1.015);
+        assert_eq!(steps[1]["step_time"]["value"], 0.75);
+        assert_eq!(fermentation["description"], "Style: APA\nOG: 1.050");
+    }
+
+    #[test]
+    fn beerjson_without_readings_has_no_steps() {
+        let document = beerjson(&brew(), &[]);
+        let steps = &document["beerjson"]["fermentations"][0]["fermentation_steps"];
+        assert_eq!(steps.as_array().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn file_stem_slugifies_label() {
+        assert_eq!(file_stem("Pale Ale #2", at(0)), "pale-ale-2-20260101");
+        assert_eq!(file_stem("???", at(0)), "export-20260101");
+    }
+}
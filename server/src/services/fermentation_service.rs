@@ -0,0 +1,329 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::*;
+
+use crate::models::entities::brews::{self, ActiveModel, Column, Entity as Brew};
+use crate::services::analytics_service::{self, Sample};
+use crate::services::{notification_service, team_service};
+use shared::{Notification, NotificationEvent};
+
+/// Settings for terminal-gravity detection, read from the environment.
+#[derive(Debug, Clone)]
+pub struct CompletionConfig {
+    /// How long gravity must stay within `tolerance` before it counts as terminal.
+    pub stable_for: Duration,
+    /// Maximum gravity spread (SG) within the stable period.
+    pub tolerance: f64,
+    /// Move brews to `Completed` once terminal instead of only recording it.
+    pub auto_complete: bool,
+}
+
+impl Default for CompletionConfig {
+    fn default() -> Self {
+        Self {
+            stable_for: Duration::days(3),
+            tolerance: 0.002,
+            auto_complete: false,
+        }
+    }
+}
+
+impl CompletionConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let stable_days = std::env::var("FERMENTATION_STABLE_DAYS")
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|d| *d > 0.0);
+        Self {
+            stable_for: stable_days
+                .map(|d| Duration::seconds((d * 86_400.0) as i64))
+                .unwrap_or(defaults.stable_for),
+            tolerance: std::env::var("FERMENTATION_STABLE_TOLERANCE")
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(defaults.tolerance),
+            auto_complete: std::env::var("FERMENTATION_AUTO_COMPLETE")
+                .map(|v| matches!(v.trim(), "1" | "true" | "yes"))
+                .unwrap_or(defaults.auto_complete),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Terminal {
+    pub stable_since: DateTime<Utc>,
+    pub fg: f64,
+}
+
+/// Decides whether a fermentation has reached terminal gravity. Either the trailing
+/// readings have stayed within `config.tolerance` for at least `config.stable_for`, or
+/// every reading over at least `config.stable_for` is at or below `target_fg`, so a single
+/// noisy sample under the target doesn't finish a brew. `samples` must be sorted oldest first.
+pub fn detect_terminal(
+    samples: &[Sample],
+    target_fg: Option<f64>,
+    config: &CompletionConfig,
+) -> Option<Terminal> {
+    let latest = samples.last()?;
+
+    if let Some(target_fg) = target_fg {
+        let below = samples
+            .iter()
+            .rev()
+            .take_while(|s| s.gravity <= target_fg)
+            .last();
+        if let Some(first_below) = below
+            && latest.recorded_at - first_below.recorded_at >= config.stable_for
+        {
+            return Some(Terminal {
+                stable_since: first_below.recorded_at,
+                fg: latest.gravity,
+            });
+        }
+    }
+
+    let (mut min, mut max) = (latest.gravity, latest.gravity);
+    let mut run_start = samples.len() - 1;
+    while run_start > 0 {
+        let gravity = samples[run_start - 1].gravity;
+        if max.max(gravity) - min.min(gravity) > config.tolerance {
+            break;
+        }
+        min = min.min(gravity);
+        max = max.max(gravity);
+        run_start -= 1;
+    }
+    let run = &samples[run_start..];
+    let stable_since = run[0].recorded_at;
+    if latest.recorded_at - stable_since < config.stable_for {
+        return None;
+    }
+    Some(Terminal {
+        stable_since,
+        fg: run.iter().map(|s| s.gravity).sum::<f64>() / run.len() as f64,
+    })
+}
+
+/// Re-evaluates every active brew, recording or clearing `stable_since` and filling in
+/// `fg`/`abv` when they are unset. Returns the brews whose state changed.
+pub async fn check_active_brews(
+    db: &DatabaseConnection,
+    config: &CompletionConfig,
+) -> Result<Vec<brews::Model>, DbErr> {
+    let active = Brew::find()
+        .filter(Column::Status.eq("Active"))
+        .all(db)
+        .await?;
+
+    let mut changed = Vec::new();
+    for brew in active {
+        let samples = analytics_service::recent_samples(db, brew.id, config.stable_for).await?;
+        let terminal = detect_terminal(&samples, brew.target_fg, config);
+
+        let mut active: ActiveModel = brew.clone().into();
+        match (terminal, brew.stable_since) {
+            (Some(terminal), None) => {
+                active.stable_since = Set(Some(terminal.stable_since.into()));
+                if brew.fg.is_none() {
+                    active.fg = Set(Some(terminal.fg));
+                }
+                // OG is detected from the start of the brew, so only then is all of it loaded.
+                let og = match (brew.og, brew.abv) {
+                    (Some(og), _) => Some(og),
+                    (None, None) => analytics_service::detect_og(
+                        &analytics_service::samples_for_brew(db, brew.id).await?,
+                    ),
+                    (None, Some(_)) => None,
+                };
+                if brew.abv.is_none()
+                    && let Some(og) = og
+                {
+                    let fg = brew.fg.unwrap_or(terminal.fg);
+                    active.abv = Set(Some(analytics_service::abv(og, fg)));
+                }
+                if config.auto_complete {
+                    active.status = Set("Completed".to_string());
+                    active.end_date = Set(Some(Utc::now().into()));
+                }
+                tracing::info!(
+                    brew_id = %brew.id,
+                    stable_since = %terminal.stable_since,
+                    fg = terminal.fg,
+                    completed = config.auto_complete,
+                    "Fermentation reached terminal gravity"
+                );
+            }
+            (None, Some(_)) => {
+                active.stable_since = Set(None);
+                tracing::info!(brew_id = %brew.id, "Fermentation no longer stable");
+            }
+            _ => continue,
+        }
+        active.updated_at = Set(Utc::now().into());
+        let updated = active.update(db).await?;
+
+        if let (Some(terminal), None) = (terminal, brew.stable_since) {
+            let notification = completion_notification(&updated, &terminal, config.auto_complete);
+            if let Err(e) = notify_brew_audience(db, &updated, &notification).await {
+                tracing::warn!(brew_id = %brew.id, error = %e, "Failed to queue completion notification");
+            }
+        }
+        changed.push(updated);
+    }
+    Ok(changed)
+}
+
+/// Who hears about a brew: its owner and, for a team brew, the team's members.
+fn recipients(owner: &str, team_members: Vec<String>) -> Vec<String> {
+    let mut recipients = vec![owner.to_string()];
+    for member in team_members {
+        if !recipients.contains(&member) {
+            recipients.push(member);
+        }
+    }
+    recipients
+}
+
+async fn notify_brew_audience(
+    db: &DatabaseConnection,
+    brew: &brews::Model,
+    notification: &Notification,
+) -> Result<(), DbErr> {
+    let team_members = match brew.team_id {
+        Some(team_id) => team_service::member_subs(db, team_id).await?,
+        None => Vec::new(),
+    };
+    for user_sub in recipients(&brew.owner, team_members) {
+        notification_service::notify_user(db, &user_sub, notification).await?;
+    }
+    Ok(())
+}
+
+fn completion_notification(
+    brew: &brews::Model,
+    terminal: &Terminal,
+    completed: bool,
+) -> Notification {
+    let mut message = format!(
+        "Gravity has held at {:.3} since {}.",
+        terminal.fg,
+        terminal.stable_since.format("%Y-%m-%d %H:%M UTC")
+    );
+    if completed {
+        message.push_str(" The brew has been marked completed.");
+    }
+    Notification {
+        event: NotificationEvent::BrewCompleted,
+        title: format!("{} finished fermenting", brew.name),
+        message,
+        brew_id: Some(brew.id),
+        hydrometer_id: Some(brew.hydrometer_id),
+        alert: None,
+        occurred_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(hours: i64, gravity: f64) -> Sample {
+        let base = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        Sample {
+            recorded_at: base + Duration::hours(hours),
+            gravity,
+            temperature_f: 66.0,
+        }
+    }
+
+    fn config() -> CompletionConfig {
+        CompletionConfig {
+            stable_for: Duration::days(2),
+            tolerance: 0.002,
+            auto_complete: false,
+        }
+    }
+
+    fn fermenting_then_flat(flat_hours: i64) -> Vec<Sample> {
+        let mut samples: Vec<Sample> = (0..48)
+            .map(|h| sample(h, 1.050 - 0.0008 * h as f64))
+            .collect();
+        samples.extend((48..48 + flat_hours).map(|h| sample(h, 1.012)));
+        samples
+    }
+
+    #[test]
+    fn no_samples_is_not_terminal() {
+        assert!(detect_terminal(&[], Some(1.010), &config()).is_none());
+    }
+
+    #[test]
+    fn still_dropping_is_not_terminal() {
+        let samples = fermenting_then_flat(0);
+        assert!(detect_terminal(&samples, None, &config()).is_none());
+    }
+
+    #[test]
+    fn flat_for_less_than_stable_period_is_not_terminal() {
+        let samples = fermenting_then_flat(24);
+        assert!(detect_terminal(&samples, None, &config()).is_none());
+    }
+
+    #[test]
+    fn flat_for_stable_period_is_terminal() {
+        let samples = fermenting_then_flat(60);
+        let terminal = detect_terminal(&samples, None, &config()).unwrap();
+        assert!((terminal.fg - 1.012).abs() < 0.001);
+        // The run starts a little before the flat section because the tail of the
+        // drop is already within tolerance of 1.012.
+        assert!(terminal.stable_since <= sample(48, 0.0).recorded_at);
+        assert!(terminal.stable_since >= sample(45, 0.0).recorded_at);
+    }
+
+    #[test]
+    fn stable_period_and_one_earlier_reading_are_enough() {
+        // What `recent_samples` loads: the last two days and the reading before them.
+        let samples = fermenting_then_flat(60);
+        let window = &samples[samples.len() - 50..];
+        assert!(detect_terminal(window, None, &config()).is_some());
+        assert!(detect_terminal(window, Some(1.013), &config()).is_some());
+        // Anything shorter than the stable period can't show it.
+        let short = &samples[samples.len() - 40..];
+        assert!(detect_terminal(short, None, &config()).is_none());
+    }
+
+    #[test]
+    fn single_dip_below_target_fg_is_not_terminal() {
+        let mut samples = fermenting_then_flat(0);
+        samples.push(sample(48, 1.009));
+        assert!(detect_terminal(&samples, Some(1.010), &config()).is_none());
+    }
+
+    #[test]
+    fn staying_below_target_fg_for_stable_period_is_terminal() {
+        let samples = fermenting_then_flat(2);
+        assert!(detect_terminal(&samples, Some(1.013), &config()).is_none());
+
+        let samples = fermenting_then_flat(60);
+        let terminal = detect_terminal(&samples, Some(1.013), &config()).unwrap();
+        assert!((terminal.fg - 1.012).abs() < 1e-9);
+        assert_eq!(terminal.stable_since, sample(47, 0.0).recorded_at);
+    }
+
+    #[test]
+    fn noisy_dip_below_target_is_ignored_once_back_above() {
+        let samples = vec![sample(0, 1.050), sample(1, 1.009), sample(2, 1.030)];
+        assert!(detect_terminal(&samples, Some(1.010), &config()).is_none());
+    }
+
+    #[test]
+    fn completion_reaches_the_owner_and_team_members_once() {
+        assert_eq!(recipients("alice", Vec::new()), vec!["alice"]);
+        assert_eq!(
+            recipients("alice", vec!["bob".to_string(), "alice".to_string()]),
+            vec!["alice", "bob"]
+        );
+    }
+}
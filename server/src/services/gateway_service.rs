@@ -0,0 +1,317 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::api_keys::{self, Entity as ApiKey};
+use crate::models::entities::gateways::{self, Column, Entity as Gateway};
+use crate::services::notification_service;
+use shared::{
+    GatewayHeartbeat, GatewayResponse, GatewayStatus, Notification, NotificationEvent, ScanStats,
+};
+
+/// Longest hostname, version or adapter string stored from a heartbeat.
+const MAX_FIELD_LEN: usize = 255;
+
+/// Settings for gateway liveness and de-duplication, read from the environment.
+#[derive(Debug, Clone)]
+pub struct GatewayConfig {
+    /// A gateway is offline once its last heartbeat is older than this.
+    pub offline_after: Duration,
+    /// Readings of one hydrometer from different gateways this close together are the same
+    /// broadcast; only the strongest signal is kept.
+    pub dedup_window: Duration,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            offline_after: Duration::minutes(5),
+            dedup_window: Duration::seconds(30),
+        }
+    }
+}
+
+impl GatewayConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            offline_after: std::env::var("GATEWAY_OFFLINE_SECONDS")
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|s| *s > 0)
+                .map(Duration::seconds)
+                .unwrap_or(defaults.offline_after),
+            dedup_window: std::env::var("GATEWAY_DEDUP_SECONDS")
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|s| *s >= 0)
+                .map(Duration::seconds)
+                .unwrap_or(defaults.dedup_window),
+        }
+    }
+}
+
+pub fn status(
+    last_seen_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    config: &GatewayConfig,
+) -> GatewayStatus {
+    if now - last_seen_at > config.offline_after {
+        GatewayStatus::Offline
+    } else {
+        GatewayStatus::Online
+    }
+}
+
+/// Rejects heartbeats with oversized free-text fields.
+pub fn validate(heartbeat: &GatewayHeartbeat) -> Result<(), &'static str> {
+    let fields = [
+        Some(&heartbeat.version),
+        heartbeat.hostname.as_ref(),
+        heartbeat.adapter.as_ref(),
+    ];
+    if fields.into_iter().flatten().any(|f| f.len() > MAX_FIELD_LEN) {
+        return Err("version, hostname and adapter must be at most 255 characters");
+    }
+    if heartbeat.version.trim().is_empty() {
+        return Err("version is required");
+    }
+    Ok(())
+}
+
+fn to_response(
+    gateway: gateways::Model,
+    key: &api_keys::Model,
+    now: DateTime<Utc>,
+    config: &GatewayConfig,
+) -> GatewayResponse {
+    let last_seen_at: DateTime<Utc> = gateway.last_seen_at.into();
+    GatewayResponse {
+        id: gateway.id,
+        api_key_id: gateway.api_key_id,
+        name: key.name.clone(),
+        api_key_prefix: key.prefix.clone(),
+        version: gateway.version,
+        hostname: gateway.hostname,
+        adapter: gateway.adapter,
+        buffered_readings: gateway.buffered_readings.max(0) as u64,
+        last_scan: gateway
+            .last_scan
+            .and_then(|s| serde_json::from_value::<ScanStats>(s).ok()),
+        last_ip: gateway.last_ip,
+        status: status(last_seen_at, now, config),
+        first_seen_at: gateway.first_seen_at.into(),
+        last_seen_at,
+    }
+}
+
+/// Records a heartbeat from the client using `key`, registering the gateway on its first
+/// heartbeat. A gateway that had been reported offline is announced as back online.
+pub async fn heartbeat(
+    db: &DatabaseConnection,
+    key: &api_keys::Model,
+    heartbeat: GatewayHeartbeat,
+    ip: Option<String>,
+) -> Result<(), DbErr> {
+    let now = Utc::now();
+    let previous = Gateway::find()
+        .filter(Column::ApiKeyId.eq(key.id))
+        .one(db)
+        .await?;
+
+    let model = gateways::ActiveModel {
+        id: Set(previous.as_ref().map_or_else(Uuid::new_v4, |g| g.id)),
+        api_key_id: Set(key.id),
+        version: Set(heartbeat.version),
+        hostname: Set(heartbeat.hostname),
+        adapter: Set(heartbeat.adapter),
+        buffered_readings: Set(heartbeat.buffered_readings.min(i64::MAX as u64) as i64),
+        last_scan: Set(heartbeat.last_scan.map(|s| serde_json::json!(s))),
+        last_ip: Set(ip),
+        first_seen_at: Set(now.into()),
+        last_seen_at: Set(now.into()),
+        offline_since: Set(None),
+    };
+    Gateway::insert(model)
+        .on_conflict(
+            OnConflict::column(Column::ApiKeyId)
+                .update_columns([
+                    Column::Version,
+                    Column::Hostname,
+                    Column::Adapter,
+                    Column::BufferedReadings,
+                    Column::LastScan,
+                    Column::LastIp,
+                    Column::LastSeenAt,
+                    Column::OfflineSince,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+    if let Some(offline_since) = previous.and_then(|g| g.offline_since) {
+        let offline_since: DateTime<Utc> = offline_since.into();
+        let notification = Notification {
+            event: NotificationEvent::GatewayOnline,
+            title: format!("Gateway {} is back online", key.name),
+            message: format!(
+                "Heartbeats resumed after being offline since {}.",
+                offline_since.format("%Y-%m-%d %H:%M UTC")
+            ),
+            brew_id: None,
+            hydrometer_id: None,
+            alert: None,
+            occurred_at: now,
+        };
+        if let Err(e) = notification_service::notify_user(db, &key.created_by, &notification).await
+        {
+            tracing::warn!(api_key_id = %key.id, error = %e, "Failed to queue gateway notification");
+        }
+    }
+    Ok(())
+}
+
+/// The gateway registered by `api_key_id`'s heartbeats, if it has sent any.
+pub async fn find_by_api_key(
+    db: &DatabaseConnection,
+    api_key_id: Uuid,
+) -> Result<Option<gateways::Model>, DbErr> {
+    Gateway::find()
+        .filter(Column::ApiKeyId.eq(api_key_id))
+        .one(db)
+        .await
+}
+
+/// Gateways registered with the caller's API keys, most recently seen first.
+pub async fn list(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    config: &GatewayConfig,
+) -> Result<Vec<GatewayResponse>, DbErr> {
+    let rows = Gateway::find()
+        .find_also_related(ApiKey)
+        .filter(api_keys::Column::CreatedBy.eq(user_sub))
+        .order_by_desc(Column::LastSeenAt)
+        .all(db)
+        .await?;
+    let now = Utc::now();
+    Ok(rows
+        .into_iter()
+        .filter_map(|(gateway, key)| Some(to_response(gateway, &key?, now, config)))
+        .collect())
+}
+
+pub async fn find(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+    config: &GatewayConfig,
+) -> Result<Option<GatewayResponse>, DbErr> {
+    let row = Gateway::find_by_id(id)
+        .find_also_related(ApiKey)
+        .filter(api_keys::Column::CreatedBy.eq(user_sub))
+        .one(db)
+        .await?;
+    Ok(row.and_then(|(gateway, key)| Some(to_response(gateway, &key?, Utc::now(), config))))
+}
+
+/// Forgets a gateway. It registers again on its next heartbeat unless its key is deleted.
+pub async fn delete(db: &DatabaseConnection, id: Uuid, user_sub: &str) -> Result<bool, DbErr> {
+    let owned = Gateway::find_by_id(id)
+        .inner_join(ApiKey)
+        .filter(api_keys::Column::CreatedBy.eq(user_sub))
+        .one(db)
+        .await?;
+    if owned.is_none() {
+        return Ok(false);
+    }
+    Gateway::delete_by_id(id).exec(db).await?;
+    Ok(true)
+}
+
+/// Marks gateways whose heartbeats have stopped as offline and notifies their owners once.
+/// Returns the gateways newly marked offline.
+pub async fn mark_offline(
+    db: &DatabaseConnection,
+    config: &GatewayConfig,
+) -> Result<Vec<gateways::Model>, DbErr> {
+    let now = Utc::now();
+    let cutoff = now - config.offline_after;
+    let stale = Gateway::find()
+        .find_also_related(ApiKey)
+        .filter(Column::LastSeenAt.lt(cutoff.fixed_offset()))
+        .filter(Column::OfflineSince.is_null())
+        .all(db)
+        .await?;
+
+    let mut changed = Vec::new();
+    for (gateway, key) in stale {
+        let last_seen_at: DateTime<Utc> = gateway.last_seen_at.into();
+        let mut active: gateways::ActiveModel = gateway.into();
+        active.offline_since = Set(Some(now.into()));
+        let gateway = active.update(db).await?;
+        tracing::info!(gateway_id = %gateway.id, %last_seen_at, "Gateway went offline");
+
+        if let Some(key) = key {
+            let notification = Notification {
+                event: NotificationEvent::GatewayOffline,
+                title: format!("Gateway {} is offline", key.name),
+                message: format!(
+                    "No heartbeat from {} since {}.",
+                    gateway.hostname.as_deref().unwrap_or("the gateway"),
+                    last_seen_at.format("%Y-%m-%d %H:%M UTC")
+                ),
+                brew_id: None,
+                hydrometer_id: None,
+                alert: None,
+                occurred_at: now,
+            };
+            if let Err(e) =
+                notification_service::notify_user(db, &key.created_by, &notification).await
+            {
+                tracing::warn!(gateway_id = %gateway.id, error = %e, "Failed to queue gateway notification");
+            }
+        }
+        changed.push(gateway);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heartbeat(version: &str, hostname: Option<String>) -> GatewayHeartbeat {
+        GatewayHeartbeat {
+            version: version.to_string(),
+            hostname,
+            adapter: None,
+            buffered_readings: 0,
+            last_scan: None,
+        }
+    }
+
+    #[test]
+    fn status_goes_offline_after_threshold() {
+        let config = GatewayConfig::default();
+        let now = Utc::now();
+        assert_eq!(status(now, now, &config), GatewayStatus::Online);
+        assert_eq!(
+            status(now - Duration::minutes(5), now, &config),
+            GatewayStatus::Online
+        );
+        assert_eq!(
+            status(now - Duration::minutes(6), now, &config),
+            GatewayStatus::Offline
+        );
+    }
+
+    #[test]
+    fn validate_rejects_missing_version_and_long_fields() {
+        assert!(validate(&heartbeat("0.1.0", Some("brewpi".into()))).is_ok());
+        assert!(validate(&heartbeat(" ", None)).is_err());
+        assert!(validate(&heartbeat("0.1.0", Some("x".repeat(256)))).is_err());
+    }
+}
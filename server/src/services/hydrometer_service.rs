@@ -3,65 +3,131 @@ use uuid::Uuid;
 
 use crate::models::entities::hydrometers::{self, ActiveModel, Column, Entity as Hydrometer};
 use crate::models::entities::readings::{self, Entity as Reading};
-use shared::{CreateHydrometer, HydrometerResponse, TiltColor, TiltReading, UpdateHydrometer};
+use crate::pagination::{Cursor, PageRequest};
+use crate::services::audit_service::{self, Target};
+use crate::services::reading_service;
+use crate::tenant::Tenant;
+use shared::{CreateHydrometer, HydrometerResponse, Page, TiltColor, UpdateHydrometer};
 
-fn model_to_response(model: hydrometers::Model, latest: Option<TiltReading>) -> HydrometerResponse {
+fn model_to_response(
+    model: hydrometers::Model,
+    latest: Option<readings::Model>,
+) -> HydrometerResponse {
+    let color = TiltColor::parse(&model.color).unwrap_or(TiltColor::Red);
     HydrometerResponse {
         id: model.id,
-        color: TiltColor::parse(&model.color).unwrap_or(TiltColor::Red),
+        color,
         name: model.name,
         temp_offset_f: model.temp_offset_f,
         gravity_offset: model.gravity_offset,
         created_at: model.created_at.into(),
-        latest_reading: latest,
+        owner: model.owner,
+        team_id: model.team_id,
+        gateway_id: latest.as_ref().and_then(|r| r.gateway_id),
+        latest_reading: latest.map(|r| reading_service::model_to_tilt_reading(&r, color)),
+    }
+}
+
+fn audit_target(model: &hydrometers::Model) -> Target<'_> {
+    Target {
+        kind: "hydrometer",
+        id: model.id,
+        owner: &model.owner,
+        team_id: model.team_id,
     }
 }
 
 async fn latest_reading_for(
     db: &DatabaseConnection,
     hydrometer_id: Uuid,
-    color: &TiltColor,
-) -> Option<TiltReading> {
-    let reading = Reading::find()
+) -> Option<readings::Model> {
+    Reading::find()
         .filter(readings::Column::HydrometerId.eq(hydrometer_id))
         .order_by_desc(readings::Column::RecordedAt)
         .one(db)
         .await
-        .ok()??;
-    Some(TiltReading::new(
-        *color,
-        reading.temperature_f,
-        reading.gravity,
-        reading.rssi,
-        reading.recorded_at.into(),
-    ))
+        .ok()?
+}
+
+/// A hydrometer the caller can see.
+pub async fn find_visible(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+) -> Result<Option<hydrometers::Model>, DbErr> {
+    Hydrometer::find_by_id(id)
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .one(db)
+        .await
 }
 
-pub async fn find_all(db: &DatabaseConnection) -> Result<Vec<HydrometerResponse>, DbErr> {
-    let models = Hydrometer::find().all(db).await?;
-    let mut results = Vec::with_capacity(models.len());
+/// A hydrometer the caller can change.
+pub async fn find_editable(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    id: Uuid,
+) -> Result<Option<hydrometers::Model>, DbErr> {
+    Hydrometer::find_by_id(id)
+        .filter(tenant.write_condition(Column::Owner, Column::TeamId))
+        .one(db)
+        .await
+}
+
+/// Ids of every hydrometer the caller can see.
+pub async fn visible_ids(db: &DatabaseConnection, tenant: &Tenant) -> Result<Vec<Uuid>, DbErr> {
+    Hydrometer::find()
+        .select_only()
+        .column(Column::Id)
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+/// One page of the caller's hydrometers ordered by `created_at`.
+pub async fn find_all(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    page: &PageRequest,
+) -> Result<Page<HydrometerResponse>, DbErr> {
+    let query = Hydrometer::find().filter(tenant.condition(Column::Owner, Column::TeamId));
+    let total = query.clone().count(db).await?;
+    let models = page
+        .apply(query, Column::CreatedAt, Column::Id)
+        .all(db)
+        .await?;
+    let (models, next_cursor) = page.finish(models, |m| Cursor {
+        at: m.created_at.into(),
+        id: m.id,
+    });
+    let mut items = Vec::with_capacity(models.len());
     for model in models {
-        let color = TiltColor::parse(&model.color).unwrap_or(TiltColor::Red);
-        let latest = latest_reading_for(db, model.id, &color).await;
-        results.push(model_to_response(model, latest));
+        let latest = latest_reading_for(db, model.id).await;
+        items.push(model_to_response(model, latest));
     }
-    Ok(results)
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
 }
 
 pub async fn find_by_id(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     id: Uuid,
 ) -> Result<Option<HydrometerResponse>, DbErr> {
-    let Some(model) = Hydrometer::find_by_id(id).one(db).await? else {
+    let Some(model) = find_visible(db, tenant, id).await? else {
         return Ok(None);
     };
-    let color = TiltColor::parse(&model.color).unwrap_or(TiltColor::Red);
-    let latest = latest_reading_for(db, model.id, &color).await;
+    let latest = latest_reading_for(db, model.id).await;
     Ok(Some(model_to_response(model, latest)))
 }
 
+/// Registers a hydrometer for the caller. Fails if their scope already has one of this color.
 pub async fn create(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     input: CreateHydrometer,
 ) -> Result<HydrometerResponse, DbErr> {
     let model = ActiveModel {
@@ -71,21 +137,35 @@ pub async fn create(
         temp_offset_f: Set(0.0),
         gravity_offset: Set(0.0),
         created_at: Set(chrono::Utc::now().into()),
+        owner: Set(tenant.owner.clone()),
+        team_id: Set(tenant.team_id),
     };
     let result = Hydrometer::insert(model).exec_with_returning(db).await?;
+    let after = audit_service::snapshot(&result);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "create",
+        audit_target(&result),
+        None,
+        Some(after),
+    )
+    .await;
     Ok(model_to_response(result, None))
 }
 
 pub async fn update(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     id: Uuid,
     input: UpdateHydrometer,
 ) -> Result<Option<HydrometerResponse>, DbErr> {
-    let existing = Hydrometer::find_by_id(id).one(db).await?;
+    let existing = find_editable(db, tenant, id).await?;
     let Some(existing) = existing else {
         return Ok(None);
     };
 
+    let before = audit_service::snapshot(&existing);
     let mut active: ActiveModel = existing.into();
     if let Some(name) = input.name {
         active.name = Set(Some(name));
@@ -98,32 +178,71 @@ pub async fn update(
     }
 
     let updated = active.update(db).await?;
-    let color = TiltColor::parse(&updated.color).unwrap_or(TiltColor::Red);
-    let latest = latest_reading_for(db, updated.id, &color).await;
+    let after = audit_service::snapshot(&updated);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "update",
+        audit_target(&updated),
+        Some(before),
+        Some(after),
+    )
+    .await;
+    let latest = latest_reading_for(db, updated.id).await;
     Ok(Some(model_to_response(updated, latest)))
 }
 
-pub async fn delete(db: &DatabaseConnection, id: Uuid) -> Result<bool, DbErr> {
+pub async fn delete(db: &DatabaseConnection, tenant: &Tenant, id: Uuid) -> Result<bool, DbErr> {
+    let Some(existing) = find_editable(db, tenant, id).await? else {
+        return Ok(false);
+    };
     let result = Hydrometer::delete_by_id(id).exec(db).await?;
+    if result.rows_affected > 0 {
+        let before = audit_service::snapshot(&existing);
+        audit_service::record(
+            db,
+            &tenant.actor,
+            "delete",
+            audit_target(&existing),
+            Some(before),
+            None,
+        )
+        .await;
+    }
     Ok(result.rows_affected > 0)
 }
 
+/// The hydrometer of this color in the caller's scope: the team's when they act in one, so
+/// every member's keys share it, otherwise their personal one. Colors are unique per scope.
+fn by_color(tenant: &Tenant, color: &shared::TiltColor) -> Select<Hydrometer> {
+    let scope = match tenant.team_id {
+        Some(team_id) => Condition::all().add(Column::TeamId.eq(team_id)),
+        None => Condition::all()
+            .add(Column::Owner.eq(tenant.owner.as_str()))
+            .add(Column::TeamId.is_null()),
+    };
+    Hydrometer::find()
+        .filter(scope)
+        .filter(Column::Color.eq(format!("{:?}", color)))
+}
+
 pub async fn find_by_color(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     color: &shared::TiltColor,
 ) -> Result<Option<hydrometers::Model>, DbErr> {
-    Hydrometer::find()
-        .filter(Column::Color.eq(format!("{:?}", color)))
-        .one(db)
-        .await
+    by_color(tenant, color).one(db).await
 }
 
+/// The caller's hydrometer of this color, registering one if there is none yet. The flag is
+/// `true` when it was just created.
 pub async fn find_or_create_by_color(
     db: &DatabaseConnection,
+    tenant: &Tenant,
     color: &shared::TiltColor,
-) -> Result<hydrometers::Model, DbErr> {
-    if let Some(existing) = find_by_color(db, color).await? {
-        return Ok(existing);
+) -> Result<(hydrometers::Model, bool), DbErr> {
+    if let Some(existing) = find_by_color(db, tenant, color).await? {
+        return Ok((existing, false));
     }
     let model = ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -132,8 +251,84 @@ pub async fn find_or_create_by_color(
         temp_offset_f: Set(0.0),
         gravity_offset: Set(0.0),
         created_at: Set(chrono::Utc::now().into()),
+        owner: Set(tenant.owner.clone()),
+        team_id: Set(tenant.team_id),
     };
-    let result = Hydrometer::insert(model).exec_with_returning(db).await?;
-    tracing::info!(color = ?color, id = %result.id, "Auto-registered new hydrometer");
-    Ok(result)
+    let result = match Hydrometer::insert(model).exec_with_returning(db).await {
+        Ok(result) => result,
+        // Another upload to the same scope registered it first.
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            let existing = find_by_color(db, tenant, color).await?;
+            return existing.map(|existing| (existing, false)).ok_or(e);
+        }
+        Err(e) => return Err(e),
+    };
+    tracing::info!(color = ?color, id = %result.id, owner = %result.owner, "Auto-registered new hydrometer");
+    let after = audit_service::snapshot(&result);
+    audit_service::record(
+        db,
+        &tenant.actor,
+        "create",
+        audit_target(&result),
+        None,
+        Some(after),
+    )
+    .await;
+    Ok((result, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entities::api_keys;
+    use shared::TeamRole;
+
+    fn team_key(created_by: &str, team_id: Uuid) -> api_keys::Model {
+        let now = chrono::Utc::now().fixed_offset();
+        api_keys::Model {
+            id: Uuid::new_v4(),
+            name: "pi".to_string(),
+            key_hash: String::new(),
+            prefix: String::new(),
+            created_by: created_by.to_string(),
+            last_used_at: None,
+            expires_at: None,
+            created_at: now,
+            team_id: Some(team_id),
+            scopes: serde_json::json!(["readings:write"]),
+            allowed_colors: None,
+            allowed_ips: None,
+            previous_key_hash: None,
+            previous_expires_at: None,
+            rotated_at: None,
+            request_count: 0,
+            last_ip: None,
+            last_user_agent: None,
+        }
+    }
+
+    /// The `WHERE` clause of the lookup.
+    fn sql(tenant: &Tenant) -> String {
+        let sql = by_color(tenant, &TiltColor::Red)
+            .build(DbBackend::Postgres)
+            .to_string();
+        sql.split_once(" WHERE ").unwrap().1.to_string()
+    }
+
+    #[test]
+    fn team_keys_of_different_members_share_the_teams_hydrometer() {
+        let team = Uuid::new_v4();
+        let alice = Tenant::api_key(&team_key("alice", team), Some(TeamRole::Brewer));
+        let bob = Tenant::api_key(&team_key("bob", team), Some(TeamRole::Brewer));
+        assert_eq!(sql(&alice), sql(&bob));
+        assert!(sql(&alice).contains(&format!(r#""team_id" = '{team}'"#)));
+        assert!(!sql(&alice).contains(r#""owner""#));
+    }
+
+    #[test]
+    fn personal_lookups_skip_team_hydrometers() {
+        let sql = sql(&Tenant::user("alice"));
+        assert!(sql.contains(r#""owner" = 'alice'"#));
+        assert!(sql.contains(r#""team_id" IS NULL"#));
+    }
 }
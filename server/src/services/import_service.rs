@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::brews::{self, Entity as Brew};
+use crate::models::entities::hydrometers;
+use crate::models::entities::readings::{Column, Entity as Reading};
+use crate::services::calibration_service::Calibration;
+use crate::services::reading_service::BatchError;
+use crate::services::{brew_service, hydrometer_service, reading_service};
+use crate::tenant::Tenant;
+use shared::tilt_csv::{LogRow, ParsedLog};
+use shared::{ImportReport, TiltColor, TiltReading};
+
+/// Readings per insert statement, well under Postgres' bind parameter limit.
+const INSERT_CHUNK: usize = 1000;
+
+/// Splits `rows` (sorted oldest first) into new rows and a count of duplicates, i.e. rows
+/// whose time is already in `existing` or repeats an earlier row.
+pub fn dedup(rows: Vec<LogRow>, existing: &[DateTime<Utc>]) -> (Vec<LogRow>, u64) {
+    let mut seen: HashSet<i64> = existing.iter().map(|at| at.timestamp_micros()).collect();
+    let mut duplicates = 0;
+    let fresh = rows
+        .into_iter()
+        .filter(|row| {
+            let new = seen.insert(row.recorded_at.timestamp_micros());
+            duplicates += u64::from(!new);
+            new
+        })
+        .collect();
+    (fresh, duplicates)
+}
+
+async fn existing_times(
+    db: &DatabaseConnection,
+    hydrometer_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<DateTime<Utc>>, DbErr> {
+    let times: Vec<DateTime<FixedOffset>> = Reading::find()
+        .select_only()
+        .column(Column::RecordedAt)
+        .filter(Column::HydrometerId.eq(hydrometer_id))
+        .filter(Column::RecordedAt.between(from.fixed_offset(), to.fixed_offset()))
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(times.into_iter().map(Into::into).collect())
+}
+
+/// Caller's brew named `name` (case-insensitive), preferring one on this hydrometer.
+/// Historical logs for an unknown name get a new completed brew spanning their readings when
+/// `create` is set.
+async fn resolve_brew(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    name: &str,
+    hydrometer: &hydrometers::Model,
+    rows: &[LogRow],
+    create: bool,
+    report: &mut ImportReport,
+) -> Result<Option<Uuid>, DbErr> {
+    let matches = Brew::find()
+        .filter(tenant.condition(brews::Column::Owner, brews::Column::TeamId))
+        .filter(Expr::expr(Func::lower(Expr::col(brews::Column::Name))).eq(name.to_lowercase()))
+        .order_by_desc(brews::Column::CreatedAt)
+        .all(db)
+        .await?;
+    if let Some(brew) = matches
+        .iter()
+        .find(|b| b.hydrometer_id == hydrometer.id)
+        .or(matches.first())
+    {
+        return Ok(Some(brew.id));
+    }
+    if !create {
+        return Ok(None);
+    }
+
+    let now = Utc::now();
+    let brew = brews::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(name.to_string()),
+        style: Set(None),
+        og: Set(None),
+        fg: Set(None),
+        target_fg: Set(None),
+        abv: Set(None),
+        status: Set("Completed".to_string()),
+        start_date: Set(rows.first().map(|r| r.recorded_at.into())),
+        end_date: Set(rows.last().map(|r| r.recorded_at.into())),
+        notes: Set(Some("Imported from a Tilt log.".to_string())),
+        hydrometer_id: Set(hydrometer.id),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+        stable_since: Set(None),
+        owner: Set(hydrometer.owner.clone()),
+        team_id: Set(hydrometer.team_id),
+    };
+    let brew = brew_service::insert(db, tenant, brew).await?;
+    report.brews_created.push(brew.name);
+    Ok(Some(brew.id))
+}
+
+/// Stores parsed log rows, creating hydrometers by color and matching brews by beer name.
+/// Values are stored as logged (the apps have already applied their own calibration), and
+/// rows whose hydrometer already has a reading at the same instant are skipped, so a file
+/// can safely be imported again. Imported history doesn't trigger alerts or live events.
+pub async fn import(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    log: ParsedLog,
+    create_brews: bool,
+) -> Result<ImportReport, DbErr> {
+    let mut report = ImportReport {
+        rows: log.rows.len() + log.errors.len(),
+        errors: log.errors,
+        ..ImportReport::default()
+    };
+
+    let mut by_color: HashMap<TiltColor, Vec<LogRow>> = HashMap::new();
+    for row in log.rows {
+        by_color.entry(row.color).or_default().push(row);
+    }
+
+    for (color, mut rows) in by_color {
+        rows.sort_by_key(|r| r.recorded_at);
+        let (hydrometer, _) =
+            hydrometer_service::find_or_create_by_color(db, tenant, &color).await?;
+        let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+            continue;
+        };
+        let existing =
+            existing_times(db, hydrometer.id, first.recorded_at, last.recorded_at).await?;
+        let (rows, duplicates) = dedup(rows, &existing);
+        report.duplicates += duplicates;
+
+        let mut by_beer: HashMap<Option<String>, Vec<LogRow>> = HashMap::new();
+        for row in rows {
+            by_beer.entry(row.beer.clone()).or_default().push(row);
+        }
+
+        for (beer, rows) in by_beer {
+            let brew_id = match beer {
+                Some(name) => {
+                    resolve_brew(db, tenant, &name, &hydrometer, &rows, create_brews, &mut report)
+                        .await?
+                }
+                None => None,
+            };
+            for chunk in rows.chunks(INSERT_CHUNK) {
+                let readings = chunk
+                    .iter()
+                    .map(|r| {
+                        TiltReading::new(color, r.temperature_f, r.gravity, None, r.recorded_at)
+                    })
+                    .collect();
+                let inserted = reading_service::batch_create(
+                    db,
+                    readings,
+                    &hydrometer,
+                    brew_id,
+                    &Calibration::default(),
+                    None,
+                )
+                .await
+                .map_err(|e| match e {
+                    BatchError::Db(e) => e,
+                    // Imported rows get fresh ids, so they can't collide with other rows.
+                    BatchError::IdConflict => DbErr::Custom("reading id conflict".to_string()),
+                })?
+                .inserted;
+                report.imported += inserted.len() as u64;
+                report.duplicates += (chunk.len() - inserted.len()) as u64;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: usize, minute: i64) -> LogRow {
+        LogRow {
+            line,
+            recorded_at: DateTime::from_timestamp(1_767_225_600 + minute * 60, 0).unwrap(),
+            gravity: 1.050,
+            temperature_f: 68.0,
+            color: TiltColor::Red,
+            beer: None,
+        }
+    }
+
+    #[test]
+    fn dedup_skips_existing_and_repeated_times() {
+        let rows = vec![row(2, 0), row(3, 1), row(4, 1), row(5, 2)];
+        let existing = vec![row(0, 2).recorded_at];
+        let (fresh, duplicates) = dedup(rows, &existing);
+        assert_eq!(fresh.iter().map(|r| r.line).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(duplicates, 2);
+    }
+
+    #[test]
+    fn dedup_without_existing_keeps_everything() {
+        let (fresh, duplicates) = dedup(vec![row(2, 0), row(3, 5)], &[]);
+        assert_eq!(fresh.len(), 2);
+        assert_eq!(duplicates, 0);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod alert_service;
+pub mod analytics_service;
 pub mod api_keys;
+pub mod audit_service;
 pub mod brew_service;
+pub mod calibration_service;
+pub mod export_service;
+pub mod fermentation_service;
+pub mod gateway_service;
 pub mod hydrometer_service;
+pub mod import_service;
+pub mod notification_service;
 pub mod reading_service;
 pub mod sessions;
+pub mod team_service;
@@ -0,0 +1,413 @@
+use chrono::{Duration, Utc};
+use sea_orm::*;
+use uuid::Uuid;
+
+use crate::models::entities::notification_channels::{self, Entity as NotificationChannel};
+use crate::models::entities::notification_deliveries::{self, Entity as NotificationDelivery};
+use crate::notifications::{self, Notifier};
+use shared::{
+    ChannelConfig, CreateNotificationChannel, DeliveryStatus, Notification,
+    NotificationChannelResponse, NotificationDeliveryResponse, NotificationEvent,
+    UpdateNotificationChannel,
+};
+
+/// Attempts before a delivery is given up on and marked `Failed`.
+pub const MAX_ATTEMPTS: i32 = 5;
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 3600;
+/// Deliveries picked up per dispatcher pass.
+const DISPATCH_BATCH: u64 = 50;
+const DEFAULT_DELIVERY_LIMIT: u64 = 100;
+
+#[derive(Debug)]
+pub enum NotificationError {
+    Invalid(&'static str),
+    Db(DbErr),
+}
+
+impl From<DbErr> for NotificationError {
+    fn from(e: DbErr) -> Self {
+        NotificationError::Db(e)
+    }
+}
+
+/// Exponential backoff after the `attempts`-th failure: 30s, 1m, 2m, 4m, ... capped at an hour.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    Duration::seconds((RETRY_BASE_SECONDS * 2i64.pow(exponent)).min(RETRY_MAX_SECONDS))
+}
+
+/// An empty subscription list means every event. Test sends always go through.
+pub fn subscribed(events: &[NotificationEvent], event: NotificationEvent) -> bool {
+    event == NotificationEvent::Test || events.is_empty() || events.contains(&event)
+}
+
+fn parse_config(model: &notification_channels::Model) -> Option<ChannelConfig> {
+    serde_json::from_value(model.config.clone()).ok()
+}
+
+fn parse_events(model: &notification_channels::Model) -> Vec<NotificationEvent> {
+    serde_json::from_value(model.events.clone()).unwrap_or_default()
+}
+
+fn channel_to_response(model: notification_channels::Model) -> Option<NotificationChannelResponse> {
+    let config = parse_config(&model)?;
+    Some(NotificationChannelResponse {
+        id: model.id,
+        kind: config.kind(),
+        events: parse_events(&model),
+        config,
+        name: model.name,
+        enabled: model.enabled,
+        created_at: model.created_at.into(),
+        updated_at: model.updated_at.into(),
+    })
+}
+
+fn delivery_to_response(model: notification_deliveries::Model) -> NotificationDeliveryResponse {
+    let title = model
+        .payload
+        .get("title")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string();
+    NotificationDeliveryResponse {
+        id: model.id,
+        channel_id: model.channel_id,
+        event: NotificationEvent::parse(&model.event).unwrap_or(NotificationEvent::Test),
+        title,
+        status: DeliveryStatus::parse(&model.status).unwrap_or(DeliveryStatus::Failed),
+        attempts: model.attempts,
+        last_error: model.last_error,
+        next_attempt_at: model.next_attempt_at.map(Into::into),
+        created_at: model.created_at.into(),
+        delivered_at: model.delivered_at.map(Into::into),
+    }
+}
+
+async fn find_owned_channel(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<Option<notification_channels::Model>, DbErr> {
+    NotificationChannel::find_by_id(id)
+        .filter(notification_channels::Column::CreatedBy.eq(user_sub))
+        .one(db)
+        .await
+}
+
+pub async fn list_channels(
+    db: &DatabaseConnection,
+    user_sub: &str,
+) -> Result<Vec<NotificationChannelResponse>, DbErr> {
+    let models = NotificationChannel::find()
+        .filter(notification_channels::Column::CreatedBy.eq(user_sub))
+        .order_by_asc(notification_channels::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(models.into_iter().filter_map(channel_to_response).collect())
+}
+
+pub async fn find_channel(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<Option<NotificationChannelResponse>, DbErr> {
+    Ok(find_owned_channel(db, id, user_sub)
+        .await?
+        .and_then(channel_to_response))
+}
+
+pub async fn create_channel(
+    db: &DatabaseConnection,
+    input: CreateNotificationChannel,
+    user_sub: &str,
+) -> Result<NotificationChannelResponse, NotificationError> {
+    notifications::validate(&input.config).map_err(NotificationError::Invalid)?;
+
+    let now = Utc::now();
+    let model = notification_channels::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(input.name),
+        kind: Set(format!("{:?}", input.config.kind())),
+        config: Set(serde_json::json!(input.config)),
+        events: Set(serde_json::json!(input.events)),
+        enabled: Set(input.enabled.unwrap_or(true)),
+        created_by: Set(user_sub.to_string()),
+        created_at: Set(now.into()),
+        updated_at: Set(now.into()),
+    };
+    let inserted = NotificationChannel::insert(model)
+        .exec_with_returning(db)
+        .await?;
+    channel_to_response(inserted).ok_or(NotificationError::Invalid("invalid channel config"))
+}
+
+pub async fn update_channel(
+    db: &DatabaseConnection,
+    id: Uuid,
+    input: UpdateNotificationChannel,
+    user_sub: &str,
+) -> Result<Option<NotificationChannelResponse>, NotificationError> {
+    let Some(existing) = find_owned_channel(db, id, user_sub).await? else {
+        return Ok(None);
+    };
+
+    let mut active: notification_channels::ActiveModel = existing.into();
+    if let Some(name) = input.name {
+        active.name = Set(name);
+    }
+    if let Some(config) = input.config {
+        notifications::validate(&config).map_err(NotificationError::Invalid)?;
+        active.kind = Set(format!("{:?}", config.kind()));
+        active.config = Set(serde_json::json!(config));
+    }
+    if let Some(events) = input.events {
+        active.events = Set(serde_json::json!(events));
+    }
+    if let Some(enabled) = input.enabled {
+        active.enabled = Set(enabled);
+    }
+    active.updated_at = Set(Utc::now().into());
+
+    let updated = active.update(db).await?;
+    Ok(channel_to_response(updated))
+}
+
+pub async fn delete_channel(
+    db: &DatabaseConnection,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<bool, DbErr> {
+    let result = NotificationChannel::delete_many()
+        .filter(notification_channels::Column::Id.eq(id))
+        .filter(notification_channels::Column::CreatedBy.eq(user_sub))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Queues a `Pending` delivery on each of `user_sub`'s enabled channels subscribed to the
+/// event. The dispatcher sends them. Nothing is ever sent to every user's channels.
+async fn enqueue(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    notification: &Notification,
+) -> Result<u64, DbErr> {
+    let channels: Vec<_> = NotificationChannel::find()
+        .filter(notification_channels::Column::Enabled.eq(true))
+        .filter(notification_channels::Column::CreatedBy.eq(user_sub))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter(|c| subscribed(&parse_events(c), notification.event))
+        .collect();
+    if channels.is_empty() {
+        return Ok(0);
+    }
+
+    let now = Utc::now();
+    let payload = serde_json::json!(notification);
+    let models: Vec<notification_deliveries::ActiveModel> = channels
+        .iter()
+        .map(|channel| notification_deliveries::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            channel_id: Set(channel.id),
+            event: Set(format!("{:?}", notification.event)),
+            payload: Set(payload.clone()),
+            status: Set("Pending".to_string()),
+            attempts: Set(0),
+            last_error: Set(None),
+            next_attempt_at: Set(Some(now.into())),
+            created_at: Set(now.into()),
+            delivered_at: Set(None),
+        })
+        .collect();
+    let count = models.len() as u64;
+    NotificationDelivery::insert_many(models)
+        .exec_without_returning(db)
+        .await?;
+    Ok(count)
+}
+
+/// Notifies one user's channels, e.g. the owner of an alert rule.
+pub async fn notify_user(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    notification: &Notification,
+) -> Result<u64, DbErr> {
+    enqueue(db, user_sub, notification).await
+}
+
+/// Makes one delivery attempt and records the outcome. Failures are rescheduled with
+/// backoff unless `retry` is false or the attempt budget is spent.
+async fn attempt(
+    db: &DatabaseConnection,
+    notifier: &Notifier,
+    delivery: notification_deliveries::Model,
+    channel: Option<&notification_channels::Model>,
+    retry: bool,
+) -> Result<notification_deliveries::Model, DbErr> {
+    let outcome = match channel {
+        None => Err("channel no longer exists".to_string()),
+        Some(channel) if !channel.enabled => Err("channel is disabled".to_string()),
+        Some(channel) => {
+            let config = parse_config(channel);
+            let notification: Option<Notification> =
+                serde_json::from_value(delivery.payload.clone()).ok();
+            match (config, notification) {
+                (Some(config), Some(notification)) => notifier
+                    .send(&config, &notification)
+                    .await
+                    .map_err(|e| e.to_string()),
+                _ => Err("stored channel config or payload is unreadable".to_string()),
+            }
+        }
+    };
+
+    let now = Utc::now();
+    let attempts = delivery.attempts + 1;
+    let mut active: notification_deliveries::ActiveModel = delivery.into();
+    active.attempts = Set(attempts);
+    match outcome {
+        Ok(()) => {
+            active.status = Set("Delivered".to_string());
+            active.delivered_at = Set(Some(now.into()));
+            active.next_attempt_at = Set(None);
+            active.last_error = Set(None);
+        }
+        Err(error) => {
+            tracing::warn!(attempts, error = %error, "Notification delivery failed");
+            if retry && attempts < MAX_ATTEMPTS {
+                active.next_attempt_at = Set(Some((now + retry_delay(attempts)).into()));
+            } else {
+                active.status = Set("Failed".to_string());
+                active.next_attempt_at = Set(None);
+            }
+            active.last_error = Set(Some(error));
+        }
+    }
+    active.update(db).await
+}
+
+/// Sends pending deliveries whose retry time has come. Returns how many were attempted.
+pub async fn deliver_due(db: &DatabaseConnection, notifier: &Notifier) -> Result<usize, DbErr> {
+    let due = NotificationDelivery::find()
+        .filter(notification_deliveries::Column::Status.eq("Pending"))
+        .filter(notification_deliveries::Column::NextAttemptAt.lte(Utc::now()))
+        .order_by_asc(notification_deliveries::Column::CreatedAt)
+        .limit(DISPATCH_BATCH)
+        .find_also_related(NotificationChannel)
+        .all(db)
+        .await?;
+
+    let count = due.len();
+    for (delivery, channel) in due {
+        attempt(db, notifier, delivery, channel.as_ref(), true).await?;
+    }
+    Ok(count)
+}
+
+/// Sends a test notification right away, without retries, and returns the logged delivery.
+pub async fn send_test(
+    db: &DatabaseConnection,
+    notifier: &Notifier,
+    id: Uuid,
+    user_sub: &str,
+) -> Result<Option<NotificationDeliveryResponse>, DbErr> {
+    let Some(channel) = find_owned_channel(db, id, user_sub).await? else {
+        return Ok(None);
+    };
+
+    let notification = Notification {
+        event: NotificationEvent::Test,
+        title: "Test notification".to_string(),
+        message: format!("Notification channel \"{}\" is working.", channel.name),
+        brew_id: None,
+        hydrometer_id: None,
+        alert: None,
+        occurred_at: Utc::now(),
+    };
+    let delivery = notification_deliveries::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        channel_id: Set(channel.id),
+        event: Set(format!("{:?}", NotificationEvent::Test)),
+        payload: Set(serde_json::json!(notification)),
+        status: Set("Pending".to_string()),
+        attempts: Set(0),
+        last_error: Set(None),
+        next_attempt_at: Set(None),
+        created_at: Set(notification.occurred_at.into()),
+        delivered_at: Set(None),
+    };
+    let delivery = NotificationDelivery::insert(delivery)
+        .exec_with_returning(db)
+        .await?;
+
+    // A disabled channel should still be testable.
+    let channel = notification_channels::Model {
+        enabled: true,
+        ..channel
+    };
+    let delivery = attempt(db, notifier, delivery, Some(&channel), false).await?;
+    Ok(Some(delivery_to_response(delivery)))
+}
+
+/// Delivery log for the caller's channels, newest first.
+pub async fn list_deliveries(
+    db: &DatabaseConnection,
+    user_sub: &str,
+    channel_id: Option<Uuid>,
+    status: Option<DeliveryStatus>,
+    limit: Option<u64>,
+) -> Result<Vec<NotificationDeliveryResponse>, DbErr> {
+    let channel_ids: Vec<Uuid> = NotificationChannel::find()
+        .filter(notification_channels::Column::CreatedBy.eq(user_sub))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+
+    let mut query = NotificationDelivery::find()
+        .filter(notification_deliveries::Column::ChannelId.is_in(channel_ids));
+    if let Some(channel_id) = channel_id {
+        query = query.filter(notification_deliveries::Column::ChannelId.eq(channel_id));
+    }
+    if let Some(status) = status {
+        query = query.filter(notification_deliveries::Column::Status.eq(format!("{:?}", status)));
+    }
+    let models = query
+        .order_by_desc(notification_deliveries::Column::CreatedAt)
+        .limit(limit.unwrap_or(DEFAULT_DELIVERY_LIMIT))
+        .all(db)
+        .await?;
+    Ok(models.into_iter().map(delivery_to_response).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_and_caps() {
+        assert_eq!(retry_delay(1), Duration::seconds(30));
+        assert_eq!(retry_delay(2), Duration::seconds(60));
+        assert_eq!(retry_delay(4), Duration::seconds(240));
+        assert_eq!(retry_delay(20), Duration::seconds(RETRY_MAX_SECONDS));
+    }
+
+    #[test]
+    fn empty_subscription_means_all_events() {
+        assert!(subscribed(&[], NotificationEvent::AlertFiring));
+        assert!(subscribed(&[], NotificationEvent::BrewCompleted));
+    }
+
+    #[test]
+    fn subscription_filters_events_but_not_tests() {
+        let events = [NotificationEvent::AlertFiring];
+        assert!(subscribed(&events, NotificationEvent::AlertFiring));
+        assert!(!subscribed(&events, NotificationEvent::AlertResolved));
+        assert!(subscribed(&events, NotificationEvent::Test));
+    }
+}
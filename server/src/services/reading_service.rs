@@ -1,58 +1,241 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use sea_orm::sea_query::{Expr, Func, OnConflict};
 use sea_orm::*;
 use uuid::Uuid;
 
-use crate::models::entities::hydrometers::Entity as Hydrometer;
+use crate::models::entities::hydrometers::{self, Entity as Hydrometer};
 use crate::models::entities::readings::{self, ActiveModel, Column, Entity as Reading};
-use shared::{ReadingResponse, ReadingsQuery, TiltColor, TiltReading};
+use crate::pagination::{self, Cursor, PageRequest};
+use crate::services::calibration_service::Calibration;
+use crate::tenant::Tenant;
+use shared::{
+    Page, ReadingBucket, ReadingResponse, ReadingsQuery, SignalSample, SortOrder, TiltColor,
+    TiltDeviceKind, TiltReading,
+};
 
-fn model_to_response(model: readings::Model, color: TiltColor) -> ReadingResponse {
+pub fn model_to_response(model: readings::Model, color: TiltColor) -> ReadingResponse {
     ReadingResponse {
         id: model.id,
         brew_id: model.brew_id,
         hydrometer_id: model.hydrometer_id,
         color,
+        device_kind: TiltDeviceKind::parse(&model.device_kind).unwrap_or_default(),
         temperature_f: model.temperature_f,
         gravity: model.gravity,
+        raw_temperature_f: model.raw_temperature_f,
+        raw_gravity: model.raw_gravity,
         rssi: model.rssi,
+        tx_power: model.tx_power,
+        gateway_id: model.gateway_id,
         recorded_at: model.recorded_at.into(),
         created_at: model.created_at.into(),
     }
 }
 
+/// Rebuilds the wire-format reading from a stored row, e.g. for `latest_reading` fields.
+pub fn model_to_tilt_reading(model: &readings::Model, color: TiltColor) -> TiltReading {
+    TiltReading {
+        device_kind: TiltDeviceKind::parse(&model.device_kind).unwrap_or_default(),
+        tx_power: model.tx_power,
+        id: Some(model.id),
+        ..TiltReading::new(
+            color,
+            model.temperature_f,
+            model.gravity,
+            model.rssi,
+            model.recorded_at.into(),
+        )
+    }
+}
+
+/// A batch uploaded by a registered gateway. Readings that other gateways stored within
+/// `window` of one of its readings are the same broadcast heard twice.
+#[derive(Debug, Clone, Copy)]
+pub struct GatewaySource {
+    pub gateway_id: Uuid,
+    pub window: Duration,
+}
+
+/// A reading another gateway stored, as compared by [`collapse_duplicates`].
+#[derive(Debug, Clone, Copy)]
+pub struct Heard {
+    pub id: Uuid,
+    pub recorded_at: DateTime<Utc>,
+    pub rssi: Option<i16>,
+}
+
+/// Compares a gateway's readings with those other gateways stored for the same hydrometer.
+/// An incoming reading within `window` of a stored one is dropped unless its RSSI is
+/// stronger, in which case it replaces every stored reading it overlaps; ties keep the
+/// stored reading. Returns the indices of incoming readings to keep and the ids of stored
+/// readings to delete.
+pub fn collapse_duplicates(
+    incoming: &[TiltReading],
+    stored: &[Heard],
+    window: Duration,
+) -> (Vec<usize>, Vec<Uuid>) {
+    let strength = |rssi: Option<i16>| rssi.unwrap_or(i16::MIN);
+    let mut keep = Vec::new();
+    let mut replaced: Vec<Uuid> = Vec::new();
+    for (i, reading) in incoming.iter().enumerate() {
+        let overlapping: Vec<&Heard> = stored
+            .iter()
+            .filter(|h| !replaced.contains(&h.id))
+            .filter(|h| (h.recorded_at - reading.recorded_at).abs() <= window)
+            .collect();
+        if overlapping
+            .iter()
+            .any(|h| strength(h.rssi) >= strength(reading.rssi))
+        {
+            continue;
+        }
+        replaced.extend(overlapping.iter().map(|h| h.id));
+        keep.push(i);
+    }
+    (keep, replaced)
+}
+
+/// `SELECT ... FOR UPDATE` on the hydrometer, which serializes gateway batches for it.
+fn lock_hydrometer(hydrometer_id: Uuid) -> Select<Hydrometer> {
+    Hydrometer::find_by_id(hydrometer_id).lock_exclusive()
+}
+
+/// Rows written by [`batch_create`].
+pub struct Created {
+    pub inserted: Vec<readings::Model>,
+    /// Readings from other gateways deleted in favour of stronger ones in the batch.
+    pub replaced: u64,
+}
+
+#[derive(Debug)]
+pub enum BatchError {
+    /// A client-supplied id is already used by a reading of another hydrometer.
+    IdConflict,
+    Db(DbErr),
+}
+
+impl From<DbErr> for BatchError {
+    fn from(e: DbErr) -> Self {
+        BatchError::Db(e)
+    }
+}
+
+/// Inserts readings, skipping any whose id or hydrometer + `recorded_at` is already stored
+/// for this hydrometer, so a retried upload is harmless. An id stored for a different
+/// hydrometer fails the whole batch instead. Batches from a gateway are first collapsed
+/// against other gateways' readings with [`collapse_duplicates`]. Readings take the
+/// hydrometer's owner and team. Returns only the rows actually inserted.
 pub async fn batch_create(
     db: &DatabaseConnection,
-    readings: Vec<TiltReading>,
-    hydrometer_id: Uuid,
+    mut readings: Vec<TiltReading>,
+    hydrometer: &hydrometers::Model,
     brew_id: Option<Uuid>,
-) -> Result<u64, DbErr> {
+    calibration: &Calibration,
+    source: Option<GatewaySource>,
+) -> Result<Created, BatchError> {
+    let txn = db.begin().await?;
+    let mut replaced = Vec::new();
+    if let Some(source) = source
+        && let (Some(first), Some(last)) = (
+            readings.iter().map(|r| r.recorded_at).min(),
+            readings.iter().map(|r| r.recorded_at).max(),
+        )
+    {
+        // Held until commit, so two gateways uploading the same broadcast at once take turns
+        // and the second sees the first's readings.
+        lock_hydrometer(hydrometer.id).one(&txn).await?;
+        let since: DateTime<FixedOffset> = (first - source.window).into();
+        let until: DateTime<FixedOffset> = (last + source.window).into();
+        let stored: Vec<Heard> = Reading::find()
+            .filter(Column::HydrometerId.eq(hydrometer.id))
+            .filter(Column::GatewayId.is_not_null())
+            .filter(Column::GatewayId.ne(source.gateway_id))
+            .filter(Column::RecordedAt.between(since, until))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|m| Heard {
+                id: m.id,
+                recorded_at: m.recorded_at.into(),
+                rssi: m.rssi,
+            })
+            .collect();
+        let (keep, ids) = collapse_duplicates(&readings, &stored, source.window);
+        let keep: HashSet<usize> = keep.into_iter().collect();
+        readings = readings
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| keep.contains(i))
+            .map(|(_, r)| r)
+            .collect();
+        replaced = ids;
+    }
     if readings.is_empty() {
-        return Ok(0);
+        return Ok(Created {
+            inserted: Vec::new(),
+            replaced: 0,
+        });
     }
 
+    let client_ids: Vec<Uuid> = readings.iter().filter_map(|r| r.id).collect();
+    let gateway_id = source.map(|s| s.gateway_id);
     let models: Vec<ActiveModel> = readings
         .into_iter()
         .map(|r| ActiveModel {
-            id: Set(Uuid::new_v4()),
+            id: Set(r.id.unwrap_or_else(Uuid::new_v4)),
             brew_id: Set(brew_id),
-            hydrometer_id: Set(hydrometer_id),
-            temperature_f: Set(r.temperature_f),
-            gravity: Set(r.gravity),
+            hydrometer_id: Set(hydrometer.id),
+            temperature_f: Set(calibration.correct_temperature(r.temperature_f)),
+            gravity: Set(calibration.correct_gravity(r.gravity)),
             rssi: Set(r.rssi),
             recorded_at: Set(r.recorded_at.into()),
             created_at: Set(chrono::Utc::now().into()),
+            raw_temperature_f: Set(r.temperature_f),
+            raw_gravity: Set(r.gravity),
+            device_kind: Set(format!("{:?}", r.device_kind)),
+            tx_power: Set(r.tx_power),
+            gateway_id: Set(gateway_id),
+            owner: Set(hydrometer.owner.clone()),
+            team_id: Set(hydrometer.team_id),
         })
         .collect();
 
-    let count = models.len() as u64;
-    Reading::insert_many(models).exec(db).await?;
-    Ok(count)
+    if !client_ids.is_empty() {
+        let taken = Reading::find()
+            .filter(Column::Id.is_in(client_ids))
+            .filter(Column::HydrometerId.ne(hydrometer.id))
+            .count(&txn)
+            .await?;
+        if taken > 0 {
+            return Err(BatchError::IdConflict);
+        }
+    }
+    if !replaced.is_empty() {
+        Reading::delete_many()
+            .filter(Column::Id.is_in(replaced.clone()))
+            .exec(&txn)
+            .await?;
+    }
+    let inserted = Reading::insert_many(models)
+        .on_conflict(OnConflict::new().do_nothing().to_owned())
+        .exec_with_returning_many(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(Created {
+        inserted,
+        replaced: replaced.len() as u64,
+    })
 }
 
-pub async fn find_filtered(
-    db: &DatabaseConnection,
-    query: &ReadingsQuery,
-) -> Result<Vec<ReadingResponse>, DbErr> {
-    let mut select = Reading::find();
+/// Cap on raw rows loaded for `max_points` downsampling, which needs the whole range.
+const DOWNSAMPLE_MAX_ROWS: u64 = 500_000;
+
+fn filtered_select(tenant: &Tenant, query: &ReadingsQuery) -> Select<Reading> {
+    let mut select = Reading::find()
+        .filter(tenant.condition(Column::Owner, Column::TeamId))
+        .filter(tenant.hydrometer_condition(Column::HydrometerId));
 
     if let Some(brew_id) = query.brew_id {
         select = select.filter(Column::BrewId.eq(brew_id));
@@ -68,21 +251,25 @@ pub async fn find_filtered(
         let until_tz: chrono::DateTime<chrono::FixedOffset> = until.into();
         select = select.filter(Column::RecordedAt.lte(until_tz));
     }
+    select
+}
 
-    let limit = query.limit_or_default();
-    let models = select
-        .order_by_desc(Column::RecordedAt)
-        .limit(limit)
-        .all(db)
-        .await?;
-
+async fn to_responses(
+    db: &DatabaseConnection,
+    models: Vec<readings::Model>,
+) -> Result<Vec<ReadingResponse>, DbErr> {
     // Build a hydrometer_id -> TiltColor lookup
-    let hydro_ids: Vec<Uuid> = models.iter().map(|m| m.hydrometer_id).collect::<std::collections::HashSet<_>>().into_iter().collect();
+    let hydro_ids: Vec<Uuid> = models
+        .iter()
+        .map(|m| m.hydrometer_id)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
     let hydrometers = Hydrometer::find()
         .filter(crate::models::entities::hydrometers::Column::Id.is_in(hydro_ids))
         .all(db)
         .await?;
-    let color_map: std::collections::HashMap<Uuid, TiltColor> = hydrometers
+    let color_map: HashMap<Uuid, TiltColor> = hydrometers
         .into_iter()
         .map(|h| (h.id, TiltColor::parse(&h.color).unwrap_or(TiltColor::Red)))
         .collect();
@@ -95,3 +282,385 @@ pub async fn find_filtered(
         })
         .collect())
 }
+
+/// One page of raw readings keyed on `(recorded_at, id)`, with how many match in total.
+pub async fn find_filtered(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    query: &ReadingsQuery,
+    page: &PageRequest,
+) -> Result<Page<ReadingResponse>, DbErr> {
+    let total = filtered_select(tenant, query).count(db).await?;
+    let (items, next_cursor) = next_readings(db, tenant, query, page).await?;
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
+}
+
+/// The readings of one page and the cursor for the next, without counting them all, for
+/// callers that walk every page anyway.
+pub async fn next_readings(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    query: &ReadingsQuery,
+    page: &PageRequest,
+) -> Result<(Vec<ReadingResponse>, Option<String>), DbErr> {
+    let models = page
+        .apply(filtered_select(tenant, query), Column::RecordedAt, Column::Id)
+        .all(db)
+        .await?;
+    let (models, next_cursor) = page.finish(models, |m| Cursor {
+        at: m.recorded_at.into(),
+        id: m.id,
+    });
+    Ok((to_responses(db, models).await?, next_cursor))
+}
+
+#[derive(Debug, FromQueryResult)]
+struct BucketRow {
+    hydrometer_id: Uuid,
+    bucket_start: chrono::DateTime<chrono::FixedOffset>,
+    count: i64,
+    gravity_min: f64,
+    gravity_max: f64,
+    gravity_avg: f64,
+    temperature_min_f: f64,
+    temperature_max_f: f64,
+    temperature_avg_f: f64,
+}
+
+/// Per-hydrometer aggregates over `bucket_seconds`-wide buckets aligned to the Unix epoch,
+/// newest first unless `order` is `asc`. `limit` applies to buckets.
+pub async fn find_bucketed(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    query: &ReadingsQuery,
+    bucket_seconds: i64,
+) -> Result<Vec<ReadingBucket>, DbErr> {
+    let bucket_start = Expr::cust(format!(
+        "to_timestamp(floor(extract(epoch from recorded_at) / {bucket_seconds}) * {bucket_seconds})"
+    ));
+    let order = pagination::sql_order(query.order.unwrap_or_default());
+    let rows = filtered_select(tenant, query)
+        .select_only()
+        .column(Column::HydrometerId)
+        .column_as(bucket_start.clone(), "bucket_start")
+        .column_as(Expr::col(Column::Id).count(), "count")
+        .column_as(Expr::col(Column::Gravity).min(), "gravity_min")
+        .column_as(Expr::col(Column::Gravity).max(), "gravity_max")
+        .column_as(Expr::expr(Func::avg(Expr::col(Column::Gravity))), "gravity_avg")
+        .column_as(Expr::col(Column::TemperatureF).min(), "temperature_min_f")
+        .column_as(Expr::col(Column::TemperatureF).max(), "temperature_max_f")
+        .column_as(Expr::expr(Func::avg(Expr::col(Column::TemperatureF))), "temperature_avg_f")
+        .group_by(Column::HydrometerId)
+        .group_by(bucket_start)
+        .order_by(Expr::cust("bucket_start"), order)
+        .order_by_asc(Column::HydrometerId)
+        .limit(query.limit_or_default())
+        .into_model::<BucketRow>()
+        .all(db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ReadingBucket {
+            hydrometer_id: r.hydrometer_id,
+            bucket_start: r.bucket_start.into(),
+            count: r.count,
+            gravity_min: r.gravity_min,
+            gravity_max: r.gravity_max,
+            gravity_avg: r.gravity_avg,
+            temperature_min_f: r.temperature_min_f,
+            temperature_max_f: r.temperature_max_f,
+            temperature_avg_f: r.temperature_avg_f,
+        })
+        .collect())
+}
+
+/// Largest-Triangle-Three-Buckets: picks `threshold` of the `points` (x ascending) that best
+/// preserve the visual shape of the line. Returns indices into `points`, ascending; always
+/// keeps the first and last point.
+pub fn lttb_indices(points: &[(f64, f64)], threshold: usize) -> Vec<usize> {
+    let n = points.len();
+    if threshold >= n || n <= 2 {
+        return (0..n).collect();
+    }
+    if threshold < 3 {
+        return vec![0, n - 1];
+    }
+
+    let mut selected = Vec::with_capacity(threshold);
+    selected.push(0);
+    // Interior points are split into threshold - 2 buckets of (nearly) equal size.
+    let every = (n - 2) as f64 / (threshold - 2) as f64;
+    let mut a = 0;
+    for i in 0..threshold - 2 {
+        let start = (i as f64 * every) as usize + 1;
+        let end = (((i + 1) as f64 * every) as usize + 1).min(n - 1);
+
+        // The next bucket's average is the third triangle vertex; the last point for the
+        // final bucket.
+        let next_end = (((i + 2) as f64 * every) as usize + 1).min(n);
+        let next = if end < n - 1 {
+            &points[end..next_end.max(end + 1)]
+        } else {
+            &points[n - 1..]
+        };
+        let (avg_x, avg_y) = next.iter().fold((0.0, 0.0), |(x, y), p| (x + p.0, y + p.1));
+        let (avg_x, avg_y) = (avg_x / next.len() as f64, avg_y / next.len() as f64);
+
+        let (ax, ay) = points[a];
+        let best = (start..end.max(start + 1))
+            .max_by(|&l, &r| {
+                let area = |j: usize| {
+                    let (bx, by) = points[j];
+                    ((ax - avg_x) * (by - ay) - (ax - bx) * (avg_y - ay)).abs()
+                };
+                area(l).total_cmp(&area(r))
+            })
+            .unwrap_or(start);
+        selected.push(best);
+        a = best;
+    }
+    selected.push(n - 1);
+    selected
+}
+
+/// Readings downsampled with LTTB on gravity to at most `max_points` per hydrometer,
+/// in `order` (newest first by default) like [`find_filtered`].
+pub async fn find_downsampled(
+    db: &DatabaseConnection,
+    tenant: &Tenant,
+    query: &ReadingsQuery,
+    max_points: usize,
+) -> Result<Vec<ReadingResponse>, DbErr> {
+    let models = filtered_select(tenant, query)
+        .order_by_asc(Column::RecordedAt)
+        .limit(DOWNSAMPLE_MAX_ROWS)
+        .all(db)
+        .await?;
+
+    let mut series: HashMap<Uuid, Vec<readings::Model>> = HashMap::new();
+    for model in models {
+        series.entry(model.hydrometer_id).or_default().push(model);
+    }
+
+    let mut kept = Vec::new();
+    for (_, models) in series {
+        let points: Vec<(f64, f64)> = models
+            .iter()
+            .map(|m| (m.recorded_at.timestamp() as f64, m.gravity))
+            .collect();
+        let indices: HashSet<usize> = lttb_indices(&points, max_points).into_iter().collect();
+        kept.extend(
+            models
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| indices.contains(i))
+                .map(|(_, m)| m),
+        );
+    }
+    match query.order.unwrap_or_default() {
+        SortOrder::Asc => kept.sort_by_key(|m| m.recorded_at),
+        SortOrder::Desc => kept.sort_by_key(|m| std::cmp::Reverse(m.recorded_at)),
+    }
+    to_responses(db, kept).await
+}
+
+/// The newest reading of a hydrometer, with the hydrometer's color and name.
+#[derive(Debug, FromQueryResult)]
+pub struct LatestReading {
+    pub hydrometer_id: Uuid,
+    pub color: String,
+    pub name: Option<String>,
+    pub gravity: f64,
+    pub temperature_f: f64,
+    pub recorded_at: DateTime<FixedOffset>,
+}
+
+/// The newest reading of every hydrometer that has any.
+pub async fn latest_per_hydrometer(db: &DatabaseConnection) -> Result<Vec<LatestReading>, DbErr> {
+    LatestReading::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "SELECT DISTINCT ON (r.hydrometer_id) r.hydrometer_id, h.color, h.name, r.gravity, \
+         r.temperature_f, r.recorded_at \
+         FROM readings r JOIN hydrometers h ON h.id = r.hydrometer_id \
+         ORDER BY r.hydrometer_id, r.recorded_at DESC",
+    ))
+    .all(db)
+    .await
+}
+
+/// RSSI history for one hydrometer, newest first. Readings without an RSSI are skipped.
+pub async fn find_signal_history(
+    db: &DatabaseConnection,
+    hydrometer_id: Uuid,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    limit: u64,
+) -> Result<Vec<SignalSample>, DbErr> {
+    let mut select = Reading::find()
+        .filter(Column::HydrometerId.eq(hydrometer_id))
+        .filter(Column::Rssi.is_not_null());
+    if let Some(since) = since {
+        let since_tz: chrono::DateTime<chrono::FixedOffset> = since.into();
+        select = select.filter(Column::RecordedAt.gte(since_tz));
+    }
+    if let Some(until) = until {
+        let until_tz: chrono::DateTime<chrono::FixedOffset> = until.into();
+        select = select.filter(Column::RecordedAt.lte(until_tz));
+    }
+
+    let models = select
+        .order_by_desc(Column::RecordedAt)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(models
+        .into_iter()
+        .filter_map(|m| {
+            Some(SignalSample {
+                recorded_at: m.recorded_at.into(),
+                rssi: m.rssi?,
+                tx_power: m.tx_power,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(n: usize) -> Vec<(f64, f64)> {
+        (0..n).map(|i| (i as f64, 1.050 - 0.0001 * i as f64)).collect()
+    }
+
+    #[test]
+    fn lttb_keeps_everything_under_threshold() {
+        assert_eq!(lttb_indices(&line(5), 10), vec![0, 1, 2, 3, 4]);
+        assert!(lttb_indices(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn lttb_returns_threshold_points_with_endpoints() {
+        let indices = lttb_indices(&line(1000), 50);
+        assert_eq!(indices.len(), 50);
+        assert_eq!(indices[0], 0);
+        assert_eq!(indices[49], 999);
+        assert!(indices.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn lttb_preserves_spikes() {
+        let mut points = line(300);
+        points[150].1 = 1.100;
+        let indices = lttb_indices(&points, 20);
+        assert!(indices.contains(&150));
+    }
+
+    #[test]
+    fn lttb_tiny_threshold_keeps_endpoints() {
+        assert_eq!(lttb_indices(&line(100), 2), vec![0, 99]);
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_767_225_600 + seconds, 0).unwrap()
+    }
+
+    fn incoming(seconds: i64, rssi: Option<i16>) -> TiltReading {
+        TiltReading::new(TiltColor::Red, 68.0, 1.050, rssi, at(seconds))
+    }
+
+    fn heard(seconds: i64, rssi: Option<i16>) -> Heard {
+        Heard {
+            id: Uuid::new_v4(),
+            recorded_at: at(seconds),
+            rssi,
+        }
+    }
+
+    #[test]
+    fn gateway_batches_lock_the_hydrometer() {
+        let sql = lock_hydrometer(Uuid::nil())
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.ends_with("FOR UPDATE"), "{sql}");
+    }
+
+    #[test]
+    fn interleaved_gateway_uploads_store_the_broadcast_once() {
+        // With the hydrometer locked, two gateways' batches for the same broadcast are
+        // applied one after the other, in either order, each against what the other stored.
+        let window = Duration::seconds(30);
+        let upload = |stored: &mut Vec<Heard>, rssi: i16| {
+            let reading = incoming(1, Some(rssi));
+            let (keep, replaced) = collapse_duplicates(&[reading], stored, window);
+            stored.retain(|h| !replaced.contains(&h.id));
+            if !keep.is_empty() {
+                stored.push(heard(1, Some(rssi)));
+            }
+        };
+        for order in [[-80, -60], [-60, -80]] {
+            let mut stored = Vec::new();
+            for rssi in order {
+                upload(&mut stored, rssi);
+            }
+            assert_eq!(stored.len(), 1);
+            assert_eq!(stored[0].rssi, Some(-60));
+        }
+    }
+
+    #[test]
+    fn collapse_keeps_stronger_reading() {
+        let window = Duration::seconds(30);
+        let weak = heard(0, Some(-90));
+        let (keep, replaced) = collapse_duplicates(&[incoming(5, Some(-60))], &[weak], window);
+        assert_eq!(keep, vec![0]);
+        assert_eq!(replaced, vec![weak.id]);
+
+        let strong = heard(0, Some(-60));
+        let (keep, replaced) =
+            collapse_duplicates(&[incoming(-5, Some(-90))], &[strong], window);
+        assert!(keep.is_empty());
+        assert!(replaced.is_empty());
+    }
+
+    #[test]
+    fn collapse_ties_and_missing_rssi_keep_stored_reading() {
+        let window = Duration::seconds(30);
+        let stored = [heard(0, Some(-70))];
+        assert!(collapse_duplicates(&[incoming(0, Some(-70))], &stored, window).0.is_empty());
+        assert!(collapse_duplicates(&[incoming(0, None)], &stored, window).0.is_empty());
+        let (keep, replaced) =
+            collapse_duplicates(&[incoming(0, Some(-80))], &[heard(0, None)], window);
+        assert_eq!((keep.len(), replaced.len()), (1, 1));
+    }
+
+    #[test]
+    fn collapse_ignores_readings_outside_window() {
+        let stored = [heard(0, Some(-40))];
+        let (keep, replaced) = collapse_duplicates(
+            &[incoming(31, Some(-90)), incoming(-31, Some(-90))],
+            &stored,
+            Duration::seconds(30),
+        );
+        assert_eq!(keep, vec![0, 1]);
+        assert!(replaced.is_empty());
+    }
+
+    #[test]
+    fn collapse_replaced_reading_is_not_compared_again() {
+        let stored = [heard(10, Some(-90))];
+        let (keep, replaced) = collapse_duplicates(
+            &[incoming(0, Some(-60)), incoming(20, Some(-95))],
+            &stored,
+            Duration::seconds(30),
+        );
+        assert_eq!(keep, vec![0, 1]);
+        assert_eq!(replaced.len(), 1);
+    }
+}
@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use chrono::{Duration, Utc};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
@@ -6,6 +8,7 @@ use sea_orm::{
 use uuid::Uuid;
 
 use crate::models::entities::user_sessions::{self, ActiveModel, Entity as UserSession};
+use crate::services::audit_service::{self, Actor, Target};
 
 pub struct CreateSessionParams {
     pub user_sub: String,
@@ -13,6 +16,17 @@ pub struct CreateSessionParams {
     pub name: String,
     pub id_token_hash: String,
     pub expires_in_secs: i64,
+    /// Where the user signed in from, for the audit log.
+    pub ip: Option<IpAddr>,
+}
+
+fn audit_target(id: Uuid, user_sub: &str) -> Target<'_> {
+    Target {
+        kind: "session",
+        id,
+        owner: user_sub,
+        team_id: None,
+    }
 }
 
 pub async fn create_session(
@@ -21,6 +35,10 @@ pub async fn create_session(
 ) -> Result<user_sessions::Model, DbErr> {
     let now = Utc::now().fixed_offset();
     let expires_at = (Utc::now() + Duration::seconds(params.expires_in_secs)).fixed_offset();
+    let actor = Actor {
+        ip: params.ip,
+        ..Actor::user(&params.user_sub)
+    };
 
     let model = ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -33,7 +51,18 @@ pub async fn create_session(
         last_seen_at: Set(now),
     };
 
-    model.insert(db).await
+    let session = model.insert(db).await?;
+    let after = audit_service::snapshot(&session);
+    audit_service::record(
+        db,
+        &actor,
+        "create",
+        audit_target(session.id, &session.user_sub),
+        None,
+        Some(after),
+    )
+    .await;
+    Ok(session)
 }
 
 pub async fn get_session_by_id(
@@ -53,8 +82,20 @@ pub async fn touch_session(db: &DatabaseConnection, id: Uuid) -> Result<(), DbEr
     Ok(())
 }
 
-pub async fn delete_session(db: &DatabaseConnection, id: Uuid) -> Result<(), DbErr> {
-    UserSession::delete_by_id(id).exec(db).await?;
+/// Ends a session, e.g. when its user signs out.
+pub async fn delete_session(db: &DatabaseConnection, actor: &Actor, id: Uuid) -> Result<(), DbErr> {
+    let result = UserSession::delete_by_id(id).exec(db).await?;
+    if result.rows_affected > 0 {
+        audit_service::record(
+            db,
+            actor,
+            "delete",
+            audit_target(id, &actor.user_sub),
+            None,
+            None,
+        )
+        .await;
+    }
     Ok(())
 }
 
@@ -79,6 +120,7 @@ mod tests {
             name: "Test User".to_string(),
             id_token_hash: "abc123".to_string(),
             expires_in_secs: 86400,
+            ip: None,
         };
         assert_eq!(params.user_sub, "sub|123");
         assert_eq!(params.email, "test@example.com");
@@ -0,0 +1,400 @@
+use chrono::{Duration, Utc};
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::guards::current_user::CurrentUser;
+use crate::models::entities::team_invitations::{self, Entity as TeamInvitation};
+use crate::models::entities::team_members::{self, Entity as TeamMember};
+use crate::models::entities::teams::{self, Entity as Team};
+use crate::services::api_keys::generate_api_key;
+use crate::tenant::Membership;
+use shared::{InvitationResponse, TeamMemberResponse, TeamResponse, TeamRole};
+
+/// Settings for team invitations, read from the environment.
+#[derive(Debug, Clone)]
+pub struct TeamConfig {
+    /// How long an invitation link can be used for.
+    pub invitation_ttl: Duration,
+    /// Prepended to `/invite/<token>` to build invitation links.
+    pub link_base: String,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        Self {
+            invitation_ttl: Duration::days(7),
+            link_base: String::new(),
+        }
+    }
+}
+
+impl TeamConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            invitation_ttl: std::env::var("TEAM_INVITATION_TTL_HOURS")
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|h| *h > 0)
+                .map(Duration::hours)
+                .unwrap_or(defaults.invitation_ttl),
+            // The first frontend origin, so links open the web UI rather than the API.
+            link_base: std::env::var("FRONTEND_URL")
+                .ok()
+                .and_then(|v| {
+                    v.split(',')
+                        .next()
+                        .map(|s| s.trim().trim_end_matches('/').to_string())
+                })
+                .unwrap_or(defaults.link_base),
+        }
+    }
+
+    pub fn link(&self, token: &str) -> String {
+        format!("{}/invite/{token}", self.link_base)
+    }
+}
+
+#[derive(Debug)]
+pub enum TeamError {
+    /// The change would leave the team without an owner.
+    LastOwner,
+    /// The invitation doesn't exist, has been used or has expired.
+    InvalidInvitation,
+    Db(DbErr),
+}
+
+impl From<DbErr> for TeamError {
+    fn from(e: DbErr) -> Self {
+        TeamError::Db(e)
+    }
+}
+
+fn role_of(model: &team_members::Model) -> TeamRole {
+    TeamRole::parse(&model.role).unwrap_or(TeamRole::Viewer)
+}
+
+fn member_to_response(model: team_members::Model) -> TeamMemberResponse {
+    TeamMemberResponse {
+        role: role_of(&model),
+        user_sub: model.user_sub,
+        email: model.email,
+        name: model.name,
+        joined_at: model.joined_at.into(),
+    }
+}
+
+fn team_to_response(team: teams::Model, role: TeamRole) -> TeamResponse {
+    TeamResponse {
+        id: team.id,
+        name: team.name,
+        role,
+        created_at: team.created_at.into(),
+    }
+}
+
+/// Owners a team would have after `user_sub` is given `new_role`, or removed if `None`.
+pub fn owners_after(
+    members: &[(String, TeamRole)],
+    user_sub: &str,
+    new_role: Option<TeamRole>,
+) -> usize {
+    members
+        .iter()
+        .map(|(sub, role)| {
+            if sub == user_sub {
+                new_role
+            } else {
+                Some(*role)
+            }
+        })
+        .filter(|role| *role == Some(TeamRole::Owner))
+        .count()
+}
+
+pub async fn memberships_for(
+    db: &DatabaseConnection,
+    user_sub: &str,
+) -> Result<Vec<Membership>, DbErr> {
+    Ok(TeamMember::find()
+        .filter(team_members::Column::UserSub.eq(user_sub))
+        .all(db)
+        .await?
+        .iter()
+        .map(|m| Membership {
+            team_id: m.team_id,
+            role: role_of(m),
+        })
+        .collect())
+}
+
+/// Teams the user belongs to, with their role in each.
+pub async fn list_teams(
+    db: &DatabaseConnection,
+    user: &CurrentUser,
+) -> Result<Vec<TeamResponse>, DbErr> {
+    let teams = Team::find()
+        .filter(teams::Column::Id.is_in(user.memberships.iter().map(|m| m.team_id)))
+        .order_by_asc(teams::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(teams
+        .into_iter()
+        .filter_map(|team| {
+            let role = user.role_in(team.id)?;
+            Some(team_to_response(team, role))
+        })
+        .collect())
+}
+
+/// Creates a team with the user as its owner.
+pub async fn create_team(
+    db: &DatabaseConnection,
+    user: &CurrentUser,
+    name: String,
+) -> Result<TeamResponse, DbErr> {
+    let now = Utc::now().fixed_offset();
+    let txn = db.begin().await?;
+    let team = Team::insert(teams::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(name),
+        created_by: Set(user.user_sub.clone()),
+        created_at: Set(now),
+    })
+    .exec_with_returning(&txn)
+    .await?;
+    add_member(&txn, team.id, user, TeamRole::Owner).await?;
+    txn.commit().await?;
+    Ok(team_to_response(team, TeamRole::Owner))
+}
+
+async fn add_member(
+    db: &impl ConnectionTrait,
+    team_id: Uuid,
+    user: &CurrentUser,
+    role: TeamRole,
+) -> Result<(), DbErr> {
+    TeamMember::insert(team_members::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        team_id: Set(team_id),
+        user_sub: Set(user.user_sub.clone()),
+        email: Set(user.email.clone()),
+        name: Set(user.name.clone()),
+        role: Set(format!("{:?}", role)),
+        joined_at: Set(Utc::now().fixed_offset()),
+    })
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_team(db: &DatabaseConnection, team_id: Uuid) -> Result<bool, DbErr> {
+    let result = Team::delete_by_id(team_id).exec(db).await?;
+    Ok(result.rows_affected > 0)
+}
+
+pub async fn list_members(
+    db: &DatabaseConnection,
+    team_id: Uuid,
+) -> Result<Vec<TeamMemberResponse>, DbErr> {
+    Ok(TeamMember::find()
+        .filter(team_members::Column::TeamId.eq(team_id))
+        .order_by_asc(team_members::Column::JoinedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(member_to_response)
+        .collect())
+}
+
+async fn members_with_roles(
+    db: &impl ConnectionTrait,
+    team_id: Uuid,
+) -> Result<Vec<team_members::Model>, DbErr> {
+    TeamMember::find()
+        .filter(team_members::Column::TeamId.eq(team_id))
+        .all(db)
+        .await
+}
+
+/// The team's members, locked until the transaction ends so two owners demoting or removing
+/// each other at once can't both pass the last-owner check.
+fn lock_members(team_id: Uuid) -> Select<TeamMember> {
+    TeamMember::find()
+        .filter(team_members::Column::TeamId.eq(team_id))
+        .lock_exclusive()
+}
+
+/// The users in a team, e.g. to notify them about a team brew.
+pub async fn member_subs(db: &DatabaseConnection, team_id: Uuid) -> Result<Vec<String>, DbErr> {
+    Ok(members_with_roles(db, team_id)
+        .await?
+        .into_iter()
+        .map(|m| m.user_sub)
+        .collect())
+}
+
+/// Changes a member's role. A team always keeps at least one owner.
+pub async fn update_member(
+    db: &DatabaseConnection,
+    team_id: Uuid,
+    user_sub: &str,
+    role: TeamRole,
+) -> Result<Option<TeamMemberResponse>, TeamError> {
+    let txn = db.begin().await?;
+    let members = lock_members(team_id).all(&txn).await?;
+    let Some(member) = members.iter().find(|m| m.user_sub == user_sub).cloned() else {
+        return Ok(None);
+    };
+    let roles: Vec<(String, TeamRole)> = members
+        .iter()
+        .map(|m| (m.user_sub.clone(), role_of(m)))
+        .collect();
+    if owners_after(&roles, user_sub, Some(role)) == 0 {
+        return Err(TeamError::LastOwner);
+    }
+    let mut active: team_members::ActiveModel = member.into();
+    active.role = Set(format!("{:?}", role));
+    let updated = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(Some(member_to_response(updated)))
+}
+
+/// Removes a member, e.g. when they leave. The last owner can't leave; they delete the
+/// team instead.
+pub async fn remove_member(
+    db: &DatabaseConnection,
+    team_id: Uuid,
+    user_sub: &str,
+) -> Result<bool, TeamError> {
+    let txn = db.begin().await?;
+    let members = lock_members(team_id).all(&txn).await?;
+    let Some(member) = members.iter().find(|m| m.user_sub == user_sub) else {
+        return Ok(false);
+    };
+    let roles: Vec<(String, TeamRole)> = members
+        .iter()
+        .map(|m| (m.user_sub.clone(), role_of(m)))
+        .collect();
+    if owners_after(&roles, user_sub, None) == 0 {
+        return Err(TeamError::LastOwner);
+    }
+    TeamMember::delete_by_id(member.id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(true)
+}
+
+/// A single-use invitation link to join the team as `role`.
+pub async fn create_invitation(
+    db: &DatabaseConnection,
+    config: &TeamConfig,
+    team_id: Uuid,
+    role: TeamRole,
+    created_by: &str,
+) -> Result<InvitationResponse, DbErr> {
+    let (token, hash, _) = generate_api_key();
+    let now = Utc::now();
+    let invitation = TeamInvitation::insert(team_invitations::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        team_id: Set(team_id),
+        token_hash: Set(hash),
+        role: Set(format!("{:?}", role)),
+        created_by: Set(created_by.to_string()),
+        created_at: Set(now.fixed_offset()),
+        expires_at: Set((now + config.invitation_ttl).fixed_offset()),
+        accepted_by: Set(None),
+        accepted_at: Set(None),
+    })
+    .exec_with_returning(db)
+    .await?;
+    Ok(InvitationResponse {
+        id: invitation.id,
+        team_id,
+        role,
+        link: config.link(&token),
+        token,
+        expires_at: invitation.expires_at.into(),
+    })
+}
+
+/// Joins the invitation's team. Existing members keep their current role.
+pub async fn accept_invitation(
+    db: &DatabaseConnection,
+    token: &str,
+    user: &CurrentUser,
+) -> Result<TeamResponse, TeamError> {
+    let hash = format!("{:x}", Sha256::digest(token.as_bytes()));
+    let now = Utc::now().fixed_offset();
+    let txn = db.begin().await?;
+    let invitation = TeamInvitation::find()
+        .filter(team_invitations::Column::TokenHash.eq(hash))
+        .filter(team_invitations::Column::AcceptedAt.is_null())
+        .filter(team_invitations::Column::ExpiresAt.gt(now))
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or(TeamError::InvalidInvitation)?;
+    let team = Team::find_by_id(invitation.team_id)
+        .one(&txn)
+        .await?
+        .ok_or(TeamError::InvalidInvitation)?;
+
+    let role = match user.role_in(team.id) {
+        Some(role) => role,
+        None => {
+            let role = TeamRole::parse(&invitation.role).unwrap_or(TeamRole::Viewer);
+            add_member(&txn, team.id, user, role).await?;
+            role
+        }
+    };
+    let mut active: team_invitations::ActiveModel = invitation.into();
+    active.accepted_by = Set(Some(user.user_sub.clone()));
+    active.accepted_at = Set(Some(now));
+    active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(team_to_response(team, role))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<(String, TeamRole)> {
+        vec![
+            ("alice".to_string(), TeamRole::Owner),
+            ("bob".to_string(), TeamRole::Brewer),
+        ]
+    }
+
+    #[test]
+    fn last_owner_cannot_step_down_or_leave() {
+        assert_eq!(owners_after(&members(), "alice", Some(TeamRole::Brewer)), 0);
+        assert_eq!(owners_after(&members(), "alice", None), 0);
+    }
+
+    #[test]
+    fn other_members_change_freely() {
+        assert_eq!(owners_after(&members(), "bob", None), 1);
+        assert_eq!(owners_after(&members(), "bob", Some(TeamRole::Owner)), 2);
+        assert_eq!(owners_after(&members(), "alice", Some(TeamRole::Owner)), 1);
+    }
+
+    #[test]
+    fn invitation_links_use_the_frontend_origin() {
+        let config = TeamConfig {
+            link_base: "https://tilt.example.com".to_string(),
+            ..TeamConfig::default()
+        };
+        assert_eq!(config.link("abc"), "https://tilt.example.com/invite/abc");
+        assert_eq!(TeamConfig::default().link("abc"), "/invite/abc");
+    }
+
+    #[test]
+    fn owner_checks_lock_the_team_members() {
+        let sql = lock_members(Uuid::nil())
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.ends_with("FOR UPDATE"), "{sql}");
+    }
+}
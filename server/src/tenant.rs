@@ -0,0 +1,234 @@
+//! Who a request acts for. Hydrometers, brews and readings belong to the user who created
+//! them (`owner`, their OIDC subject) and may be shared with a team (`team_id`); every
+//! service query is narrowed to the rows the caller can see, and every change to the rows
+//! they can edit: their own, and those of teams where they are at least a brewer. API keys
+//! may further be limited to some hydrometer colors.
+
+use std::net::IpAddr;
+
+use sea_orm::sea_query::Query;
+use sea_orm::{ColumnTrait, Condition};
+use uuid::Uuid;
+
+use shared::{TeamRole, TiltColor};
+
+use crate::models::entities::{api_keys, hydrometers};
+use crate::services::api_keys::ApiKeyRestrictions;
+use crate::services::audit_service::Actor;
+
+/// A team the caller belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Membership {
+    pub team_id: Uuid,
+    pub role: TeamRole,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    /// Subject of the user that owns what the caller creates.
+    pub owner: String,
+    /// Team that what the caller creates is shared with.
+    pub team_id: Option<Uuid>,
+    /// Teams whose rows the caller can see besides their own.
+    pub team_ids: Vec<Uuid>,
+    /// Teams whose rows the caller can change besides their own.
+    pub editable_team_ids: Vec<Uuid>,
+    /// Teams the caller owns, whose shared settings such as API keys they manage.
+    pub owned_team_ids: Vec<Uuid>,
+    /// Hydrometer colors the caller is limited to, as stored in `hydrometers.color`.
+    pub colors: Option<Vec<String>>,
+    /// Who is making the request, for the audit log.
+    pub actor: Actor,
+}
+
+impl Tenant {
+    pub fn user(user_sub: &str) -> Self {
+        Self {
+            owner: user_sub.to_string(),
+            team_id: None,
+            team_ids: Vec::new(),
+            editable_team_ids: Vec::new(),
+            owned_team_ids: Vec::new(),
+            colors: None,
+            actor: Actor::user(user_sub),
+        }
+    }
+
+    /// A signed-in user acting in `team_id`, one of their `memberships`, or for themselves.
+    pub fn member(user_sub: &str, memberships: &[Membership], team_id: Option<Uuid>) -> Self {
+        Self {
+            owner: user_sub.to_string(),
+            team_id,
+            team_ids: memberships.iter().map(|m| m.team_id).collect(),
+            editable_team_ids: memberships
+                .iter()
+                .filter(|m| m.role >= TeamRole::Brewer)
+                .map(|m| m.team_id)
+                .collect(),
+            owned_team_ids: memberships
+                .iter()
+                .filter(|m| m.role == TeamRole::Owner)
+                .map(|m| m.team_id)
+                .collect(),
+            colors: None,
+            actor: Actor::user(user_sub),
+        }
+    }
+
+    /// Requests made with an API key act for the user who created it and the key's team,
+    /// with the creator's current `team_role` there: a key made by someone since demoted to
+    /// viewer can no longer change team rows.
+    pub fn api_key(key: &api_keys::Model, team_role: Option<TeamRole>) -> Self {
+        let editable = team_role.is_some_and(|role| role >= TeamRole::Brewer);
+        Self {
+            owner: key.created_by.clone(),
+            team_id: key.team_id,
+            team_ids: key.team_id.into_iter().collect(),
+            editable_team_ids: key.team_id.filter(|_| editable).into_iter().collect(),
+            owned_team_ids: Vec::new(),
+            colors: ApiKeyRestrictions::of(key)
+                .allowed_colors
+                .map(|colors| colors.iter().map(|c| format!("{:?}", c)).collect()),
+            actor: Actor {
+                api_key_id: Some(key.id),
+                ..Actor::user(&key.created_by)
+            },
+        }
+    }
+
+    /// Records the client address of the request on the actor.
+    pub fn with_ip(mut self, ip: Option<IpAddr>) -> Self {
+        self.actor.ip = ip;
+        self
+    }
+
+    pub fn allows_color(&self, color: &TiltColor) -> bool {
+        self.colors
+            .as_ref()
+            .is_none_or(|colors| colors.contains(&format!("{:?}", color)))
+    }
+
+    /// Rows whose hydrometer has one of the caller's colors; every row if they aren't limited.
+    pub fn hydrometer_condition(&self, hydrometer_id: impl ColumnTrait) -> Condition {
+        let Some(colors) = &self.colors else {
+            return Condition::all();
+        };
+        Condition::all().add(
+            hydrometer_id.in_subquery(
+                Query::select()
+                    .column(hydrometers::Column::Id)
+                    .from(hydrometers::Entity)
+                    .and_where(hydrometers::Column::Color.is_in(colors.iter().cloned()))
+                    .to_owned(),
+            ),
+        )
+    }
+
+    /// Rows owned by the caller or shared with one of their teams.
+    pub fn condition(&self, owner: impl ColumnTrait, team_id: impl ColumnTrait) -> Condition {
+        self.owned_or_in(owner, team_id, &self.team_ids)
+    }
+
+    /// Rows owned by the caller or shared with a team they can edit in.
+    pub fn write_condition(&self, owner: impl ColumnTrait, team_id: impl ColumnTrait) -> Condition {
+        self.owned_or_in(owner, team_id, &self.editable_team_ids)
+    }
+
+    fn owned_or_in(
+        &self,
+        owner: impl ColumnTrait,
+        team_id: impl ColumnTrait,
+        teams: &[Uuid],
+    ) -> Condition {
+        let condition = Condition::any().add(owner.eq(self.owner.as_str()));
+        if teams.is_empty() {
+            condition
+        } else {
+            condition.add(team_id.is_in(teams.iter().copied()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entities::brews::{Column, Entity as Brew};
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    fn sql(tenant: &Tenant) -> String {
+        Brew::find()
+            .filter(tenant.condition(Column::Owner, Column::TeamId))
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn condition_without_teams_matches_only_the_owner() {
+        let sql = sql(&Tenant::user("alice"));
+        assert!(sql.ends_with(r#"WHERE "brews"."owner" = 'alice'"#), "{sql}");
+    }
+
+    #[test]
+    fn condition_includes_the_callers_teams() {
+        let team = Uuid::nil();
+        let tenant = Tenant::member(
+            "alice",
+            &[Membership {
+                team_id: team,
+                role: TeamRole::Viewer,
+            }],
+            None,
+        );
+        let sql = sql(&tenant);
+        assert!(
+            sql.ends_with(&format!(
+                r#"WHERE "brews"."owner" = 'alice' OR "brews"."team_id" IN ('{team}')"#
+            )),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn viewers_cannot_edit_team_rows() {
+        let (viewing, brewing) = (Uuid::new_v4(), Uuid::new_v4());
+        let tenant = Tenant::member(
+            "alice",
+            &[
+                Membership {
+                    team_id: viewing,
+                    role: TeamRole::Viewer,
+                },
+                Membership {
+                    team_id: brewing,
+                    role: TeamRole::Brewer,
+                },
+            ],
+            Some(brewing),
+        );
+        assert_eq!(tenant.team_ids, vec![viewing, brewing]);
+        assert_eq!(tenant.editable_team_ids, vec![brewing]);
+        assert!(tenant.owned_team_ids.is_empty());
+        assert_eq!(tenant.team_id, Some(brewing));
+    }
+
+    #[test]
+    fn color_limited_tenants_only_see_those_hydrometers() {
+        let tenant = Tenant {
+            colors: Some(vec!["Red".to_string()]),
+            ..Tenant::user("alice")
+        };
+        assert!(tenant.allows_color(&TiltColor::Red));
+        assert!(!tenant.allows_color(&TiltColor::Blue));
+        let sql = Brew::find()
+            .filter(tenant.hydrometer_condition(Column::HydrometerId))
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(
+            sql.ends_with(
+                r#"WHERE "brews"."hydrometer_id" IN (SELECT "id" FROM "hydrometers" WHERE "hydrometers"."color" IN ('Red'))"#
+            ),
+            "{sql}"
+        );
+        assert!(Tenant::user("alice").allows_color(&TiltColor::Blue));
+    }
+}
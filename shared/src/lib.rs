@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub mod tilt_csv;
+
 const TILT_UUID_RED: Uuid = Uuid::from_bytes([
     0xA4, 0x95, 0xBB, 0x10, 0xC5, 0xB1, 0x4B, 0x44, 0xB5, 0x12, 0x13, 0x70, 0xF0, 0x2D, 0x74, 0xDE,
 ]);
@@ -99,6 +101,25 @@ impl TiltColor {
     }
 }
 
+/// Which iBeacon encoding a reading was decoded from. Tilt Pro units broadcast
+/// temperature ×10 and gravity ×10000 instead of whole °F and gravity ×1000.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TiltDeviceKind {
+    #[default]
+    Standard,
+    Pro,
+}
+
+impl TiltDeviceKind {
+    pub fn parse(s: &str) -> Option<TiltDeviceKind> {
+        match s {
+            "Standard" => Some(TiltDeviceKind::Standard),
+            "Pro" => Some(TiltDeviceKind::Pro),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TiltReading {
@@ -107,6 +128,15 @@ pub struct TiltReading {
     pub gravity: f64,
     pub rssi: Option<i16>,
     pub recorded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub device_kind: TiltDeviceKind,
+    /// Calibrated TX power (RSSI at 1 m) from the iBeacon payload.
+    #[serde(default)]
+    pub tx_power: Option<i16>,
+    /// Generated by the client when the reading is taken, so re-sending it after a timeout
+    /// doesn't store it twice. Readings without one are deduplicated on hydrometer and time.
+    #[serde(default)]
+    pub id: Option<Uuid>,
 }
 
 impl TiltReading {
@@ -123,6 +153,9 @@ impl TiltReading {
             gravity,
             rssi,
             recorded_at,
+            device_kind: TiltDeviceKind::Standard,
+            tx_power: None,
+            id: Some(Uuid::new_v4()),
         }
     }
 }
@@ -131,6 +164,23 @@ impl TiltReading {
 #[serde(rename_all = "camelCase")]
 pub struct CreateReadingsBatch(pub Vec<TiltReading>);
 
+/// Response to `POST /readings`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingsBatchResult {
+    /// Same as `inserted`; the original response had only this field and existing
+    /// consumers still read it.
+    #[serde(default)]
+    pub count: u64,
+    pub inserted: u64,
+    /// Readings the server already had, e.g. from a retried upload, or that another gateway
+    /// heard at about the same time with a stronger signal.
+    pub duplicates: u64,
+    /// Weaker readings from other gateways that readings in this batch replaced.
+    #[serde(default)]
+    pub replaced: u64,
+}
+
 impl CreateReadingsBatch {
     pub fn new(readings: Vec<TiltReading>) -> Self {
         Self(readings)
@@ -198,7 +248,41 @@ pub struct BrewResponse {
     pub hydrometer_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When gravity was first detected as terminal, set by the fermentation monitor.
+    pub stable_since: Option<DateTime<Utc>>,
     pub latest_reading: Option<TiltReading>,
+    /// Subject of the user the brew belongs to.
+    #[serde(default)]
+    pub owner: String,
+    /// Team the brew is shared with, if any.
+    pub team_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GravityVelocity {
+    pub window_hours: u32,
+    /// Gravity points (thousandths) dropped per day; negative if gravity rose.
+    pub points_per_day: Option<f64>,
+}
+
+/// Derived fermentation figures for a brew. Attenuation and ABV are percentages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrewAnalytics {
+    pub brew_id: Uuid,
+    pub og: Option<f64>,
+    /// True when `og` was detected from the first stable readings rather than entered.
+    pub og_detected: bool,
+    pub current_gravity: Option<f64>,
+    pub current_temperature_f: Option<f64>,
+    pub apparent_attenuation: Option<f64>,
+    pub real_attenuation: Option<f64>,
+    pub abv: Option<f64>,
+    pub gravity_velocity: Vec<GravityVelocity>,
+    pub hours_since_pitch: Option<f64>,
+    pub reading_count: u64,
+    pub latest_reading_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -226,6 +310,14 @@ pub struct HydrometerResponse {
     pub gravity_offset: f64,
     pub created_at: DateTime<Utc>,
     pub latest_reading: Option<TiltReading>,
+    /// Gateway that heard the latest reading. Near-simultaneous readings from several
+    /// gateways are collapsed to the strongest signal, so this is the best-placed one.
+    pub gateway_id: Option<Uuid>,
+    /// Subject of the user the hydrometer belongs to.
+    #[serde(default)]
+    pub owner: String,
+    /// Team the hydrometer is shared with, if any.
+    pub team_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -235,13 +327,56 @@ pub struct ReadingResponse {
     pub brew_id: Option<Uuid>,
     pub hydrometer_id: Uuid,
     pub color: TiltColor,
+    pub device_kind: TiltDeviceKind,
     pub temperature_f: f64,
     pub gravity: f64,
+    pub raw_temperature_f: f64,
+    pub raw_gravity: f64,
     pub rssi: Option<i16>,
+    pub tx_power: Option<i16>,
+    /// Gateway that uploaded the reading; `None` for readings imported or sent from the web.
+    pub gateway_id: Option<Uuid>,
     pub recorded_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignalSample {
+    pub recorded_at: DateTime<Utc>,
+    pub rssi: i16,
+    pub tx_power: Option<i16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCalibrationPoint {
+    pub raw_gravity: f64,
+    pub actual_gravity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationPointResponse {
+    pub id: Uuid,
+    pub hydrometer_id: Uuid,
+    pub raw_gravity: f64,
+    pub actual_gravity: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Calibration points for a hydrometer together with the fitted correction curve.
+/// Both the curve input and output are in gravity points (`(sg - 1.0) * 1000`):
+/// `coefficients[i]` multiplies the raw reading's points raised to the power `i`,
+/// and the sum is the number of points added to the raw reading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalibrationResponse {
+    pub hydrometer_id: Uuid,
+    pub points: Vec<CalibrationPointResponse>,
+    pub coefficients: Vec<f64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadingsQuery {
@@ -250,12 +385,536 @@ pub struct ReadingsQuery {
     pub since: Option<DateTime<Utc>>,
     pub until: Option<DateTime<Utc>>,
     pub limit: Option<u64>,
+    /// Aggregate into fixed time buckets such as `5m`, `1h` or `1d`; see [`ReadingBucket`].
+    pub bucket: Option<String>,
+    /// Downsample to at most this many readings per hydrometer, keeping the curve's shape.
+    pub max_points: Option<u64>,
+    /// Opaque `next_cursor` from a previous [`Page`] of raw readings.
+    pub cursor: Option<String>,
+    /// Sort by `recorded_at`; newest first when unset.
+    pub order: Option<SortOrder>,
 }
 
 impl ReadingsQuery {
     pub fn limit_or_default(&self) -> u64 {
         self.limit.unwrap_or(10_000)
     }
+
+    /// Bucket width in seconds, or `None` if no bucket was requested or it doesn't parse.
+    pub fn bucket_seconds(&self) -> Option<i64> {
+        self.bucket.as_deref().and_then(parse_bucket)
+    }
+}
+
+/// Parses `<n><unit>` where unit is `s`, `m`, `h` or `d` into seconds.
+pub fn parse_bucket(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let unit = s.chars().last()?;
+    let count: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    (count > 0).then(|| count.checked_mul(multiplier)).flatten()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn parse(s: &str) -> Option<SortOrder> {
+        match s {
+            "asc" => Some(SortOrder::Asc),
+            "desc" => Some(SortOrder::Desc),
+            _ => None,
+        }
+    }
+}
+
+/// One page of a list endpoint. Pass `next_cursor` back as `cursor` to fetch the
+/// following page; it is `None` on the last page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items matching the request's filters across all pages.
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    /// A page with nothing after it.
+    pub fn last(items: Vec<T>) -> Self {
+        Self {
+            total: items.len() as u64,
+            items,
+            next_cursor: None,
+        }
+    }
+}
+
+/// Min/max/avg of one hydrometer's readings within a time bucket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingBucket {
+    pub hydrometer_id: Uuid,
+    pub bucket_start: DateTime<Utc>,
+    pub count: i64,
+    pub gravity_min: f64,
+    pub gravity_max: f64,
+    pub gravity_avg: f64,
+    pub temperature_min_f: f64,
+    pub temperature_max_f: f64,
+    pub temperature_avg_f: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One row per reading, corrected and raw values side by side.
+    Csv,
+    /// The brew followed by one [`ExportRecord`] per line.
+    Jsonl,
+    /// A BeerJSON 1.0 document with the fermentation summarised into daily steps.
+    BeerJson,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<ExportFormat> {
+        match s {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" => Some(ExportFormat::Jsonl),
+            "beerjson" => Some(ExportFormat::BeerJson),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::BeerJson => "beer.json",
+        }
+    }
+}
+
+/// A line of a JSON Lines export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportRecord {
+    Brew(BrewResponse),
+    Reading(ReadingResponse),
+}
+
+/// A log row that couldn't be imported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Outcome of `POST /readings/import`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// Data rows in the file, excluding the header and blank or comment-only rows.
+    pub rows: usize,
+    pub imported: u64,
+    /// Rows skipped because a reading already exists for that hydrometer and time.
+    pub duplicates: u64,
+    /// Names of brews created for beer names that didn't match an existing brew.
+    pub brews_created: Vec<String>,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Payload of a `readings` event on the live stream: one ingested batch for a hydrometer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingsEvent {
+    pub hydrometer_id: Uuid,
+    pub brew_id: Option<Uuid>,
+    pub readings: Vec<ReadingResponse>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlertRuleKind {
+    /// Latest temperature above `threshold` °F.
+    TemperatureAbove,
+    /// Latest temperature below `threshold` °F.
+    TemperatureBelow,
+    /// Gravity moved less than `threshold` points (default 1) over `duration_minutes`
+    /// while still above the target FG.
+    GravityStalled,
+    /// No reading received for `duration_minutes`.
+    NoReading,
+    /// Latest gravity at or below `threshold`, or the brew's target FG if unset.
+    GravityReachedTarget,
+}
+
+impl AlertRuleKind {
+    pub fn parse(s: &str) -> Option<AlertRuleKind> {
+        match s {
+            "TemperatureAbove" => Some(AlertRuleKind::TemperatureAbove),
+            "TemperatureBelow" => Some(AlertRuleKind::TemperatureBelow),
+            "GravityStalled" => Some(AlertRuleKind::GravityStalled),
+            "NoReading" => Some(AlertRuleKind::NoReading),
+            "GravityReachedTarget" => Some(AlertRuleKind::GravityReachedTarget),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// A rule applies to exactly one of `brew_id` or `hydrometer_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAlertRule {
+    pub name: String,
+    pub brew_id: Option<Uuid>,
+    pub hydrometer_id: Option<Uuid>,
+    pub kind: AlertRuleKind,
+    pub threshold: Option<f64>,
+    pub duration_minutes: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAlertRule {
+    pub name: Option<String>,
+    pub threshold: Option<f64>,
+    pub duration_minutes: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRuleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub brew_id: Option<Uuid>,
+    pub hydrometer_id: Option<Uuid>,
+    pub kind: AlertRuleKind,
+    pub threshold: Option<f64>,
+    pub duration_minutes: Option<i32>,
+    pub enabled: bool,
+    pub firing: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertResponse {
+    pub id: Uuid,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub kind: AlertRuleKind,
+    pub brew_id: Option<Uuid>,
+    pub hydrometer_id: Option<Uuid>,
+    pub state: AlertState,
+    pub message: String,
+    pub value: Option<f64>,
+    pub fired_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NotificationEvent {
+    AlertFiring,
+    AlertResolved,
+    BrewCompleted,
+    /// A gateway has stopped sending heartbeats.
+    GatewayOffline,
+    /// A gateway that was offline has sent a heartbeat again.
+    GatewayOnline,
+    /// Sent by the test-send endpoint; always delivered regardless of subscriptions.
+    Test,
+}
+
+impl NotificationEvent {
+    pub fn parse(s: &str) -> Option<NotificationEvent> {
+        match s {
+            "AlertFiring" => Some(NotificationEvent::AlertFiring),
+            "AlertResolved" => Some(NotificationEvent::AlertResolved),
+            "BrewCompleted" => Some(NotificationEvent::BrewCompleted),
+            "GatewayOffline" => Some(NotificationEvent::GatewayOffline),
+            "GatewayOnline" => Some(NotificationEvent::GatewayOnline),
+            "Test" => Some(NotificationEvent::Test),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationChannelKind {
+    Webhook,
+    Ntfy,
+    Email,
+    Slack,
+    Discord,
+}
+
+/// Where and how a channel delivers. Serialized with a `kind` tag, e.g.
+/// `{"kind":"Ntfy","url":"https://ntfy.sh","topic":"brewery"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all_fields = "camelCase")]
+pub enum ChannelConfig {
+    /// JSON POST of the [`Notification`], signed with HMAC-SHA256 when `secret` is set.
+    Webhook { url: String, secret: Option<String> },
+    Ntfy {
+        url: String,
+        topic: String,
+        token: Option<String>,
+        priority: Option<u8>,
+    },
+    /// Sent through the server's SMTP relay.
+    Email { to: String },
+    Slack { url: String },
+    Discord { url: String },
+}
+
+impl ChannelConfig {
+    pub fn kind(&self) -> NotificationChannelKind {
+        match self {
+            ChannelConfig::Webhook { .. } => NotificationChannelKind::Webhook,
+            ChannelConfig::Ntfy { .. } => NotificationChannelKind::Ntfy,
+            ChannelConfig::Email { .. } => NotificationChannelKind::Email,
+            ChannelConfig::Slack { .. } => NotificationChannelKind::Slack,
+            ChannelConfig::Discord { .. } => NotificationChannelKind::Discord,
+        }
+    }
+}
+
+/// Body of every notification; webhooks receive it verbatim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub title: String,
+    pub message: String,
+    pub brew_id: Option<Uuid>,
+    pub hydrometer_id: Option<Uuid>,
+    pub alert: Option<AlertResponse>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// `events` empty means every event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateNotificationChannel {
+    pub name: String,
+    pub config: ChannelConfig,
+    #[serde(default)]
+    pub events: Vec<NotificationEvent>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotificationChannel {
+    pub name: Option<String>,
+    pub config: Option<ChannelConfig>,
+    pub events: Option<Vec<NotificationEvent>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationChannelResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    pub config: ChannelConfig,
+    pub events: Vec<NotificationEvent>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn parse(s: &str) -> Option<DeliveryStatus> {
+        match s {
+            "Pending" => Some(DeliveryStatus::Pending),
+            "Delivered" => Some(DeliveryStatus::Delivered),
+            "Failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDeliveryResponse {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+    pub event: NotificationEvent,
+    pub title: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Results of a gateway's most recent BLE scan window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStats {
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub readings: u32,
+    pub colors: Vec<TiltColor>,
+}
+
+/// Body of `POST /gateways/heartbeat`, sent periodically by each client. The gateway is
+/// identified by the API key the request is made with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayHeartbeat {
+    pub version: String,
+    pub hostname: Option<String>,
+    pub adapter: Option<String>,
+    /// Readings waiting in the client's buffer for this server.
+    #[serde(default)]
+    pub buffered_readings: u64,
+    pub last_scan: Option<ScanStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GatewayStatus {
+    Online,
+    Offline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayResponse {
+    pub id: Uuid,
+    pub api_key_id: Uuid,
+    /// Name of the gateway's API key.
+    pub name: String,
+    pub api_key_prefix: String,
+    pub version: String,
+    pub hostname: Option<String>,
+    pub adapter: Option<String>,
+    pub buffered_readings: u64,
+    pub last_scan: Option<ScanStats>,
+    pub last_ip: Option<String>,
+    pub status: GatewayStatus,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+/// What a team member may do, in increasing order of privilege: viewers see the team's
+/// dashboards, brewers also edit its brews, hydrometers and API keys, and owners also
+/// manage the team and its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TeamRole {
+    Viewer,
+    Brewer,
+    Owner,
+}
+
+impl TeamRole {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Viewer" => Some(TeamRole::Viewer),
+            "Brewer" => Some(TeamRole::Brewer),
+            "Owner" => Some(TeamRole::Owner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTeam {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// The caller's role in the team.
+    pub role: TeamRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMemberResponse {
+    pub user_sub: String,
+    pub email: String,
+    pub name: String,
+    pub role: TeamRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTeamMember {
+    pub role: TeamRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInvitation {
+    pub role: TeamRole,
+}
+
+/// A single-use link that adds whoever opens it to the team with `role`. The token is only
+/// returned when the invitation is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitationResponse {
+    pub id: Uuid,
+    pub team_id: Uuid,
+    pub role: TeamRole,
+    pub token: String,
+    pub link: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A change to a brew, hydrometer, API key or session. `changes` maps each changed field to
+/// its `before` and `after` values; secrets are redacted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    /// Subject of the user who acted, or who created the API key used.
+    pub actor: String,
+    pub api_key_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub changes: serde_json::Value,
+    pub ip: Option<String>,
 }
 
 #[cfg(test)]
@@ -356,6 +1015,49 @@ mod tests {
         assert!((deserialized.temperature_f - reading.temperature_f).abs() < f64::EPSILON);
         assert!((deserialized.gravity - reading.gravity).abs() < f64::EPSILON);
         assert_eq!(deserialized.rssi, reading.rssi);
+        assert_eq!(deserialized.device_kind, reading.device_kind);
+    }
+
+    #[test]
+    fn tilt_reading_device_kind_defaults_to_standard() {
+        let json = r#"{"color":"Red","temperatureF":68.0,"gravity":1.05,"rssi":null,"recordedAt":"2026-01-01T00:00:00Z"}"#;
+        let reading: TiltReading = serde_json::from_str(json).unwrap();
+        assert_eq!(reading.device_kind, TiltDeviceKind::Standard);
+        assert!(reading.tx_power.is_none());
+        assert!(reading.id.is_none());
+    }
+
+    #[test]
+    fn tilt_reading_new_generates_stable_id() {
+        let reading = TiltReading::new(TiltColor::Red, 68.0, 1.050, None, Utc::now());
+        let id = reading.id.expect("new readings carry an id");
+        let resent: TiltReading =
+            serde_json::from_str(&serde_json::to_string(&reading).unwrap()).unwrap();
+        assert_eq!(resent.id, Some(id));
+        assert_ne!(
+            TiltReading::new(TiltColor::Red, 68.0, 1.050, None, Utc::now()).id,
+            Some(id)
+        );
+    }
+
+    #[test]
+    fn signal_sample_serializes_camel_case() {
+        let sample = SignalSample {
+            recorded_at: Utc::now(),
+            rssi: -72,
+            tx_power: Some(-59),
+        };
+        let json = serde_json::to_string(&sample).unwrap();
+        assert!(json.contains("\"recordedAt\""));
+        assert!(json.contains("\"txPower\":-59"));
+    }
+
+    #[test]
+    fn tilt_device_kind_parse_round_trip() {
+        for kind in [TiltDeviceKind::Standard, TiltDeviceKind::Pro] {
+            assert_eq!(TiltDeviceKind::parse(&format!("{:?}", kind)), Some(kind));
+        }
+        assert_eq!(TiltDeviceKind::parse("Mini"), None);
     }
 
     #[test]
@@ -478,7 +1180,10 @@ mod tests {
             hydrometer_id: Uuid::new_v4(),
             created_at: now,
             updated_at: now,
+            stable_since: None,
             latest_reading: None,
+            owner: "user-1".to_string(),
+            team_id: None,
         };
         let json = serde_json::to_string(&resp).unwrap();
         assert!(json.contains("\"latestReading\""));
@@ -488,6 +1193,32 @@ mod tests {
         assert_eq!(deserialized.status, BrewStatus::Active);
     }
 
+    #[test]
+    fn brew_analytics_serializes_camel_case() {
+        let analytics = BrewAnalytics {
+            brew_id: Uuid::new_v4(),
+            og: Some(1.050),
+            og_detected: true,
+            current_gravity: Some(1.010),
+            current_temperature_f: Some(66.0),
+            apparent_attenuation: Some(80.0),
+            real_attenuation: Some(65.5),
+            abv: Some(5.25),
+            gravity_velocity: vec![GravityVelocity {
+                window_hours: 24,
+                points_per_day: Some(8.0),
+            }],
+            hours_since_pitch: Some(96.0),
+            reading_count: 100,
+            latest_reading_at: None,
+        };
+        let json = serde_json::to_string(&analytics).unwrap();
+        assert!(json.contains("\"ogDetected\":true"));
+        assert!(json.contains("\"apparentAttenuation\""));
+        assert!(json.contains("\"windowHours\":24"));
+        assert!(json.contains("\"pointsPerDay\""));
+    }
+
     #[test]
     fn create_hydrometer_required_and_optional() {
         let json = r#"{"color":"Red"}"#;
@@ -535,6 +1266,9 @@ mod tests {
             gravity_offset: 0.0,
             created_at: now,
             latest_reading: None,
+            gateway_id: None,
+            owner: "user-1".to_string(),
+            team_id: None,
         };
         let json = serde_json::to_string(&resp).unwrap();
         assert!(json.contains("\"tempOffsetF\""));
@@ -553,9 +1287,14 @@ mod tests {
             brew_id: Some(Uuid::new_v4()),
             hydrometer_id: Uuid::new_v4(),
             color: TiltColor::Orange,
+            device_kind: TiltDeviceKind::Pro,
             temperature_f: 68.0,
             gravity: 1.050,
+            raw_temperature_f: 67.0,
+            raw_gravity: 1.052,
             rssi: Some(-59),
+            tx_power: Some(-59),
+            gateway_id: Some(Uuid::new_v4()),
             recorded_at: now,
             created_at: now,
         };
@@ -564,9 +1303,12 @@ mod tests {
         assert!(json.contains("\"hydrometerId\""));
         assert!(json.contains("\"temperatureF\""));
         assert!(json.contains("\"recordedAt\""));
+        assert!(json.contains("\"rawTemperatureF\""));
+        assert!(json.contains("\"rawGravity\""));
         let deserialized: ReadingResponse = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.color, TiltColor::Orange);
         assert!((deserialized.gravity - 1.050).abs() < f64::EPSILON);
+        assert!((deserialized.raw_gravity - 1.052).abs() < f64::EPSILON);
     }
 
     #[test]
@@ -577,9 +1319,14 @@ mod tests {
             brew_id: None,
             hydrometer_id: Uuid::new_v4(),
             color: TiltColor::Black,
+            device_kind: TiltDeviceKind::Standard,
             temperature_f: 72.0,
             gravity: 1.030,
+            raw_temperature_f: 72.0,
+            raw_gravity: 1.030,
             rssi: None,
+            tx_power: None,
+            gateway_id: None,
             recorded_at: now,
             created_at: now,
         };
@@ -587,6 +1334,136 @@ mod tests {
         let deserialized: ReadingResponse = serde_json::from_str(&json).unwrap();
         assert!(deserialized.brew_id.is_none());
         assert!(deserialized.rssi.is_none());
+        assert!(deserialized.tx_power.is_none());
+    }
+
+    #[test]
+    fn create_calibration_point_camel_case_fields() {
+        let json = r#"{"rawGravity":1.052,"actualGravity":1.050}"#;
+        let point: CreateCalibrationPoint = serde_json::from_str(json).unwrap();
+        assert!((point.raw_gravity - 1.052).abs() < f64::EPSILON);
+        assert!((point.actual_gravity - 1.050).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn calibration_response_serde_round_trip() {
+        let hydrometer_id = Uuid::new_v4();
+        let resp = CalibrationResponse {
+            hydrometer_id,
+            points: vec![CalibrationPointResponse {
+                id: Uuid::new_v4(),
+                hydrometer_id,
+                raw_gravity: 1.002,
+                actual_gravity: 1.000,
+                created_at: Utc::now(),
+            }],
+            coefficients: vec![-2.0],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"hydrometerId\""));
+        assert!(json.contains("\"actualGravity\""));
+        let deserialized: CalibrationResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.points.len(), 1);
+        assert_eq!(deserialized.coefficients, vec![-2.0]);
+    }
+
+    #[test]
+    fn alert_rule_kind_parse_round_trip() {
+        for kind in [
+            AlertRuleKind::TemperatureAbove,
+            AlertRuleKind::TemperatureBelow,
+            AlertRuleKind::GravityStalled,
+            AlertRuleKind::NoReading,
+            AlertRuleKind::GravityReachedTarget,
+        ] {
+            assert_eq!(AlertRuleKind::parse(&format!("{:?}", kind)), Some(kind));
+        }
+        assert_eq!(AlertRuleKind::parse("Unknown"), None);
+    }
+
+    #[test]
+    fn create_alert_rule_optional_fields() {
+        let json = r#"{"name":"Too warm","hydrometerId":"a495bb10-c5b1-4b44-b512-1370f02d74de","kind":"TemperatureAbove","threshold":72.0}"#;
+        let rule: CreateAlertRule = serde_json::from_str(json).unwrap();
+        assert_eq!(rule.kind, AlertRuleKind::TemperatureAbove);
+        assert!(rule.brew_id.is_none());
+        assert!(rule.duration_minutes.is_none());
+        assert!(rule.enabled.is_none());
+    }
+
+    #[test]
+    fn alert_response_serializes_camel_case() {
+        let alert = AlertResponse {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            rule_name: "Too warm".to_string(),
+            kind: AlertRuleKind::TemperatureAbove,
+            brew_id: None,
+            hydrometer_id: Some(Uuid::new_v4()),
+            state: AlertState::Firing,
+            message: "Temperature 74.0°F is above 72.0°F".to_string(),
+            value: Some(74.0),
+            fired_at: Utc::now(),
+            resolved_at: None,
+        };
+        let json = serde_json::to_string(&alert).unwrap();
+        assert!(json.contains("\"ruleId\""));
+        assert!(json.contains("\"firedAt\""));
+        assert!(json.contains("\"state\":\"Firing\""));
+    }
+
+    #[test]
+    fn channel_config_tagged_by_kind() {
+        let json = r#"{"kind":"Ntfy","url":"https://ntfy.sh","topic":"brewery"}"#;
+        let config: ChannelConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.kind(), NotificationChannelKind::Ntfy);
+        assert_eq!(
+            config,
+            ChannelConfig::Ntfy {
+                url: "https://ntfy.sh".to_string(),
+                topic: "brewery".to_string(),
+                token: None,
+                priority: None,
+            }
+        );
+
+        let webhook = ChannelConfig::Webhook {
+            url: "https://example.com/hook".to_string(),
+            secret: Some("s3cret".to_string()),
+        };
+        let json = serde_json::to_string(&webhook).unwrap();
+        assert!(json.contains("\"kind\":\"Webhook\""));
+        assert!(json.contains("\"secret\":\"s3cret\""));
+    }
+
+    #[test]
+    fn create_notification_channel_events_default_empty() {
+        let json = r#"{"name":"Phone","config":{"kind":"Email","to":"brewer@example.com"}}"#;
+        let input: CreateNotificationChannel = serde_json::from_str(json).unwrap();
+        assert!(input.events.is_empty());
+        assert_eq!(input.config.kind(), NotificationChannelKind::Email);
+    }
+
+    #[test]
+    fn notification_event_and_delivery_status_parse() {
+        for event in [
+            NotificationEvent::AlertFiring,
+            NotificationEvent::AlertResolved,
+            NotificationEvent::BrewCompleted,
+            NotificationEvent::GatewayOffline,
+            NotificationEvent::GatewayOnline,
+            NotificationEvent::Test,
+        ] {
+            assert_eq!(NotificationEvent::parse(&format!("{:?}", event)), Some(event));
+        }
+        for status in [
+            DeliveryStatus::Pending,
+            DeliveryStatus::Delivered,
+            DeliveryStatus::Failed,
+        ] {
+            assert_eq!(DeliveryStatus::parse(&format!("{:?}", status)), Some(status));
+        }
+        assert_eq!(DeliveryStatus::parse("Queued"), None);
     }
 
     #[test]
@@ -597,6 +1474,59 @@ mod tests {
         assert!(query.since.is_none());
         assert!(query.until.is_none());
         assert!(query.limit.is_none());
+        assert!(query.bucket.is_none());
+        assert!(query.max_points.is_none());
+        assert!(query.cursor.is_none());
+        assert!(query.order.is_none());
+    }
+
+    #[test]
+    fn sort_order_serializes_lowercase() {
+        assert_eq!(serde_json::to_string(&SortOrder::Asc).unwrap(), "\"asc\"");
+        assert_eq!(SortOrder::parse("desc"), Some(SortOrder::Desc));
+        assert_eq!(SortOrder::parse("Desc"), None);
+        assert_eq!(SortOrder::default(), SortOrder::Desc);
+    }
+
+    #[test]
+    fn page_serializes_next_cursor_camel_case() {
+        let page = Page {
+            items: vec![1, 2],
+            next_cursor: Some("abc".to_string()),
+            total: 5,
+        };
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["items"], serde_json::json!([1, 2]));
+        assert_eq!(json["nextCursor"], "abc");
+        assert_eq!(json["total"], 5);
+        assert!(Page::last(vec![1]).next_cursor.is_none());
+        assert_eq!(Page::last(vec![1, 2, 3]).total, 3);
+    }
+
+    #[test]
+    fn export_format_parse_and_extension() {
+        assert_eq!(ExportFormat::parse("beerjson"), Some(ExportFormat::BeerJson));
+        assert_eq!(ExportFormat::parse("CSV"), None);
+        assert_eq!(ExportFormat::Jsonl.extension(), "jsonl");
+    }
+
+    #[test]
+    fn parse_bucket_units() {
+        assert_eq!(parse_bucket("30s"), Some(30));
+        assert_eq!(parse_bucket("5m"), Some(300));
+        assert_eq!(parse_bucket("1h"), Some(3600));
+        assert_eq!(parse_bucket("1d"), Some(86_400));
+        assert_eq!(parse_bucket("0h"), None);
+        assert_eq!(parse_bucket("-5m"), None);
+        assert_eq!(parse_bucket("h"), None);
+        assert_eq!(parse_bucket("5w"), None);
+        assert_eq!(parse_bucket(""), None);
+    }
+
+    #[test]
+    fn readings_query_bucket_seconds() {
+        let query: ReadingsQuery = serde_json::from_str(r#"{"bucket":"15m"}"#).unwrap();
+        assert_eq!(query.bucket_seconds(), Some(900));
     }
 
     #[test]
@@ -611,4 +1541,29 @@ mod tests {
         let query: ReadingsQuery = serde_json::from_str(json).unwrap();
         assert_eq!(query.limit_or_default(), 50);
     }
+
+    #[test]
+    fn readings_batch_result_keeps_count_field() {
+        let result = ReadingsBatchResult {
+            count: 3,
+            inserted: 3,
+            duplicates: 1,
+            replaced: 0,
+        };
+        let json = serde_json::to_value(result).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "count": 3, "inserted": 3, "duplicates": 1, "replaced": 0 })
+        );
+    }
+
+    #[test]
+    fn team_roles_are_ordered_by_privilege() {
+        assert!(TeamRole::Viewer < TeamRole::Brewer);
+        assert!(TeamRole::Brewer < TeamRole::Owner);
+        for role in [TeamRole::Viewer, TeamRole::Brewer, TeamRole::Owner] {
+            assert_eq!(TeamRole::parse(&format!("{:?}", role)), Some(role));
+        }
+        assert_eq!(TeamRole::parse("Admin"), None);
+    }
 }
@@ -0,0 +1,331 @@
+//! Parser for the CSV logs written by the Tilt apps, TiltPi and their Google Sheets
+//! exports. Columns are found by header name, so the different layouts (and extra columns
+//! such as `Comment`) all parse:
+//!
+//! - Tilt app / Sheets: `Timepoint,Temp,SG,Beer,Color,Comment` (`Timepoint` may be a
+//!   spreadsheet serial date)
+//! - TiltPi: `Timestamp,Time,SG,Temp,Color,Beer,Comment`
+//!
+//! Temperatures are taken as °F unless the header says °C (e.g. `Temp (C)`).
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+use crate::{ImportRowError, TiltColor};
+
+const TIMESTAMP_HEADERS: [&str; 5] = ["timepoint", "timestamp", "datetime", "date", "time"];
+const GRAVITY_HEADERS: [&str; 3] = ["sg", "gravity", "specific gravity"];
+const TEMPERATURE_HEADERS: [&str; 2] = ["temp", "temperature"];
+const COLOR_HEADERS: [&str; 2] = ["color", "colour"];
+const BEER_HEADERS: [&str; 3] = ["beer", "beer name", "name"];
+
+const NAIVE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogOptions {
+    /// Color for logs without a color column, e.g. a per-Tilt TiltPi file.
+    pub color: Option<TiltColor>,
+    /// Offset of timestamps that carry no zone of their own.
+    pub utc_offset: FixedOffset,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self {
+            color: None,
+            utc_offset: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRow {
+    /// 1-based line in the file, for error reports.
+    pub line: usize,
+    pub recorded_at: DateTime<Utc>,
+    pub gravity: f64,
+    pub temperature_f: f64,
+    pub color: TiltColor,
+    pub beer: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedLog {
+    pub rows: Vec<LogRow>,
+    pub errors: Vec<ImportRowError>,
+}
+
+struct Columns {
+    timestamp: usize,
+    gravity: usize,
+    temperature: usize,
+    celsius: bool,
+    color: Option<usize>,
+    beer: Option<usize>,
+}
+
+/// Parses a whole log. Fails only when the header is missing a required column; bad rows
+/// are reported in [`ParsedLog::errors`] and skipped. Blank rows (and the comment-only rows
+/// the Tilt app writes) are ignored.
+pub fn parse(text: &str, options: &LogOptions) -> Result<ParsedLog, String> {
+    let mut records = records(text).into_iter();
+    let (_, header) = records.next().ok_or("file is empty")?;
+    let columns = find_columns(&header)?;
+    if columns.color.is_none() && options.color.is_none() {
+        return Err("no color column; pass a color for this log".to_string());
+    }
+
+    let mut log = ParsedLog::default();
+    for (line, fields) in records {
+        let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or_default();
+        if field(columns.gravity).is_empty() && field(columns.temperature).is_empty() {
+            continue;
+        }
+        match parse_row(&fields, &columns, options) {
+            Ok((recorded_at, gravity, temperature_f, color, beer)) => log.rows.push(LogRow {
+                line,
+                recorded_at,
+                gravity,
+                temperature_f,
+                color,
+                beer,
+            }),
+            Err(message) => log.errors.push(ImportRowError { line, message }),
+        }
+    }
+    Ok(log)
+}
+
+type Row = (DateTime<Utc>, f64, f64, TiltColor, Option<String>);
+
+fn parse_row(fields: &[String], columns: &Columns, options: &LogOptions) -> Result<Row, String> {
+    let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or_default();
+
+    let raw_timestamp = field(columns.timestamp);
+    let recorded_at = parse_timestamp(raw_timestamp, options.utc_offset)
+        .ok_or_else(|| format!("unrecognised timestamp '{raw_timestamp}'"))?;
+
+    let raw_gravity = field(columns.gravity);
+    let mut gravity: f64 = raw_gravity
+        .parse()
+        .map_err(|_| format!("invalid gravity '{raw_gravity}'"))?;
+    // Some logs store SG × 1000 (e.g. 1050).
+    if gravity > 100.0 {
+        gravity /= 1000.0;
+    }
+    if !(0.95..=1.2).contains(&gravity) {
+        return Err(format!("gravity {gravity} out of range"));
+    }
+
+    let raw_temperature = field(columns.temperature);
+    let temperature: f64 = raw_temperature
+        .parse()
+        .map_err(|_| format!("invalid temperature '{raw_temperature}'"))?;
+    let temperature_f = if columns.celsius {
+        temperature * 9.0 / 5.0 + 32.0
+    } else {
+        temperature
+    };
+
+    let color = match columns.color.map(field).filter(|c| !c.is_empty()) {
+        Some(name) => parse_color(name).ok_or_else(|| format!("unknown color '{name}'"))?,
+        None => options.color.ok_or("missing color")?,
+    };
+    let beer = columns
+        .beer
+        .map(field)
+        .filter(|b| !b.is_empty())
+        .map(str::to_string);
+
+    Ok((recorded_at, gravity, temperature_f, color, beer))
+}
+
+fn find_columns(header: &[String]) -> Result<Columns, String> {
+    let names: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    // Strip a unit suffix such as "temp (°f)" or "temperature [c]".
+    let base = |name: &str| -> String {
+        name.split(['(', '['])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    let find = |candidates: &[&str]| {
+        candidates
+            .iter()
+            .find_map(|c| names.iter().position(|n| base(n) == *c))
+    };
+
+    let temperature = find(&TEMPERATURE_HEADERS).ok_or("no temperature column")?;
+    let unit = &names[temperature][base(&names[temperature]).len()..];
+    Ok(Columns {
+        timestamp: find(&TIMESTAMP_HEADERS).ok_or("no timestamp column")?,
+        gravity: find(&GRAVITY_HEADERS).ok_or("no SG column")?,
+        temperature,
+        celsius: unit.contains('c'),
+        color: find(&COLOR_HEADERS),
+        beer: find(&BEER_HEADERS),
+    })
+}
+
+fn parse_color(name: &str) -> Option<TiltColor> {
+    TiltColor::all()
+        .iter()
+        .find(|c| format!("{c:?}").eq_ignore_ascii_case(name))
+        .copied()
+}
+
+/// RFC 3339, common naive date-time layouts (read in `offset`), or a spreadsheet serial
+/// date (days since 1899-12-30, also in `offset`).
+pub fn parse_timestamp(s: &str, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Some(at.with_timezone(&Utc));
+    }
+    if let Ok(serial) = s.parse::<f64>() {
+        // Plausible serials cover roughly 1955 to 2118.
+        if !(20_000.0..80_000.0).contains(&serial) {
+            return None;
+        }
+        let epoch = NaiveDateTime::parse_from_str("1899-12-30 00:00:00", NAIVE_FORMATS[0]).ok()?;
+        let naive = epoch + Duration::milliseconds((serial * 86_400_000.0).round() as i64);
+        return offset.from_local_datetime(&naive).single().map(|at| at.with_timezone(&Utc));
+    }
+    NAIVE_FORMATS.iter().find_map(|format| {
+        let naive = NaiveDateTime::parse_from_str(s, format).ok()?;
+        offset.from_local_datetime(&naive).single().map(|at| at.with_timezone(&Utc))
+    })
+}
+
+/// Splits CSV text into records of fields (RFC 4180: quoted fields may contain commas,
+/// doubled quotes and line breaks), each with the line it starts on. Blank lines are dropped.
+fn records(text: &str) -> Vec<(usize, Vec<String>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                if fields.iter().any(|f| !f.trim().is_empty()) {
+                    records.push((start_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                start_line = line;
+            }
+            '\n' => {
+                field.push(c);
+                line += 1;
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    if fields.iter().any(|f| !f.trim().is_empty()) {
+        records.push((start_line, fields));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn records_handle_quotes_and_line_breaks() {
+        let parsed = records("a,\"b,c\",\"say \"\"hi\"\"\"\r\n\n1,\"two\nlines\",3\n");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0], (1, vec!["a".into(), "b,c".into(), "say \"hi\"".into()]));
+        assert_eq!(parsed[1].0, 3);
+        assert_eq!(parsed[1].1[1], "two\nlines");
+    }
+
+    #[test]
+    fn parses_tilt_app_sheet_with_serial_dates() {
+        let text = "Timepoint,Temp,SG,Beer,Color,Comment\n\
+                    46023.5,68.0,1.050,Pale Ale,RED,\n\
+                    46023.75,67.5,1.048,Pale Ale,RED,\n\
+                    46023.8,,,Pale Ale,RED,Dry hopped\n";
+        let log = parse(text, &LogOptions::default()).unwrap();
+        assert!(log.errors.is_empty(), "{:?}", log.errors);
+        assert_eq!(log.rows.len(), 2);
+        assert_eq!(log.rows[0].recorded_at, utc("2026-01-01T12:00:00Z"));
+        assert_eq!(log.rows[0].color, TiltColor::Red);
+        assert_eq!(log.rows[1].beer.as_deref(), Some("Pale Ale"));
+        assert_eq!(log.rows[1].line, 3);
+    }
+
+    #[test]
+    fn parses_tiltpi_layout_with_offset_and_celsius() {
+        let text = "Timestamp,Time,SG,Temp (C),Color,Beer,Comment\n\
+                    2026-01-01 08:00:00,08:00,1050,20,Blue,,\n";
+        let options = LogOptions {
+            color: None,
+            utc_offset: FixedOffset::east_opt(-5 * 3600).unwrap(),
+        };
+        let log = parse(text, &options).unwrap();
+        let row = &log.rows[0];
+        assert_eq!(row.recorded_at, utc("2026-01-01T13:00:00Z"));
+        assert!((row.gravity - 1.050).abs() < 1e-9);
+        assert!((row.temperature_f - 68.0).abs() < 1e-9);
+        assert_eq!(row.color, TiltColor::Blue);
+        assert!(row.beer.is_none());
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_line_numbers() {
+        let text = "Timestamp,SG,Temp,Color\n\
+                    2026-01-01T00:00:00Z,1.050,68,Red\n\
+                    yesterday,1.050,68,Red\n\
+                    2026-01-01T01:00:00Z,1.050,68,Teal\n\
+                    2026-01-01T02:00:00Z,9.0,68,Red\n";
+        let log = parse(text, &LogOptions::default()).unwrap();
+        assert_eq!(log.rows.len(), 1);
+        let lines: Vec<usize> = log.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert!(log.errors[1].message.contains("Teal"));
+    }
+
+    #[test]
+    fn color_falls_back_to_option() {
+        let text = "Time,SG,Temp\n2026-01-01 00:00,1.040,65\n";
+        assert!(parse(text, &LogOptions::default()).is_err());
+        let options = LogOptions {
+            color: Some(TiltColor::Pink),
+            ..LogOptions::default()
+        };
+        assert_eq!(parse(text, &options).unwrap().rows[0].color, TiltColor::Pink);
+    }
+
+    #[test]
+    fn missing_required_column_is_an_error() {
+        assert_eq!(
+            parse("Timestamp,Temp,Color\n", &LogOptions::default()).unwrap_err(),
+            "no SG column"
+        );
+        assert!(parse("", &LogOptions::default()).is_err());
+    }
+}
@@ -4,14 +4,16 @@ import type {
   BrewResponse,
   BrewStatus,
   CreateBrew,
+  Page,
   UpdateBrew,
 } from "@/types";
 
 export function useBrews(status?: BrewStatus, options?: { refetchInterval?: number }) {
   const params = status ? `?status=${status}` : "";
-  return useQuery<BrewResponse[]>({
+  return useQuery({
     queryKey: ["brews", status],
-    queryFn: () => apiGet<BrewResponse[]>(`/brews${params}`),
+    queryFn: () => apiGet<Page<BrewResponse>>(`/brews${params}`),
+    select: (page) => page.items,
     refetchInterval: options?.refetchInterval,
   });
 }
@@ -3,13 +3,15 @@ import { apiGet, apiPost, apiPut, apiDelete } from "@/lib/api";
 import type {
   HydrometerResponse,
   CreateHydrometer,
+  Page,
   UpdateHydrometer,
 } from "@/types";
 
 export function useHydrometers() {
-  return useQuery<HydrometerResponse[]>({
+  return useQuery({
     queryKey: ["hydrometers"],
-    queryFn: () => apiGet<HydrometerResponse[]>("/hydrometers"),
+    queryFn: () => apiGet<Page<HydrometerResponse>>("/hydrometers"),
+    select: (page) => page.items,
   });
 }
 
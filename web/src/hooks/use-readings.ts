@@ -1,20 +1,39 @@
 import { useQuery } from "@tanstack/react-query";
 import { apiGet } from "@/lib/api";
-import type { ReadingResponse, ReadingsQuery } from "@/types";
+import type { Page, ReadingBucket, ReadingResponse, ReadingsQuery } from "@/types";
 
-export function useReadings(params?: ReadingsQuery) {
+function readingsPath(params?: ReadingsQuery) {
   const searchParams = new URLSearchParams();
   if (params?.brewId) searchParams.set("brew_id", params.brewId);
   if (params?.hydrometerId) searchParams.set("hydrometer_id", params.hydrometerId);
   if (params?.since) searchParams.set("since", params.since);
   if (params?.until) searchParams.set("until", params.until);
   if (params?.limit) searchParams.set("limit", String(params.limit));
+  if (params?.bucket) searchParams.set("bucket", params.bucket);
+  if (params?.maxPoints) searchParams.set("max_points", String(params.maxPoints));
+  if (params?.cursor) searchParams.set("cursor", params.cursor);
+  if (params?.order) searchParams.set("order", params.order);
 
   const query = searchParams.toString();
-  const path = `/readings${query ? `?${query}` : ""}`;
+  return `/readings${query ? `?${query}` : ""}`;
+}
+
+export function useReadings(params?: Omit<ReadingsQuery, "bucket">) {
+  const path = readingsPath(params);
 
-  return useQuery<ReadingResponse[]>({
+  return useQuery({
     queryKey: ["readings", params],
-    queryFn: () => apiGet<ReadingResponse[]>(path),
+    queryFn: () => apiGet<Page<ReadingResponse>>(path),
+    select: (page) => page.items,
+  });
+}
+
+export function useReadingBuckets(params: ReadingsQuery & { bucket: string }) {
+  const path = readingsPath(params);
+
+  return useQuery({
+    queryKey: ["readings", "buckets", params],
+    queryFn: () => apiGet<Page<ReadingBucket>>(path),
+    select: (page) => page.items,
   });
 }
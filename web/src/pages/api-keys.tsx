@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
-import { Plus, Trash2, Copy, Check, Key } from "lucide-react";
+import { Plus, Trash2, Copy, Check, Key, RefreshCw } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import {
@@ -29,12 +29,23 @@ import { apiGet, apiPost, apiDelete } from "@/lib/api";
 import * as toast from "@/lib/toast";
 import { format } from "date-fns";
 
+type ApiKeyScope = "readings:write" | "readings:read" | "brews:read" | "admin";
+
 interface ApiKeySummary {
   id: string;
   name: string;
   prefix: string;
   createdBy: string;
+  teamId: string | null;
+  scopes: ApiKeyScope[];
+  allowedColors: string[] | null;
+  allowedIps: string[] | null;
   lastUsedAt: string | null;
+  requestCount: number;
+  lastIp: string | null;
+  lastUserAgent: string | null;
+  rotatedAt: string | null;
+  previousExpiresAt: string | null;
   expiresAt: string | null;
   createdAt: string;
 }
@@ -45,6 +56,7 @@ interface ApiKeyCreated {
   prefix: string;
   key: string;
   createdAt: string;
+  previousExpiresAt?: string;
   message: string;
 }
 
@@ -73,6 +85,15 @@ export default function ApiKeysPage() {
     onError: () => toast.error("Failed to create API key"),
   });
 
+  const rotateMutation = useMutation({
+    mutationFn: (id: string) => apiPost<ApiKeyCreated>(`/api-keys/${id}/rotate`, {}),
+    onSuccess: (data) => {
+      setCreatedKey(data);
+      queryClient.invalidateQueries({ queryKey: ["api-keys"] });
+    },
+    onError: () => toast.error("Failed to rotate API key"),
+  });
+
   const deleteMutation = useMutation({
     mutationFn: (id: string) => apiDelete(`/api-keys/${id}`),
     onSuccess: () => {
@@ -125,15 +146,31 @@ export default function ApiKeysPage() {
                     <Badge variant="outline" className="font-mono text-xs">
                       {key.prefix}••••••••
                     </Badge>
+                    {key.scopes.map((scope) => (
+                      <Badge key={scope} variant="secondary" className="text-xs">
+                        {scope}
+                      </Badge>
+                    ))}
+                  </div>
+                  <div className="flex items-center">
+                    <Button
+                      variant="ghost"
+                      size="icon"
+                      title="Rotate secret"
+                      disabled={rotateMutation.isPending}
+                      onClick={() => rotateMutation.mutate(key.id)}
+                    >
+                      <RefreshCw className="h-4 w-4" />
+                    </Button>
+                    <Button
+                      variant="ghost"
+                      size="icon"
+                      className="text-destructive hover:text-destructive"
+                      onClick={() => setDeleteId(key.id)}
+                    >
+                      <Trash2 className="h-4 w-4" />
+                    </Button>
                   </div>
-                  <Button
-                    variant="ghost"
-                    size="icon"
-                    className="text-destructive hover:text-destructive"
-                    onClick={() => setDeleteId(key.id)}
-                  >
-                    <Trash2 className="h-4 w-4" />
-                  </Button>
                 </div>
                 <CardDescription className="text-xs space-y-0.5">
                   <span>Created {format(new Date(key.createdAt), "MMM d, yyyy")}</span>
@@ -147,6 +184,23 @@ export default function ApiKeysPage() {
                       Expires {format(new Date(key.expiresAt), "MMM d, yyyy")}
                     </span>
                   )}
+                  {key.allowedColors && (
+                    <span className="ml-3">Colors: {key.allowedColors.join(", ")}</span>
+                  )}
+                  {key.allowedIps && (
+                    <span className="ml-3">From: {key.allowedIps.join(", ")}</span>
+                  )}
+                  <span className="block">
+                    {key.requestCount} request{key.requestCount === 1 ? "" : "s"}
+                    {key.lastIp && <> · last from {key.lastIp}</>}
+                    {key.lastUserAgent && <> ({key.lastUserAgent})</>}
+                  </span>
+                  {key.previousExpiresAt && (
+                    <span className="block">
+                      Previous secret works until{" "}
+                      {format(new Date(key.previousExpiresAt), "MMM d, yyyy HH:mm")}
+                    </span>
+                  )}
                 </CardDescription>
               </CardHeader>
             </Card>
@@ -196,7 +250,14 @@ export default function ApiKeysPage() {
         <DialogContent>
           <DialogHeader>
             <DialogTitle>API Key Created</DialogTitle>
-            <DialogDescription>{createdKey?.message}</DialogDescription>
+            <DialogDescription>
+              {createdKey?.message}
+              {createdKey?.previousExpiresAt &&
+                ` The previous key keeps working until ${format(
+                  new Date(createdKey.previousExpiresAt),
+                  "MMM d, yyyy HH:mm",
+                )}.`}
+            </DialogDescription>
           </DialogHeader>
           <div className="space-y-2">
             <Label>Your API Key</Label>
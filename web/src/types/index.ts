@@ -8,6 +8,8 @@ export type TiltColor =
   | "Yellow"
   | "Pink";
 
+export type TiltDeviceKind = "Standard" | "Pro";
+
 export type BrewStatus = "Active" | "Completed" | "Archived";
 
 export interface TiltReading {
@@ -16,10 +18,22 @@ export interface TiltReading {
   gravity: number;
   rssi: number | null;
   recordedAt: string;
+  deviceKind: TiltDeviceKind;
+  txPower: number | null;
+  /** Client-generated id; re-sending a reading with the same id is a no-op. */
+  id?: string | null;
 }
 
 export type CreateReadingsBatch = TiltReading[];
 
+export interface ReadingsBatchResult {
+  /** Same as `inserted`, kept for consumers of the original response. */
+  count: number;
+  inserted: number;
+  duplicates: number;
+  replaced: number;
+}
+
 export interface CreateBrew {
   name: string;
   hydrometerId: string;
@@ -56,7 +70,30 @@ export interface BrewResponse {
   hydrometerId: string;
   createdAt: string;
   updatedAt: string;
+  stableSince: string | null;
   latestReading: TiltReading | null;
+  owner: string;
+  teamId: string | null;
+}
+
+export interface GravityVelocity {
+  windowHours: number;
+  pointsPerDay: number | null;
+}
+
+export interface BrewAnalytics {
+  brewId: string;
+  og: number | null;
+  ogDetected: boolean;
+  currentGravity: number | null;
+  currentTemperatureF: number | null;
+  apparentAttenuation: number | null;
+  realAttenuation: number | null;
+  abv: number | null;
+  gravityVelocity: GravityVelocity[];
+  hoursSincePitch: number | null;
+  readingCount: number;
+  latestReadingAt: string | null;
 }
 
 export interface CreateHydrometer {
@@ -78,6 +115,9 @@ export interface HydrometerResponse {
   gravityOffset: number;
   createdAt: string;
   latestReading: TiltReading | null;
+  gatewayId: string | null;
+  owner: string;
+  teamId: string | null;
 }
 
 export interface ReadingResponse {
@@ -85,17 +125,324 @@ export interface ReadingResponse {
   brewId: string | null;
   hydrometerId: string;
   color: TiltColor;
+  deviceKind: TiltDeviceKind;
   temperatureF: number;
   gravity: number;
+  rawTemperatureF: number;
+  rawGravity: number;
   rssi: number | null;
+  txPower: number | null;
+  gatewayId: string | null;
   recordedAt: string;
   createdAt: string;
 }
 
+export interface SignalSample {
+  recordedAt: string;
+  rssi: number;
+  txPower: number | null;
+}
+
+export interface CreateCalibrationPoint {
+  rawGravity: number;
+  actualGravity: number;
+}
+
+export interface CalibrationPointResponse {
+  id: string;
+  hydrometerId: string;
+  rawGravity: number;
+  actualGravity: number;
+  createdAt: string;
+}
+
+export interface CalibrationResponse {
+  hydrometerId: string;
+  points: CalibrationPointResponse[];
+  coefficients: number[];
+}
+
 export interface ReadingsQuery {
   brewId?: string;
   hydrometerId?: string;
   since?: string;
   until?: string;
   limit?: number;
+  /** Aggregate into time buckets such as "5m", "1h" or "1d"; returns ReadingBucket[]. */
+  bucket?: string;
+  /** Shape-preserving downsample to at most this many readings per hydrometer. */
+  maxPoints?: number;
+  /** `nextCursor` from the previous page of raw readings. */
+  cursor?: string;
+  order?: SortOrder;
+}
+
+export type SortOrder = "asc" | "desc";
+
+export interface Page<T> {
+  items: T[];
+  /** Pass back as `cursor` for the next page; null on the last page. */
+  nextCursor: string | null;
+  /** Items matching the filters across all pages. */
+  total: number;
+}
+
+export type ExportFormat = "csv" | "jsonl" | "beerjson";
+
+/** One line of a `jsonl` brew export. */
+export type ExportRecord =
+  | ({ type: "brew" } & BrewResponse)
+  | ({ type: "reading" } & ReadingResponse);
+
+export interface ImportRowError {
+  line: number;
+  message: string;
+}
+
+export interface ImportReport {
+  rows: number;
+  imported: number;
+  duplicates: number;
+  brewsCreated: string[];
+  errors: ImportRowError[];
+}
+
+export interface ReadingBucket {
+  hydrometerId: string;
+  bucketStart: string;
+  count: number;
+  gravityMin: number;
+  gravityMax: number;
+  gravityAvg: number;
+  temperatureMinF: number;
+  temperatureMaxF: number;
+  temperatureAvgF: number;
+}
+
+export type AlertRuleKind =
+  | "TemperatureAbove"
+  | "TemperatureBelow"
+  | "GravityStalled"
+  | "NoReading"
+  | "GravityReachedTarget";
+
+export type AlertState = "Firing" | "Resolved";
+
+export interface CreateAlertRule {
+  name: string;
+  brewId?: string;
+  hydrometerId?: string;
+  kind: AlertRuleKind;
+  threshold?: number;
+  durationMinutes?: number;
+  enabled?: boolean;
+}
+
+export interface UpdateAlertRule {
+  name?: string;
+  threshold?: number;
+  durationMinutes?: number;
+  enabled?: boolean;
+}
+
+export interface AlertRuleResponse {
+  id: string;
+  name: string;
+  brewId: string | null;
+  hydrometerId: string | null;
+  kind: AlertRuleKind;
+  threshold: number | null;
+  durationMinutes: number | null;
+  enabled: boolean;
+  firing: boolean;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface AlertResponse {
+  id: string;
+  ruleId: string;
+  ruleName: string;
+  kind: AlertRuleKind;
+  brewId: string | null;
+  hydrometerId: string | null;
+  state: AlertState;
+  message: string;
+  value: number | null;
+  firedAt: string;
+  resolvedAt: string | null;
+}
+
+export type NotificationEvent =
+  | "AlertFiring"
+  | "AlertResolved"
+  | "BrewCompleted"
+  | "GatewayOffline"
+  | "GatewayOnline"
+  | "Test";
+
+export type NotificationChannelKind =
+  | "Webhook"
+  | "Ntfy"
+  | "Email"
+  | "Slack"
+  | "Discord";
+
+export type ChannelConfig =
+  | { kind: "Webhook"; url: string; secret: string | null }
+  | {
+      kind: "Ntfy";
+      url: string;
+      topic: string;
+      token: string | null;
+      priority: number | null;
+    }
+  | { kind: "Email"; to: string }
+  | { kind: "Slack"; url: string }
+  | { kind: "Discord"; url: string };
+
+export interface Notification {
+  event: NotificationEvent;
+  title: string;
+  message: string;
+  brewId: string | null;
+  hydrometerId: string | null;
+  alert: AlertResponse | null;
+  occurredAt: string;
+}
+
+export interface CreateNotificationChannel {
+  name: string;
+  config: ChannelConfig;
+  events?: NotificationEvent[];
+  enabled?: boolean;
+}
+
+export interface UpdateNotificationChannel {
+  name?: string;
+  config?: ChannelConfig;
+  events?: NotificationEvent[];
+  enabled?: boolean;
+}
+
+export interface NotificationChannelResponse {
+  id: string;
+  name: string;
+  kind: NotificationChannelKind;
+  config: ChannelConfig;
+  events: NotificationEvent[];
+  enabled: boolean;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export type DeliveryStatus = "Pending" | "Delivered" | "Failed";
+
+export interface NotificationDeliveryResponse {
+  id: string;
+  channelId: string;
+  event: NotificationEvent;
+  title: string;
+  status: DeliveryStatus;
+  attempts: number;
+  lastError: string | null;
+  nextAttemptAt: string | null;
+  createdAt: string;
+  deliveredAt: string | null;
+}
+
+export interface ReadingsEvent {
+  hydrometerId: string;
+  brewId: string | null;
+  readings: ReadingResponse[];
+}
+
+/** SSE event names on `GET /api/v1/readings/stream` and their payloads. */
+export interface StreamEventMap {
+  readings: ReadingsEvent;
+  alert: AlertResponse;
+  brew: BrewResponse;
+}
+
+export interface ScanStats {
+  finishedAt: string;
+  durationMs: number;
+  readings: number;
+  colors: TiltColor[];
+}
+
+export interface GatewayHeartbeat {
+  version: string;
+  hostname: string | null;
+  adapter: string | null;
+  bufferedReadings?: number;
+  lastScan: ScanStats | null;
+}
+
+export type GatewayStatus = "Online" | "Offline";
+
+export interface GatewayResponse {
+  id: string;
+  apiKeyId: string;
+  name: string;
+  apiKeyPrefix: string;
+  version: string;
+  hostname: string | null;
+  adapter: string | null;
+  bufferedReadings: number;
+  lastScan: ScanStats | null;
+  lastIp: string | null;
+  status: GatewayStatus;
+  firstSeenAt: string;
+  lastSeenAt: string;
+}
+
+export type TeamRole = "Viewer" | "Brewer" | "Owner";
+
+export interface CreateTeam {
+  name: string;
+}
+
+export interface TeamResponse {
+  id: string;
+  name: string;
+  role: TeamRole;
+  createdAt: string;
+}
+
+export interface TeamMemberResponse {
+  userSub: string;
+  email: string;
+  name: string;
+  role: TeamRole;
+  joinedAt: string;
+}
+
+export interface UpdateTeamMember {
+  role: TeamRole;
+}
+
+export interface CreateInvitation {
+  role: TeamRole;
+}
+
+export interface InvitationResponse {
+  id: string;
+  teamId: string;
+  role: TeamRole;
+  token: string;
+  link: string;
+  expiresAt: string;
+}
+
+export interface AuditEvent {
+  id: string;
+  occurredAt: string;
+  actor: string;
+  apiKeyId: string | null;
+  action: string;
+  targetType: string;
+  targetId: string;
+  changes: Record<string, { before: unknown; after: unknown }>;
+  ip: string | null;
 }